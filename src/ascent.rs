@@ -0,0 +1,246 @@
+use crate::{
+    body::Body,
+    engine,
+    game_data::GameData,
+    sas::{Sas, SasMode},
+    sin_cos, staging,
+    vector3::Vector3,
+    vessel::Vessel,
+    Number,
+};
+
+/// Where the ascent autopilot should put the vessel and how it should get there
+#[derive(Debug, Clone, Copy)]
+pub struct AscentSettings {
+    /// Meters above sea level
+    pub apoapsis: Number,
+    /// Radians against the body's equator
+    pub inclination: Number,
+    /// Altitude the vessel starts pitching over at
+    pub turn_start: Number,
+    /// Altitude the vessel is flying horizontally by
+    pub turn_end: Number,
+    /// Meters per second squared the engines get throttled back to
+    pub max_acceleration: Number,
+}
+
+impl AscentSettings {
+    /// Turns over between a kilometer up and most of the way to the edge of the atmosphere, or
+    /// most of the way to `apoapsis` on bodies without one
+    pub fn new(apoapsis: Number, inclination: Number, body: &Body) -> Self {
+        let turn_end = body
+            .atmosphere
+            .map_or(apoapsis, |atmosphere| atmosphere.height.min(apoapsis))
+            * Number::from_num(0.8);
+        Self {
+            apoapsis,
+            inclination,
+            turn_start: Number::from_num(1000).min(turn_end / 2),
+            turn_end,
+            max_acceleration: Number::from_num(3.0 * engine::STANDARD_GRAVITY),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AscentPhase {
+    /// Burning up through the gravity turn until the apoapsis is high enough
+    Ascent,
+    /// Waiting for apoapsis, topping the apoapsis up again if drag pulls it down
+    Coast,
+    /// Burning horizontally at apoapsis until the orbit is round
+    Circularize,
+    Done,
+}
+
+/// Flies a vessel from the ground into a circular orbit
+#[derive(Debug, Clone, Copy)]
+pub struct AscentAutopilot {
+    pub settings: AscentSettings,
+    phase: AscentPhase,
+    sas: Sas,
+    /// Meters per second left to burn last tick while circularizing
+    remaining_delta_v: Option<Number>,
+}
+
+impl AscentAutopilot {
+    pub fn new(settings: AscentSettings) -> Self {
+        Self {
+            settings,
+            phase: AscentPhase::Ascent,
+            sas: Sas::new(SasMode::Direction(Vector3::ZERO)),
+            remaining_delta_v: None,
+        }
+    }
+
+    pub fn phase(&self) -> AscentPhase {
+        self.phase
+    }
+
+    /// Steers, throttles and stages `vessel` for the next `dt` seconds, returning the vessels
+    /// staging dropped
+    pub fn update(
+        &mut self,
+        vessel: &mut Vessel,
+        data: &GameData,
+        body: &Body,
+        dt: Number,
+    ) -> Vec<Vessel> {
+        let mut separated = Vec::new();
+        if self.phase != AscentPhase::Done
+            && staging::remaining_stages(vessel) > 0
+            && staging::burnt_out(vessel, data)
+        {
+            separated = staging::activate_next_stage(vessel, data);
+        }
+
+        let position = vessel.transform.position() - body.position;
+        let radius = position.length();
+        let altitude = radius - body.radius;
+        let up = position / radius;
+        let velocity = vessel.velocity - body.velocity;
+        let orbit = body.orbit(vessel);
+        let apoapsis = orbit
+            .apoapsis()
+            .map_or(Number::MAX, |apoapsis| apoapsis - body.radius);
+
+        let environment = body.environment(vessel.transform.position());
+        let mass = vessel.mass(data);
+        let thrust = engine::available_thrust(vessel, data, environment.pressure);
+        // Throttle that gives an acceleration, capped by the settings
+        let throttle_for = |acceleration: Number| {
+            if thrust > Number::ZERO {
+                (acceleration.min(self.settings.max_acceleration) * mass / thrust)
+                    .clamp(Number::ZERO, Number::ONE)
+            } else {
+                Number::ZERO
+            }
+        };
+        // Easing off over the last bit of apoapsis keeps from overshooting it
+        let apoapsis_margin = self.settings.apoapsis / 50;
+        let apoapsis_throttle = || {
+            throttle_for(self.settings.max_acceleration)
+                .min((self.settings.apoapsis - apoapsis) / apoapsis_margin + Number::from_num(0.05))
+        };
+        let in_atmosphere = body
+            .atmosphere
+            .is_some_and(|atmosphere| altitude < atmosphere.height);
+
+        let (direction, throttle) = match self.phase {
+            AscentPhase::Ascent => {
+                if apoapsis >= self.settings.apoapsis {
+                    self.phase = AscentPhase::Coast;
+                }
+                (self.turn_direction(body, up, altitude), apoapsis_throttle())
+            }
+            AscentPhase::Coast => {
+                let throttle = if in_atmosphere && apoapsis < self.settings.apoapsis {
+                    apoapsis_throttle()
+                } else {
+                    Number::ZERO
+                };
+                let direction = if throttle > Number::ZERO {
+                    self.turn_direction(body, up, altitude)
+                } else {
+                    velocity
+                };
+
+                // Start circularizing once half of the burn would fit before apoapsis
+                let apoapsis_radius = apoapsis + body.radius;
+                let circular_speed = (body.mu / apoapsis_radius).sqrt();
+                let apoapsis_speed = (body.mu
+                    * (Number::from_num(2) / apoapsis_radius
+                        - Number::ONE / orbit.semi_major_axis))
+                    .max(Number::ZERO)
+                    .sqrt();
+                let burn_time = if thrust > Number::ZERO {
+                    (circular_speed - apoapsis_speed) * mass / thrust
+                } else {
+                    Number::ZERO
+                };
+                let falling = velocity.dot(up) < Number::ZERO;
+                if !in_atmosphere
+                    && (falling
+                        || orbit
+                            .time_to_apoapsis()
+                            .is_some_and(|time| time <= burn_time / 2))
+                {
+                    self.phase = AscentPhase::Circularize;
+                }
+                (direction, throttle)
+            }
+            AscentPhase::Circularize => {
+                let horizontal = velocity - up * velocity.dot(up);
+                // Going straight up or down leaves no way around to keep, so go around along
+                // the launch heading instead
+                let around = if horizontal.length() > Number::ZERO {
+                    horizontal / horizontal.length()
+                } else {
+                    self.turn_direction(body, up, self.settings.turn_end)
+                };
+                let circular_velocity = around * (body.mu / radius).sqrt();
+                let remaining = circular_velocity - velocity;
+                let remaining_delta_v = remaining.length();
+
+                // Stop once there is nothing left to burn, or when burning stops helping
+                let finished = remaining_delta_v < Number::from_num(0.5)
+                    || self.remaining_delta_v.is_some_and(|previous| {
+                        remaining_delta_v > previous && remaining_delta_v < Number::from_num(5)
+                    });
+                self.remaining_delta_v = Some(remaining_delta_v);
+                if finished {
+                    self.phase = AscentPhase::Done;
+                    (velocity, Number::ZERO)
+                } else {
+                    // Only burn when roughly pointing the right way, finishing over about a second
+                    let forward = vessel.transform.transform_direction(Vector3::Y);
+                    let aligned =
+                        forward.dot(remaining) > remaining_delta_v * Number::from_num(0.95);
+                    let throttle = if aligned {
+                        throttle_for(remaining_delta_v)
+                    } else {
+                        Number::ZERO
+                    };
+                    (remaining, throttle)
+                }
+            }
+            AscentPhase::Done => (velocity, Number::ZERO),
+        };
+
+        vessel.throttle = throttle;
+        self.sas.mode = SasMode::Direction(direction);
+        self.sas.update(vessel, &body.reference(), dt);
+        separated
+    }
+
+    /// Where to point during the gravity turn, tipping over from straight up to horizontal
+    /// along the launch heading
+    fn turn_direction(&self, body: &Body, up: Vector3, altitude: Number) -> Vector3 {
+        let progress = ((altitude - self.settings.turn_start)
+            / (self.settings.turn_end - self.settings.turn_start))
+            .clamp(Number::ZERO, Number::ONE);
+        let (sin_pitch, cos_pitch) = sin_cos(progress.sqrt() * Number::FRAC_PI_2);
+
+        // Launching north east at the right angle ends up at the wanted inclination, or as
+        // close as the launch latitude allows
+        let pole = body.spin_axis();
+        let east = pole.cross(up);
+        let east = if east.sqr_length() > Number::ZERO {
+            east / east.length()
+        } else {
+            Vector3::Y
+        };
+        let north = up.cross(east);
+        let (_, cos_inclination) = sin_cos(self.settings.inclination);
+        let cos_latitude = north.dot(pole).abs();
+        let sin_heading = if cos_latitude > Number::ZERO {
+            (cos_inclination / cos_latitude).clamp(-Number::ONE, Number::ONE)
+        } else {
+            Number::ZERO
+        };
+        let cos_heading = (Number::ONE - sin_heading * sin_heading).sqrt();
+        let heading = north * cos_heading + east * sin_heading;
+
+        up * cos_pitch + heading * sin_pitch
+    }
+}
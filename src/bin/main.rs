@@ -1,9 +1,10 @@
-use space::game::Game;
-use std::sync::Arc;
+use space::{control::ControlInput, game::Game, vector3::Vector3, Number};
+use std::{collections::HashSet, sync::Arc};
 use winit::{
     dpi::PhysicalSize,
-    event::{Event, WindowEvent},
+    event::{ElementState, Event, KeyEvent, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
+    keyboard::{Key, KeyCode, NamedKey, PhysicalKey},
     window::WindowBuilder,
 };
 
@@ -22,6 +23,7 @@ fn main() {
     event_loop.set_control_flow(ControlFlow::Poll);
     let start_time = std::time::Instant::now();
     let mut last_time = start_time;
+    let mut held_keys = HashSet::new();
     window.set_visible(true);
     event_loop
         .run(move |event, elwt| match event {
@@ -34,6 +36,51 @@ fn main() {
                 }
                 WindowEvent::Resized(PhysicalSize { width, height }) => game.resize(width, height),
                 WindowEvent::RedrawRequested => game.render(),
+                WindowEvent::KeyboardInput {
+                    event:
+                        KeyEvent {
+                            logical_key: Key::Named(NamedKey::Space),
+                            state: ElementState::Pressed,
+                            repeat: false,
+                            ..
+                        },
+                    ..
+                } => game.activate_next_stage(),
+                WindowEvent::KeyboardInput {
+                    event:
+                        KeyEvent {
+                            physical_key: PhysicalKey::Code(KeyCode::KeyT),
+                            state: ElementState::Pressed,
+                            repeat: false,
+                            ..
+                        },
+                    ..
+                } => game.toggle_sas(),
+                WindowEvent::KeyboardInput {
+                    event:
+                        KeyEvent {
+                            physical_key: PhysicalKey::Code(KeyCode::KeyU),
+                            state: ElementState::Pressed,
+                            repeat: false,
+                            ..
+                        },
+                    ..
+                } => game.toggle_solar_panels(),
+                WindowEvent::KeyboardInput {
+                    event:
+                        KeyEvent {
+                            physical_key: PhysicalKey::Code(code),
+                            state,
+                            ..
+                        },
+                    ..
+                } => {
+                    match state {
+                        ElementState::Pressed => held_keys.insert(code),
+                        ElementState::Released => held_keys.remove(&code),
+                    };
+                    game.set_control(control_input(&held_keys));
+                }
                 _ => {}
             },
             Event::AboutToWait => {
@@ -48,3 +95,21 @@ fn main() {
         })
         .unwrap();
 }
+
+/// WASD and QE rotate, IJKL and HN translate
+fn control_input(held_keys: &HashSet<KeyCode>) -> ControlInput {
+    let axis = |positive, negative| {
+        let held = |key| Number::from_num(u8::from(held_keys.contains(&key)));
+        held(positive) - held(negative)
+    };
+    ControlInput {
+        pitch: axis(KeyCode::KeyW, KeyCode::KeyS),
+        yaw: axis(KeyCode::KeyA, KeyCode::KeyD),
+        roll: axis(KeyCode::KeyQ, KeyCode::KeyE),
+        translate: Vector3::new(
+            axis(KeyCode::KeyL, KeyCode::KeyJ),
+            axis(KeyCode::KeyH, KeyCode::KeyN),
+            axis(KeyCode::KeyI, KeyCode::KeyK),
+        ),
+    }
+}
@@ -0,0 +1,97 @@
+use crate::Number;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Regions of a body's surface, painted as flat colors onto an image
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct BiomeMapConfig {
+    /// Covers the whole body, longitude -180° to 180° from left to right and latitude 90° to
+    /// -90° from top to bottom
+    pub image: String,
+    /// Which biome each color in the image stands for
+    pub biomes: Vec<BiomeConfig>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct BiomeConfig {
+    pub name: String,
+    /// Red, green and blue
+    pub color: [u8; 3],
+}
+
+/// The biome of every pixel of an equirectangular image
+#[derive(Debug)]
+pub struct BiomeMap {
+    width: usize,
+    height: usize,
+    names: Vec<String>,
+    /// Index into `names`, row by row from the north pole
+    biomes: Vec<usize>,
+}
+
+impl BiomeMap {
+    /// Paths are relative to `directory`
+    pub fn load(config: &BiomeMapConfig, directory: &Path) -> Self {
+        let path = directory.join(&config.image);
+        let image = image::open(&path)
+            .unwrap_or_else(|error| panic!("{}: {error}", path.display()))
+            .to_rgb8();
+        Self::new(
+            image.width() as usize,
+            image.height() as usize,
+            image.pixels().map(|pixel| pixel.0).collect(),
+            config
+                .biomes
+                .iter()
+                .map(|biome| (biome.name.clone(), biome.color))
+                .collect(),
+        )
+    }
+
+    /// Each pixel goes to the biome with the closest color, so blurred or compressed edges
+    /// between regions still belong to one of them
+    pub fn new(
+        width: usize,
+        height: usize,
+        pixels: Vec<[u8; 3]>,
+        biomes: Vec<(String, [u8; 3])>,
+    ) -> Self {
+        assert_eq!(pixels.len(), width * height);
+        assert!(width > 0 && height > 0);
+        assert!(!biomes.is_empty(), "a biome map needs at least one biome");
+        let distance = |a: [u8; 3], b: [u8; 3]| {
+            (0..3)
+                .map(|channel| (a[channel] as i32 - b[channel] as i32).pow(2))
+                .sum::<i32>()
+        };
+        let indices = pixels
+            .into_iter()
+            .map(|pixel| {
+                (0..biomes.len())
+                    .min_by_key(|&index| distance(pixel, biomes[index].1))
+                    .unwrap()
+            })
+            .collect();
+        Self {
+            width,
+            height,
+            names: biomes.into_iter().map(|(name, _)| name).collect(),
+            biomes: indices,
+        }
+    }
+
+    /// Name of the biome at a latitude and longitude in radians, from the pixel they fall on
+    pub fn biome_at(&self, latitude: Number, longitude: Number) -> &str {
+        let x = ((longitude + Number::PI).rem_euclid(Number::TAU) / Number::TAU
+            * Number::from_num(self.width))
+        .to_num::<usize>()
+        .min(self.width - 1);
+        let y = ((Number::FRAC_PI_2 - latitude) / Number::PI * Number::from_num(self.height))
+            .max(Number::ZERO)
+            .to_num::<usize>()
+            .min(self.height - 1);
+        &self.names[self.biomes[y * self.width + x]]
+    }
+}
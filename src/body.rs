@@ -0,0 +1,565 @@
+use crate::{
+    biome::{BiomeMap, BiomeMapConfig},
+    contact::Ground,
+    exp,
+    geodetic::{Ellipsoid, Geodetic},
+    motor::Transform,
+    orbit::Orbit,
+    physics::Environment,
+    registry::{Id, Registry},
+    sas::Reference,
+    situation::SituationAltitudes,
+    terrain::{Terrain, TerrainConfig},
+    vector3::Vector3,
+    vessel::Vessel,
+    Number,
+};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub type BodyId = Id<BodyDefinition>;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct BodyConfig {
+    pub name: String,
+    pub title: String,
+    /// Name of the body this one orbits, `None` for the one everything else orbits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    /// Meters at sea level on the equator
+    pub radius: f64,
+    /// How much shorter the polar radius is, as a fraction of `radius`
+    #[serde(default)]
+    pub flattening: f64,
+    /// Gravitational parameter, cubic meters per second squared
+    pub mu: f64,
+    /// Watts of light given off, only for the star at the root of the hierarchy
+    #[serde(default)]
+    pub luminosity: f64,
+    /// Seconds per turn around the north pole, `None` for bodies that don't turn
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotation_period: Option<f64>,
+    /// Degrees the north pole leans over from +Z, turning around +X
+    #[serde(default)]
+    pub axial_tilt: f64,
+    /// Around the parent, required for every body that has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orbit: Option<OrbitConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub atmosphere: Option<AtmosphereConfig>,
+    /// Ground above and below sea level, a perfectly smooth sea level when `None`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub terrain: Option<TerrainConfig>,
+    /// Whether the ground below sea level is under water
+    #[serde(default)]
+    pub ocean: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub biome_map: Option<BiomeMapConfig>,
+    /// Meters above sea level where flying low turns into flying high
+    #[serde(default)]
+    pub flying_high_altitude: f64,
+    /// Meters above sea level where space low turns into space high
+    #[serde(default)]
+    pub space_high_altitude: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub texture_color_map: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+/// Keplerian elements at the epoch, angles in degrees against the parent's equator and +X axis
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct OrbitConfig {
+    /// Meters
+    pub semi_major_axis: f64,
+    #[serde(default)]
+    pub eccentricity: f64,
+    #[serde(default)]
+    pub inclination: f64,
+    #[serde(default)]
+    pub longitude_of_ascending_node: f64,
+    #[serde(default)]
+    pub argument_of_periapsis: f64,
+    #[serde(default)]
+    pub mean_anomaly: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct AtmosphereConfig {
+    /// Atmospheres at sea level
+    pub pressure: f64,
+    /// Kilograms per cubic meter at sea level
+    pub density: f64,
+    /// Meters over which pressure and density fall by a factor of e
+    pub scale_height: f64,
+    /// Meters above sea level where the atmosphere ends
+    pub height: f64,
+    /// Kelvin of the air, the same all the way up
+    #[serde(default = "default_air_temperature")]
+    pub temperature: f64,
+}
+
+fn default_air_temperature() -> f64 {
+    288.0
+}
+
+/// Air that thins out exponentially with altitude
+#[derive(Debug, Clone, Copy)]
+pub struct Atmosphere {
+    /// Atmospheres at sea level
+    pub pressure: Number,
+    /// Kilograms per cubic meter at sea level
+    pub density: Number,
+    /// Meters over which pressure and density fall by a factor of e
+    pub scale_height: Number,
+    /// Meters above sea level where the atmosphere ends
+    pub height: Number,
+    /// Kelvin
+    pub temperature: Number,
+}
+
+impl Atmosphere {
+    /// Fraction of the sea level pressure and density left at `altitude`
+    fn falloff(&self, altitude: Number) -> Number {
+        if altitude >= self.height {
+            Number::ZERO
+        } else {
+            exp(-altitude.max(Number::ZERO) / self.scale_height)
+        }
+    }
+
+    /// Atmospheres at `altitude` meters above sea level
+    pub fn pressure(&self, altitude: Number) -> Number {
+        self.pressure * self.falloff(altitude)
+    }
+
+    /// Kilograms per cubic meter at `altitude` meters above sea level
+    pub fn density(&self, altitude: Number) -> Number {
+        self.density * self.falloff(altitude)
+    }
+}
+
+/// Everything about a planet, moon or star that doesn't change as it moves
+pub struct BodyDefinition {
+    pub config: BodyConfig,
+    /// Always loaded before this body
+    pub parent: Option<BodyId>,
+    /// Meters at sea level on the equator
+    pub radius: Number,
+    /// How much shorter the polar radius is, as a fraction of `radius`
+    pub flattening: Number,
+    /// Gravitational parameter, cubic meters per second squared
+    pub mu: Number,
+    /// Watts of light given off
+    pub luminosity: Number,
+    /// Seconds per turn, `None` for bodies that don't turn
+    pub rotation_period: Option<Number>,
+    /// Radians
+    pub axial_tilt: Number,
+    /// Around the parent at the epoch
+    pub orbit: Option<Orbit>,
+    pub atmosphere: Option<Atmosphere>,
+    pub terrain: Option<Arc<Terrain>>,
+    /// Whether the ground below sea level is under water
+    pub ocean: bool,
+    pub biomes: Option<Arc<BiomeMap>>,
+    pub situation_altitudes: SituationAltitudes,
+    pub texture_color_map: Option<PathBuf>,
+    pub model: Option<PathBuf>,
+}
+
+impl BodyDefinition {
+    /// `parent` is the body named by the config, with paths relative to `directory`
+    pub fn new(
+        config: BodyConfig,
+        parent: Option<(BodyId, &BodyDefinition)>,
+        directory: &Path,
+    ) -> Self {
+        let orbit = match (&config.orbit, parent) {
+            (Some(orbit), Some((_, parent))) => {
+                let angle = |degrees: f64| Number::from_num(degrees.to_radians());
+                let orbit = Orbit {
+                    mu: parent.mu,
+                    semi_major_axis: Number::from_num(orbit.semi_major_axis),
+                    eccentricity: Number::from_num(orbit.eccentricity),
+                    inclination: angle(orbit.inclination),
+                    longitude_of_ascending_node: angle(orbit.longitude_of_ascending_node),
+                    argument_of_periapsis: angle(orbit.argument_of_periapsis),
+                    true_anomaly: Number::ZERO,
+                }
+                .at_mean_anomaly(angle(orbit.mean_anomaly));
+                Some(orbit)
+            }
+            (None, Some(_)) => panic!("{} has a parent but no orbit around it", config.name),
+            (_, None) => None,
+        };
+        let atmosphere = config.atmosphere.as_ref().map(|atmosphere| Atmosphere {
+            pressure: Number::from_num(atmosphere.pressure),
+            density: Number::from_num(atmosphere.density),
+            scale_height: Number::from_num(atmosphere.scale_height),
+            height: Number::from_num(atmosphere.height),
+            temperature: Number::from_num(atmosphere.temperature),
+        });
+        let radius = Number::from_num(config.radius);
+        let terrain = config
+            .terrain
+            .as_ref()
+            .map(|terrain| Arc::new(Terrain::new(terrain, radius, directory)));
+        let biomes = config
+            .biome_map
+            .as_ref()
+            .map(|biome_map| Arc::new(BiomeMap::load(biome_map, directory)));
+        Self {
+            parent: parent.map(|(id, _)| id),
+            radius,
+            flattening: Number::from_num(config.flattening),
+            mu: Number::from_num(config.mu),
+            luminosity: Number::from_num(config.luminosity),
+            rotation_period: config.rotation_period.map(Number::from_num),
+            axial_tilt: Number::from_num(config.axial_tilt.to_radians()),
+            orbit,
+            atmosphere,
+            terrain,
+            ocean: config.ocean,
+            biomes,
+            situation_altitudes: SituationAltitudes {
+                flying_high: Number::from_num(config.flying_high_altitude),
+                space_high: Number::from_num(config.space_high_altitude),
+            },
+            texture_color_map: config
+                .texture_color_map
+                .as_ref()
+                .map(|path| directory.join(path)),
+            model: config.model.as_ref().map(|path| directory.join(path)),
+            config,
+        }
+    }
+
+    /// The body at a world space position and velocity, `time` seconds after the epoch
+    pub fn body(&self, position: Vector3, velocity: Vector3, time: Number) -> Body {
+        let (rotation, angular_velocity) = self.rotation_at(time);
+        Body {
+            position,
+            velocity,
+            rotation,
+            angular_velocity,
+            radius: self.radius,
+            flattening: self.flattening,
+            mu: self.mu,
+            atmosphere: self.atmosphere,
+            terrain: self.terrain.clone(),
+            ocean: self.ocean,
+            biomes: self.biomes.clone(),
+            situation_altitudes: self.situation_altitudes,
+        }
+    }
+
+    /// Orientation of the body-fixed frame `time` seconds after the epoch, and how fast it's
+    /// turning in world space
+    pub fn rotation_at(&self, time: Number) -> (Transform, Vector3) {
+        let tilt = Transform::rotation(Vector3::X, self.axial_tilt);
+        let Some(period) = self.rotation_period else {
+            return (tilt, Vector3::ZERO);
+        };
+        // Going through the fraction of a turn keeps the angle precise over long time warps
+        let angle = Number::TAU * (time.rem_euclid(period) / period);
+        (
+            Transform::rotation(Vector3::Z, angle).apply(tilt),
+            tilt.transform_direction(Vector3::Z) * (Number::TAU / period),
+        )
+    }
+}
+
+/// Every body `time` seconds after the epoch, moving on rails around its parent, indexed like
+/// `bodies`
+///
+/// The body without a parent sits still at the origin.
+pub fn propagate(bodies: &Registry<BodyDefinition>, time: Number) -> Vec<Body> {
+    let mut states = Vec::<Body>::with_capacity(bodies.len());
+    for (_, definition) in bodies.iter() {
+        let (position, velocity) = match (definition.parent, definition.orbit) {
+            (Some(parent), Some(orbit)) => {
+                let parent = &states[parent.index()];
+                let (position, velocity) = orbit.after(time).state();
+                (parent.position + position, parent.velocity + velocity)
+            }
+            _ => (Vector3::ZERO, Vector3::ZERO),
+        };
+        states.push(definition.body(position, velocity, time));
+    }
+    states
+}
+
+/// Shifts `bodies` from [`propagate`] so that `origin` sits still at the world origin, which is the
+/// frame vessels near it are simulated in
+///
+/// The frame moves along with the body without turning with it. Everything in it falls around
+/// the body's parents together, so vessels only feel the body's own gravity. Transforms also keep
+/// their positions to about a millimeter near the origin, where out by the planets they'd lose
+/// meters.
+pub fn center_on(bodies: &mut [Body], origin: BodyId) {
+    let (position, velocity) = (
+        bodies[origin.index()].position,
+        bodies[origin.index()].velocity,
+    );
+    for body in bodies {
+        body.position -= position;
+        body.velocity -= velocity;
+    }
+}
+
+/// A spherical planet or moon
+///
+/// Its body-fixed frame is centered on it and turns with it, with the north pole along +Z and
+/// longitude measured from +X.
+#[derive(Debug, Clone)]
+pub struct Body {
+    /// Of the center, in world space
+    pub position: Vector3,
+    /// In world space
+    pub velocity: Vector3,
+    /// From the axes of the body-fixed frame to world space ones
+    pub rotation: Transform,
+    /// Radians per second around the north pole, in world space
+    pub angular_velocity: Vector3,
+    /// Meters at sea level on the equator
+    pub radius: Number,
+    /// How much shorter the polar radius is, as a fraction of `radius`
+    pub flattening: Number,
+    /// Gravitational parameter, cubic meters per second squared
+    pub mu: Number,
+    pub atmosphere: Option<Atmosphere>,
+    /// Shared with the body's definition, a smooth sea level when `None`
+    pub terrain: Option<Arc<Terrain>>,
+    /// Whether the ground below sea level is under water
+    pub ocean: bool,
+    /// Shared with the body's definition
+    pub biomes: Option<Arc<BiomeMap>>,
+    pub situation_altitudes: SituationAltitudes,
+}
+
+impl Body {
+    /// A smooth, airless sphere sitting still at the origin, for building bodies by hand with
+    /// struct update syntax
+    pub fn new(radius: Number, mu: Number) -> Self {
+        Self {
+            position: Vector3::ZERO,
+            velocity: Vector3::ZERO,
+            rotation: Transform::IDENTITY,
+            angular_velocity: Vector3::ZERO,
+            radius,
+            flattening: Number::ZERO,
+            mu,
+            atmosphere: None,
+            terrain: None,
+            ocean: false,
+            biomes: None,
+            situation_altitudes: SituationAltitudes::default(),
+        }
+    }
+
+    /// Shape of the sea level in the body-fixed frame
+    pub fn ellipsoid(&self) -> Ellipsoid {
+        Ellipsoid {
+            equatorial_radius: self.radius,
+            flattening: self.flattening,
+        }
+    }
+
+    /// Meters above sea level of a world space position
+    pub fn altitude(&self, position: Vector3) -> Number {
+        self.to_geodetic(position).altitude
+    }
+
+    /// World space position `altitude` meters above sea level, with latitude and longitude in
+    /// radians and longitude measured from +X
+    pub fn surface_position(
+        &self,
+        latitude: Number,
+        longitude: Number,
+        altitude: Number,
+    ) -> Vector3 {
+        self.from_geodetic(Geodetic::new(latitude, longitude, altitude))
+    }
+
+    /// Latitude, longitude and altitude of a world space position
+    pub fn to_geodetic(&self, position: Vector3) -> Geodetic {
+        self.ellipsoid()
+            .to_geodetic(self.to_body_fixed_position(position))
+    }
+
+    /// World space position of a latitude, longitude and altitude
+    pub fn from_geodetic(&self, geodetic: Geodetic) -> Vector3 {
+        self.to_world_position(self.ellipsoid().to_cartesian(geodetic))
+    }
+
+    /// Meters above sea level of the ground at a latitude and longitude
+    pub fn terrain_height(&self, latitude: Number, longitude: Number) -> Number {
+        self.terrain.as_ref().map_or(Number::ZERO, |terrain| {
+            terrain.height_at(latitude, longitude)
+        })
+    }
+
+    /// Name of the biome at a latitude and longitude, `None` for bodies without a biome map
+    pub fn biome_at(&self, latitude: Number, longitude: Number) -> Option<&str> {
+        self.biomes
+            .as_ref()
+            .map(|biomes| biomes.biome_at(latitude, longitude))
+    }
+
+    /// Body-fixed normal of the ground at a latitude and longitude
+    pub fn surface_normal(&self, latitude: Number, longitude: Number) -> Vector3 {
+        match &self.terrain {
+            Some(terrain) => terrain.normal_at(&self.ellipsoid(), latitude, longitude),
+            None => self.ellipsoid().up(latitude, longitude),
+        }
+    }
+
+    /// World space normal of the ground at a latitude and longitude
+    pub fn ground_normal(&self, latitude: Number, longitude: Number) -> Vector3 {
+        self.rotation
+            .transform_direction(self.surface_normal(latitude, longitude))
+    }
+
+    /// Meters a world space position is above the ground right below it, negative when it's
+    /// underground
+    pub fn height_above_ground(&self, position: Vector3) -> Number {
+        let geodetic = self.to_geodetic(position);
+        geodetic.altitude - self.terrain_height(geodetic.latitude, geodetic.longitude)
+    }
+
+    /// From the local north-east-up frame at a point to world space, see
+    /// [`Ellipsoid::north_east_up`]
+    pub fn north_east_up(&self, geodetic: Geodetic) -> Transform {
+        self.to_world_transform(self.ellipsoid().north_east_up(geodetic))
+    }
+
+    /// From the body-fixed frame to world space
+    pub fn frame(&self) -> Transform {
+        self.rotation.apply(Transform::translation(self.position))
+    }
+
+    /// World space velocity of the ground, or anything else turning along with the body, at a
+    /// world space position
+    pub fn rotation_velocity(&self, position: Vector3) -> Vector3 {
+        self.velocity + self.angular_velocity.cross(position - self.position)
+    }
+
+    /// Velocity relative to the turning surface, still along world space axes, which is what a
+    /// surface speed readout shows
+    pub fn surface_velocity(&self, position: Vector3, velocity: Vector3) -> Vector3 {
+        velocity - self.rotation_velocity(position)
+    }
+
+    pub fn to_body_fixed_position(&self, position: Vector3) -> Vector3 {
+        // Only turning the offset from the center keeps far away bodies precise
+        self.rotation
+            .inverse()
+            .transform_direction(position - self.position)
+    }
+
+    pub fn to_world_position(&self, position: Vector3) -> Vector3 {
+        self.position + self.rotation.transform_direction(position)
+    }
+
+    /// Velocity in the body-fixed frame of something moving at `velocity` through the world
+    /// space `position`
+    pub fn to_body_fixed_velocity(&self, position: Vector3, velocity: Vector3) -> Vector3 {
+        self.rotation
+            .inverse()
+            .transform_direction(self.surface_velocity(position, velocity))
+    }
+
+    /// World space velocity of something moving at `velocity` through the body-fixed `position`
+    pub fn to_world_velocity(&self, position: Vector3, velocity: Vector3) -> Vector3 {
+        self.rotation.transform_direction(velocity)
+            + self.rotation_velocity(self.to_world_position(position))
+    }
+
+    /// A transform into world space turned into one into the body-fixed frame
+    pub fn to_body_fixed_transform(&self, transform: Transform) -> Transform {
+        transform.apply(self.frame().inverse())
+    }
+
+    /// A transform into the body-fixed frame turned into one into world space
+    pub fn to_world_transform(&self, transform: Transform) -> Transform {
+        transform.apply(self.frame())
+    }
+
+    /// Acceleration towards the center at a world space position
+    pub fn gravity(&self, position: Vector3) -> Vector3 {
+        let offset = self.position - position;
+        let distance = offset.length();
+        // Dividing one step at a time keeps the cube of the distance from overflowing
+        offset / distance * (self.mu / distance / distance)
+    }
+
+    /// Meters per second squared at sea level
+    pub fn surface_gravity(&self) -> Number {
+        self.mu / self.radius / self.radius
+    }
+
+    /// Conditions at a world space position, without sunlight, see
+    /// [`Environment::with_sunlight`]
+    pub fn environment(&self, position: Vector3) -> Environment {
+        let altitude = self.altitude(position);
+        Environment {
+            gravity: self.gravity(position),
+            pressure: self
+                .atmosphere
+                .map_or(Number::ZERO, |atmosphere| atmosphere.pressure(altitude)),
+            density: self
+                .atmosphere
+                .map_or(Number::ZERO, |atmosphere| atmosphere.density(altitude)),
+            air_temperature: self
+                .atmosphere
+                .map_or(Number::ZERO, |atmosphere| atmosphere.temperature),
+            air_velocity: self.rotation_velocity(position),
+            sun_direction: Vector3::ZERO,
+            solar_flux: Number::ZERO,
+        }
+    }
+
+    /// The terrain, for vessels to land on
+    pub fn ground(&self) -> Ground {
+        Ground::Body(Box::new(self.clone()))
+    }
+
+    /// For pointing vessels prograde, radial and so on around this body
+    pub fn reference(&self) -> Reference {
+        Reference {
+            position: self.position,
+            velocity: self.velocity,
+        }
+    }
+
+    /// Along the north pole, in world space
+    pub fn spin_axis(&self) -> Vector3 {
+        self.rotation.transform_direction(Vector3::Z)
+    }
+
+    /// A world space direction along the axes of the equatorial frame, which has the north pole
+    /// along +Z like the body-fixed frame but doesn't turn with the body
+    ///
+    /// +X stays along the line where the equator crosses the world's XY plane.
+    pub fn to_equatorial(&self, direction: Vector3) -> Vector3 {
+        let z = self.spin_axis();
+        let x = Vector3::X - z * Vector3::X.dot(z);
+        let x = x / x.length();
+        let y = z.cross(x);
+        Vector3::new(direction.dot(x), direction.dot(y), direction.dot(z))
+    }
+
+    /// The orbit `vessel` is on around this body, in its equatorial frame
+    pub fn orbit(&self, vessel: &Vessel) -> Orbit {
+        Orbit::from_state(
+            self.to_equatorial(vessel.transform.position() - self.position),
+            self.to_equatorial(vessel.velocity - self.velocity),
+            self.mu,
+        )
+    }
+}
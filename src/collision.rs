@@ -0,0 +1,452 @@
+use crate::{
+    convex_hull::ConvexHull, game_data::GameData, motor::Transform, vector3::Vector3,
+    vessel::Vessel, Number,
+};
+
+/// GJK and EPA give up after this many steps, which only rounding ever needs
+const MAX_ITERATIONS: usize = 64;
+
+/// Meters EPA may be off by
+const TOLERANCE: Number = Number::lit("0.0001");
+
+/// An axis aligned box in world space
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Aabb {
+    pub min: Vector3,
+    pub max: Vector3,
+}
+
+impl Aabb {
+    /// Box around a sphere
+    pub fn around_sphere(center: Vector3, radius: Number) -> Self {
+        let extent = Vector3::new(radius, radius, radius);
+        Self {
+            min: center - extent,
+            max: center + extent,
+        }
+    }
+
+    /// Box around a hull at `transform`, or `None` for empty hulls
+    pub fn around_hull(hull: &ConvexHull, transform: Transform) -> Option<Self> {
+        (!hull.is_empty())
+            .then(|| Self::around_sphere(transform.transform_point(hull.center), hull.radius))
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            min: Vector3::new(
+                self.min.x.min(other.min.x),
+                self.min.y.min(other.min.y),
+                self.min.z.min(other.min.z),
+            ),
+            max: Vector3::new(
+                self.max.x.max(other.max.x),
+                self.max.y.max(other.max.y),
+                self.max.z.max(other.max.z),
+            ),
+        }
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+            && self.min.z <= other.max.z
+            && other.min.z <= self.max.z
+    }
+}
+
+/// Where two shapes overlap, in world space
+#[derive(Debug, Clone, Copy)]
+pub struct Contact {
+    /// Halfway between the deepest points of the two shapes
+    pub point: Vector3,
+    /// Unit direction from the first shape into the second, moving the second shape `depth`
+    /// along it separates them
+    pub normal: Vector3,
+    /// Meters the shapes overlap by
+    pub depth: Number,
+}
+
+/// Tests two hulls posed by their transforms against each other, returning how they overlap
+pub fn collide(
+    a: &ConvexHull,
+    a_transform: Transform,
+    b: &ConvexHull,
+    b_transform: Transform,
+) -> Option<Contact> {
+    if a.is_empty() || b.is_empty() {
+        return None;
+    }
+
+    // Working in the first hull's space keeps the numbers small, far from the world origin
+    let relative = b_transform.apply(a_transform.inverse());
+    let inverse = relative.inverse();
+    let shapes = Shapes {
+        a,
+        b,
+        relative,
+        inverse,
+    };
+
+    let start = relative.transform_point(b.center) - a.center;
+    let simplex = gjk(&shapes, start)?;
+    let (normal, depth, on_a, on_b) = epa(&shapes, simplex)?;
+    Some(Contact {
+        point: a_transform.transform_point((on_a + on_b) / Number::from_num(2)),
+        normal: a_transform.transform_direction(normal),
+        depth,
+    })
+}
+
+/// Two hulls, the second posed in the space of the first
+struct Shapes<'a> {
+    a: &'a ConvexHull,
+    b: &'a ConvexHull,
+    relative: Transform,
+    inverse: Transform,
+}
+
+/// A corner of the Minkowski difference, remembering the corners of both hulls it came from
+#[derive(Debug, Clone, Copy)]
+struct Support {
+    point: Vector3,
+    a: Vector3,
+    b: Vector3,
+}
+
+impl Shapes<'_> {
+    fn support(&self, direction: Vector3) -> Support {
+        let a = self.a.support(direction);
+        let b = self
+            .relative
+            .transform_point(self.b.support(self.inverse.transform_direction(-direction)));
+        Support { point: a - b, a, b }
+    }
+}
+
+/// Searches the Minkowski difference of the shapes for the origin, returning a tetrahedron
+/// around it when the shapes overlap
+fn gjk(shapes: &Shapes, start: Vector3) -> Option<Vec<Support>> {
+    let mut direction = if start.sqr_length() > Number::ZERO {
+        start
+    } else {
+        Vector3::X
+    };
+    let mut simplex = vec![shapes.support(direction)];
+    direction = -simplex[0].point;
+
+    for _ in 0..MAX_ITERATIONS {
+        if direction.sqr_length() == Number::ZERO {
+            // The origin lies on the simplex itself
+            return complete_tetrahedron(shapes, simplex);
+        }
+        let support = shapes.support(direction);
+        if support.point.dot(direction) < Number::ZERO {
+            return None;
+        }
+        simplex.push(support);
+        if let Some(next) = next_direction(&mut simplex) {
+            direction = next;
+        } else {
+            return Some(simplex);
+        }
+    }
+    None
+}
+
+/// Drops the parts of the simplex furthest from the origin, returning the direction to search
+/// next, or `None` once the simplex is a tetrahedron around the origin
+fn next_direction(simplex: &mut Vec<Support>) -> Option<Vector3> {
+    // The newest point is always last
+    match simplex.len() {
+        2 => line_towards_origin(simplex),
+        3 => {
+            let [c, b, a] = [simplex[0].point, simplex[1].point, simplex[2].point];
+            let ab = b - a;
+            let ac = c - a;
+            let ao = -a;
+            let normal = ab.cross(ac);
+            if normal.cross(ac).dot(ao) > Number::ZERO {
+                if ac.dot(ao) > Number::ZERO {
+                    simplex.remove(1);
+                    Some(ac.cross(ao).cross(ac))
+                } else {
+                    simplex.remove(0);
+                    line_towards_origin(simplex)
+                }
+            } else if ab.cross(normal).dot(ao) > Number::ZERO {
+                simplex.remove(0);
+                line_towards_origin(simplex)
+            } else if normal.dot(ao) > Number::ZERO {
+                Some(normal)
+            } else {
+                simplex.swap(0, 1);
+                Some(-normal)
+            }
+        }
+        4 => {
+            let a = simplex[3].point;
+            let ao = -a;
+            // Each face touching the newest point, with the index of the point it leaves out
+            for ([first, second], left_out) in [([2, 1], 0), ([1, 0], 2), ([0, 2], 1)] {
+                let normal = (simplex[first].point - a).cross(simplex[second].point - a);
+                let normal = if normal.dot(simplex[left_out].point - a) > Number::ZERO {
+                    -normal
+                } else {
+                    normal
+                };
+                if normal.dot(ao) > Number::ZERO {
+                    simplex.remove(left_out);
+                    return next_direction(simplex);
+                }
+            }
+            None
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Direction from the line in the simplex towards the origin
+fn line_towards_origin(simplex: &mut Vec<Support>) -> Option<Vector3> {
+    let [b, a] = [simplex[0].point, simplex[1].point];
+    let ab = b - a;
+    let ao = -a;
+    if ab.dot(ao) > Number::ZERO {
+        Some(ab.cross(ao).cross(ab))
+    } else {
+        simplex.remove(0);
+        Some(ao)
+    }
+}
+
+/// Grows a simplex the origin lies on into a tetrahedron, so EPA has a volume to expand
+fn complete_tetrahedron(shapes: &Shapes, mut simplex: Vec<Support>) -> Option<Vec<Support>> {
+    let axes = [Vector3::X, Vector3::Y, Vector3::Z];
+    while simplex.len() < 4 {
+        let candidates = match simplex.len() {
+            1 => axes
+                .iter()
+                .flat_map(|&axis| [axis, -axis])
+                .collect::<Vec<_>>(),
+            2 => {
+                let line = simplex[1].point - simplex[0].point;
+                axes.iter()
+                    .map(|&axis| line.cross(axis))
+                    .flat_map(|direction| [direction, -direction])
+                    .collect()
+            }
+            _ => {
+                let normal = (simplex[1].point - simplex[0].point)
+                    .cross(simplex[2].point - simplex[0].point);
+                vec![normal, -normal]
+            }
+        };
+        let added = candidates
+            .into_iter()
+            .filter(|direction| direction.sqr_length() > Number::ZERO)
+            .map(|direction| shapes.support(direction))
+            .find(|support| !spans_nothing(&simplex, support.point))?;
+        simplex.push(added);
+    }
+    Some(simplex)
+}
+
+/// Whether adding `point` to the simplex would leave it flat
+fn spans_nothing(simplex: &[Support], point: Vector3) -> bool {
+    let offset = point - simplex[0].point;
+    match simplex.len() {
+        1 => offset.sqr_length() <= TOLERANCE * TOLERANCE,
+        2 => {
+            let line = simplex[1].point - simplex[0].point;
+            line.cross(offset).length() <= TOLERANCE * line.length()
+        }
+        _ => {
+            let normal =
+                (simplex[1].point - simplex[0].point).cross(simplex[2].point - simplex[0].point);
+            normal.dot(offset).abs() <= TOLERANCE * normal.length()
+        }
+    }
+}
+
+/// Pushes the faces of the tetrahedron outwards until the face closest to the origin is on the
+/// surface of the Minkowski difference, returning its normal, distance and the closest points on
+/// each shape
+fn epa(shapes: &Shapes, mut points: Vec<Support>) -> Option<(Vector3, Number, Vector3, Vector3)> {
+    let center = points
+        .iter()
+        .fold(Vector3::ZERO, |sum, support| sum + support.point)
+        / Number::from_num(4);
+    let mut faces = Vec::new();
+    for indices in [[0, 1, 2], [0, 1, 3], [0, 2, 3], [1, 2, 3]] {
+        let [a, b, c] = indices.map(|index| points[index].point);
+        let normal = (b - a).cross(c - a);
+        faces.push(if normal.dot(a - center) < Number::ZERO {
+            [indices[0], indices[2], indices[1]]
+        } else {
+            indices
+        });
+    }
+
+    // Unit outward normal and distance from the origin of a face, or `None` when it has no area
+    let plane = |points: &[Support], [a, b, c]: [usize; 3]| {
+        let a = points[a].point;
+        let normal = (points[b].point - a).cross(points[c].point - a);
+        let length = normal.length();
+        (length > Number::ZERO).then(|| {
+            let normal = normal / length;
+            (normal, normal.dot(a))
+        })
+    };
+
+    for _ in 0..MAX_ITERATIONS {
+        let (closest, (normal, distance)) = faces
+            .iter()
+            .enumerate()
+            .filter_map(|(index, &face)| Some((index, plane(&points, face)?)))
+            .min_by_key(|&(_, (_, distance))| distance)?;
+
+        let support = shapes.support(normal);
+        if support.point.dot(normal) - distance < TOLERANCE {
+            let [a, b, c] = faces[closest].map(|index| points[index]);
+            let [u, v, w] = barycentric(normal * distance, a.point, b.point, c.point);
+            let on_a = a.a * u + b.a * v + c.a * w;
+            let on_b = a.b * u + b.b * v + c.b * w;
+            return Some((normal, distance.max(Number::ZERO), on_a, on_b));
+        }
+
+        // Replace every face the new point sees with a fan from the edges around the hole
+        points.push(support);
+        let index = points.len() - 1;
+        let mut edges = Vec::<(usize, usize)>::new();
+        faces.retain(|&face| {
+            let sees = plane(&points, face).is_none_or(|(normal, distance)| {
+                normal.dot(support.point) - distance > Number::ZERO
+            });
+            if !sees {
+                return true;
+            }
+            let [a, b, c] = face;
+            for edge in [(a, b), (b, c), (c, a)] {
+                match edges.iter().position(|&(from, to)| (to, from) == edge) {
+                    Some(shared) => {
+                        edges.swap_remove(shared);
+                    }
+                    None => edges.push(edge),
+                }
+            }
+            false
+        });
+        faces.extend(edges.into_iter().map(|(from, to)| [from, to, index]));
+    }
+    None
+}
+
+/// Weights of `a`, `b` and `c` that give `point`, which lies in their plane
+fn barycentric(point: Vector3, a: Vector3, b: Vector3, c: Vector3) -> [Number; 3] {
+    let ab = b - a;
+    let ac = c - a;
+    let ap = point - a;
+    let d00 = ab.dot(ab);
+    let d01 = ab.dot(ac);
+    let d11 = ac.dot(ac);
+    let d20 = ap.dot(ab);
+    let d21 = ap.dot(ac);
+    let denominator = d00 * d11 - d01 * d01;
+    if denominator == Number::ZERO {
+        return [Number::ONE, Number::ZERO, Number::ZERO];
+    }
+    let v = (d11 * d20 - d01 * d21) / denominator;
+    let w = (d00 * d21 - d01 * d20) / denominator;
+    [Number::ONE - v - w, v, w]
+}
+
+/// World space box around every collidable part of `vessel`, or `None` when it has none
+pub fn vessel_bounds(vessel: &Vessel, data: &GameData) -> Option<Aabb> {
+    vessel
+        .parts
+        .iter()
+        .enumerate()
+        .filter_map(|(index, part)| {
+            Aabb::around_hull(&data.parts[part.part].hull, vessel.part_transform(index))
+        })
+        .reduce(Aabb::union)
+}
+
+/// Two parts of different vessels touching
+#[derive(Debug, Clone, Copy)]
+pub struct PartContact {
+    /// Indices of the two vessels
+    pub vessels: [usize; 2],
+    /// Indices of the touching part in each vessel
+    pub parts: [usize; 2],
+    /// With the normal pointing from the first vessel's part into the second's
+    pub contact: Contact,
+}
+
+/// Every pair of touching parts between different vessels
+///
+/// Vessels are paired up by their bounding boxes first, then their parts' boxes, before any
+/// hulls are tested.
+pub fn find_contacts(vessels: &[Vessel], data: &GameData) -> Vec<PartContact> {
+    let bounds = vessels
+        .iter()
+        .map(|vessel| vessel_bounds(vessel, data))
+        .collect::<Vec<_>>();
+
+    let mut contacts = Vec::new();
+    for first in 0..vessels.len() {
+        let Some(first_bounds) = bounds[first] else {
+            continue;
+        };
+        for second in first + 1..vessels.len() {
+            if !bounds[second].is_some_and(|bounds| bounds.overlaps(&first_bounds)) {
+                continue;
+            }
+            contacts.extend(vessel_contacts(vessels, data, [first, second]));
+        }
+    }
+    contacts
+}
+
+fn vessel_contacts(vessels: &[Vessel], data: &GameData, indices: [usize; 2]) -> Vec<PartContact> {
+    // Every collidable part of each vessel with its world transform and box
+    let [first, second] = indices.map(|index| {
+        let vessel = &vessels[index];
+        vessel
+            .parts
+            .iter()
+            .enumerate()
+            .filter_map(|(part_index, part)| {
+                let hull = &data.parts[part.part].hull;
+                let transform = vessel.part_transform(part_index);
+                Some((
+                    part_index,
+                    hull,
+                    transform,
+                    Aabb::around_hull(hull, transform)?,
+                ))
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let mut contacts = Vec::new();
+    for &(first_part, first_hull, first_transform, first_bounds) in &first {
+        for &(second_part, second_hull, second_transform, second_bounds) in &second {
+            if !first_bounds.overlaps(&second_bounds) {
+                continue;
+            }
+            if let Some(contact) =
+                collide(first_hull, first_transform, second_hull, second_transform)
+            {
+                contacts.push(PartContact {
+                    vessels: indices,
+                    parts: [first_part, second_part],
+                    contact,
+                });
+            }
+        }
+    }
+    contacts
+}
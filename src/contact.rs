@@ -0,0 +1,425 @@
+use crate::{
+    body::Body,
+    collision::{self, Contact},
+    game_data::GameData,
+    matrix3::Matrix3,
+    motor::Transform,
+    part::PartId,
+    vector3::Vector3,
+    vessel::Vessel,
+    Number,
+};
+
+/// Times the impulses get refined each tick, more settles stacks of contacts better
+const ITERATIONS: usize = 10;
+
+/// Meters per second of approach below which contacts don't bounce, so resting vessels stay put
+/// instead of hopping on the speed gravity adds each tick
+const RESTING_SPEED: Number = Number::lit("0.5");
+
+/// Meters of overlap left alone, so resting contacts don't flicker in and out of existence
+const SLOP: Number = Number::lit("0.005");
+
+/// Something vessels can stand on that nothing pushes around
+#[derive(Debug, Clone)]
+pub enum Ground {
+    /// An endless flat floor
+    Plane {
+        /// Any point on the floor
+        point: Vector3,
+        /// Unit length, pointing up
+        normal: Vector3,
+    },
+    /// The terrain of a body, turning along with it
+    Body(Box<Body>),
+}
+
+impl Ground {
+    /// Meters a world space point is above the ground, negative below it, and which way is up
+    /// there
+    pub fn probe(&self, point: Vector3) -> (Number, Vector3) {
+        match self {
+            &Ground::Plane {
+                point: origin,
+                normal,
+            } => ((point - origin).dot(normal), normal),
+            Ground::Body(body) => {
+                let geodetic = body.to_geodetic(point);
+                let height =
+                    geodetic.altitude - body.terrain_height(geodetic.latitude, geodetic.longitude);
+                let normal = body.ground_normal(geodetic.latitude, geodetic.longitude);
+                let up = body.rotation.transform_direction(
+                    body.ellipsoid().up(geodetic.latitude, geodetic.longitude),
+                );
+                // Out of a slope along its normal is shorter than straight up
+                (height * normal.dot(up), normal)
+            }
+        }
+    }
+
+    /// How fast the ground at a world space point is moving
+    pub fn velocity_at(&self, point: Vector3) -> Vector3 {
+        match self {
+            Ground::Plane { .. } => Vector3::ZERO,
+            Ground::Body(body) => body.rotation_velocity(point),
+        }
+    }
+}
+
+/// A part that hit something too fast and was destroyed
+#[derive(Debug, Clone, Copy)]
+pub struct Crash {
+    pub part: PartId,
+    /// In world space, where the part was
+    pub position: Vector3,
+    /// Meters per second the part hit at
+    pub speed: Number,
+}
+
+/// Pushes touching vessels apart, and out of `ground`, bouncing and rubbing against each other
+///
+/// Runs after the vessels moved for the tick. Parts hitting faster than their crash tolerance
+/// get destroyed, which can split vessels in two or remove them entirely, so vessel indices
+/// don't stay valid across this call.
+pub fn resolve_contacts(
+    vessels: &mut Vec<Vessel>,
+    data: &GameData,
+    ground: Option<&Ground>,
+) -> Vec<Crash> {
+    let mut solids = vessels
+        .iter()
+        .map(|vessel| Solid::new(vessel, data))
+        .collect::<Vec<_>>();
+
+    let mut contacts = Vec::new();
+    for part_contact in collision::find_contacts(vessels, data) {
+        let [first, second] = part_contact.vessels;
+        let [first_part, second_part] = part_contact.parts;
+        contacts.push(Constraint::new(
+            [(first, first_part), (second, second_part)].map(Some),
+            part_contact.contact,
+            Vector3::ZERO,
+            &solids,
+            vessels,
+            data,
+        ));
+    }
+    if let Some(ground) = ground {
+        for (index, vessel) in vessels.iter().enumerate() {
+            for (part, contact) in ground_contacts(vessel, data, ground) {
+                contacts.push(Constraint::new(
+                    [None, Some((index, part))],
+                    contact,
+                    ground.velocity_at(contact.point),
+                    &solids,
+                    vessels,
+                    data,
+                ));
+            }
+        }
+    }
+
+    // Parts hitting too hard break instead of stopping the vessel
+    let mut destroyed = vessels
+        .iter()
+        .map(|vessel| vec![false; vessel.parts.len()])
+        .collect::<Vec<_>>();
+    let mut crashes = Vec::new();
+    for contact in &contacts {
+        for (vessel, part) in contact.parts.into_iter().flatten() {
+            let definition = &data.parts[vessels[vessel].parts[part].part];
+            if contact.approach_speed > definition.crash_tolerance && !destroyed[vessel][part] {
+                destroyed[vessel][part] = true;
+                crashes.push(Crash {
+                    part: vessels[vessel].parts[part].part,
+                    position: vessels[vessel].parts[part]
+                        .transform
+                        .apply(vessels[vessel].transform)
+                        .position(),
+                    speed: contact.approach_speed,
+                });
+            }
+        }
+    }
+    contacts.retain(|contact| {
+        contact
+            .parts
+            .into_iter()
+            .flatten()
+            .all(|(vessel, part)| !destroyed[vessel][part])
+    });
+
+    for _ in 0..ITERATIONS {
+        for contact in &mut contacts {
+            contact.solve(&mut solids);
+        }
+    }
+
+    // Only the deepest contact moves each vessel, so resting on many points doesn't push it out
+    // many times over
+    let mut corrections = vec![Vector3::ZERO; vessels.len()];
+    for contact in &contacts {
+        let depth = contact.depth - SLOP;
+        if depth <= Number::ZERO {
+            continue;
+        }
+        let total_inverse_mass = contact
+            .parts
+            .iter()
+            .flatten()
+            .map(|&(vessel, _)| solids[vessel].inverse_mass)
+            .sum::<Number>();
+        if total_inverse_mass == Number::ZERO {
+            continue;
+        }
+        for (side, sign) in contact.parts.iter().zip([-Number::ONE, Number::ONE]) {
+            if let Some((vessel, _)) = *side {
+                let correction = contact.normal
+                    * (depth * sign * solids[vessel].inverse_mass / total_inverse_mass);
+                if correction.sqr_length() > corrections[vessel].sqr_length() {
+                    corrections[vessel] = correction;
+                }
+            }
+        }
+    }
+
+    for ((vessel, solid), correction) in vessels.iter_mut().zip(&solids).zip(corrections) {
+        vessel.velocity = solid.velocity;
+        vessel.angular_velocity = solid.angular_velocity;
+        vessel.transform = vessel.transform.apply(Transform::translation(correction));
+    }
+
+    let mut broken_off = Vec::new();
+    for (vessel, destroyed) in vessels.iter_mut().zip(&destroyed) {
+        if destroyed.contains(&true) {
+            broken_off.extend(vessel.destroy_parts(destroyed, data));
+        }
+    }
+    vessels.retain(|vessel| !vessel.parts.is_empty());
+    vessels.extend(broken_off);
+    crashes
+}
+
+/// Every corner of every part of `vessel` that is below the ground, with the part it belongs to
+pub fn ground_contacts(vessel: &Vessel, data: &GameData, ground: &Ground) -> Vec<(usize, Contact)> {
+    let mut contacts = Vec::new();
+    for (index, part) in vessel.parts.iter().enumerate() {
+        let hull = &data.parts[part.part].hull;
+        if hull.is_empty() {
+            continue;
+        }
+        let transform = vessel.part_transform(index);
+        let (height, _) = ground.probe(transform.transform_point(hull.center));
+        if height > hull.radius {
+            continue;
+        }
+        for &vertex in &hull.vertices {
+            let point = transform.transform_point(vertex);
+            let (height, normal) = ground.probe(point);
+            if height < Number::ZERO {
+                contacts.push((
+                    index,
+                    Contact {
+                        point,
+                        normal,
+                        depth: -height,
+                    },
+                ));
+            }
+        }
+    }
+    contacts
+}
+
+/// A vessel as a rigid body while its contacts are being solved
+struct Solid {
+    inverse_mass: Number,
+    /// In world space
+    inverse_inertia: Matrix3,
+    /// In world space
+    center_of_mass: Vector3,
+    velocity: Vector3,
+    angular_velocity: Vector3,
+}
+
+impl Solid {
+    fn new(vessel: &Vessel, data: &GameData) -> Self {
+        let mass_properties = vessel.mass_properties(data);
+        Self {
+            inverse_mass: if mass_properties.mass > Number::ZERO {
+                Number::ONE / mass_properties.mass
+            } else {
+                Number::ZERO
+            },
+            inverse_inertia: mass_properties
+                .transformed_inertia(vessel.transform)
+                .inverse()
+                .unwrap_or(Matrix3::ZERO),
+            center_of_mass: vessel
+                .transform
+                .transform_point(mass_properties.center_of_mass),
+            velocity: vessel.velocity,
+            angular_velocity: vessel.angular_velocity,
+        }
+    }
+
+    fn velocity_at(&self, offset: Vector3) -> Vector3 {
+        self.velocity + self.angular_velocity.cross(offset)
+    }
+
+    fn apply_impulse(&mut self, impulse: Vector3, offset: Vector3) {
+        self.velocity += impulse * self.inverse_mass;
+        self.angular_velocity += self.inverse_inertia * offset.cross(impulse);
+    }
+
+    /// How much an impulse along `direction` at `offset` speeds the point up there
+    fn inverse_effective_mass(&self, direction: Vector3, offset: Vector3) -> Number {
+        self.inverse_mass
+            + direction.dot((self.inverse_inertia * offset.cross(direction)).cross(offset))
+    }
+}
+
+/// A contact point being solved, pushing the second side away from the first along the normal
+struct Constraint {
+    /// Vessel and part index on each side, `None` for the ground
+    parts: [Option<(usize, usize)>; 2],
+    /// From the first side into the second
+    normal: Vector3,
+    tangents: [Vector3; 2],
+    depth: Number,
+    /// From each side's center of mass to the contact point
+    offsets: [Vector3; 2],
+    /// Of the ground at the contact point, when one side is the ground
+    ground_velocity: Vector3,
+    normal_mass: Number,
+    tangent_masses: [Number; 2],
+    /// Meters per second the sides were closing at before solving
+    approach_speed: Number,
+    /// Meters per second the sides should separate at
+    bounce: Number,
+    friction: Number,
+    normal_impulse: Number,
+    tangent_impulses: [Number; 2],
+}
+
+impl Constraint {
+    fn new(
+        parts: [Option<(usize, usize)>; 2],
+        contact: Contact,
+        ground_velocity: Vector3,
+        solids: &[Solid],
+        vessels: &[Vessel],
+        data: &GameData,
+    ) -> Self {
+        let normal = contact.normal;
+        let side = if normal.x.abs() < Number::lit("0.57") {
+            Vector3::X
+        } else {
+            Vector3::Y
+        };
+        let first_tangent = normal.cross(side);
+        let first_tangent = first_tangent / first_tangent.length();
+        let tangents = [first_tangent, normal.cross(first_tangent)];
+
+        let offsets = parts.map(|side| {
+            side.map_or(Vector3::ZERO, |(vessel, _)| {
+                contact.point - solids[vessel].center_of_mass
+            })
+        });
+        let inverse_mass = |direction: Vector3| {
+            parts
+                .iter()
+                .zip(offsets)
+                .filter_map(|(side, offset)| {
+                    side.map(|(vessel, _)| solids[vessel].inverse_effective_mass(direction, offset))
+                })
+                .sum::<Number>()
+        };
+        let mass = |direction: Vector3| {
+            let inverse = inverse_mass(direction);
+            if inverse > Number::ZERO {
+                Number::ONE / inverse
+            } else {
+                Number::ZERO
+            }
+        };
+
+        // Bouncy parts bounce, and rough parts grip, even against smooth ones
+        let definitions = parts
+            .iter()
+            .flatten()
+            .map(|&(vessel, part)| &data.parts[vessels[vessel].parts[part].part])
+            .collect::<Vec<_>>();
+        let restitution = definitions
+            .iter()
+            .map(|definition| definition.restitution)
+            .max()
+            .unwrap_or(Number::ZERO);
+        let friction = definitions
+            .iter()
+            .map(|definition| definition.friction)
+            .max()
+            .unwrap_or(Number::ZERO);
+
+        let mut constraint = Self {
+            parts,
+            normal,
+            tangents,
+            depth: contact.depth,
+            offsets,
+            ground_velocity,
+            normal_mass: mass(normal),
+            tangent_masses: tangents.map(mass),
+            approach_speed: Number::ZERO,
+            bounce: Number::ZERO,
+            friction,
+            normal_impulse: Number::ZERO,
+            tangent_impulses: [Number::ZERO; 2],
+        };
+        constraint.approach_speed = -constraint.relative_velocity(solids).dot(normal);
+        if constraint.approach_speed > RESTING_SPEED {
+            constraint.bounce = constraint.approach_speed * restitution;
+        }
+        constraint
+    }
+
+    /// Of the second side's contact point, relative to the first's
+    fn relative_velocity(&self, solids: &[Solid]) -> Vector3 {
+        let velocity = |index: usize| {
+            self.parts[index].map_or(self.ground_velocity, |(vessel, _)| {
+                solids[vessel].velocity_at(self.offsets[index])
+            })
+        };
+        velocity(1) - velocity(0)
+    }
+
+    fn apply_impulse(&self, solids: &mut [Solid], impulse: Vector3) {
+        for (index, sign) in [(0, -Number::ONE), (1, Number::ONE)] {
+            if let Some((vessel, _)) = self.parts[index] {
+                solids[vessel].apply_impulse(impulse * sign, self.offsets[index]);
+            }
+        }
+    }
+
+    /// Moves the accumulated impulses closer to ones that stop the sides going into each other
+    fn solve(&mut self, solids: &mut [Solid]) {
+        let normal_speed = self.relative_velocity(solids).dot(self.normal);
+        let impulse = (self.normal_impulse + (self.bounce - normal_speed) * self.normal_mass)
+            .max(Number::ZERO);
+        let change = impulse - self.normal_impulse;
+        self.normal_impulse = impulse;
+        self.apply_impulse(solids, self.normal * change);
+
+        // Friction can hold back at most a fraction of how hard the sides push together
+        let limit = self.normal_impulse * self.friction;
+        for axis in 0..2 {
+            let tangent = self.tangents[axis];
+            let speed = self.relative_velocity(solids).dot(tangent);
+            let impulse = (self.tangent_impulses[axis] - speed * self.tangent_masses[axis])
+                .clamp(-limit, limit);
+            let change = impulse - self.tangent_impulses[axis];
+            self.tangent_impulses[axis] = impulse;
+            self.apply_impulse(solids, tangent * change);
+        }
+    }
+}
@@ -0,0 +1,312 @@
+use crate::{
+    engine::{self, EngineOutput, Propellant, PropellantConfig, STANDARD_GRAVITY},
+    float_curve::FloatCurve,
+    flow::{self, Consumer},
+    game_data::GameData,
+    physics::Forces,
+    registry::Registry,
+    resource::{ResourceDefinition, ResourceRate, ResourceRateConfig},
+    sin_cos,
+    vector3::Vector3,
+    vessel::Vessel,
+    Number,
+};
+use serde::{Deserialize, Serialize};
+
+/// What the pilot asks of the vessel, every value between -1 and 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ControlInput {
+    /// Rotation around the vessel's +X axis
+    pub pitch: Number,
+    /// Rotation around the vessel's +Z axis
+    pub yaw: Number,
+    /// Rotation around the vessel's +Y axis, the way engines push
+    pub roll: Number,
+    /// In vessel space
+    pub translate: Vector3,
+}
+
+impl ControlInput {
+    pub const NEUTRAL: Self = Self {
+        pitch: Number::ZERO,
+        yaw: Number::ZERO,
+        roll: Number::ZERO,
+        translate: Vector3::ZERO,
+    };
+
+    /// Requested rotation in vessel space, right handed around each axis
+    pub fn rotation(&self) -> Vector3 {
+        clamp_axes(Vector3::new(self.pitch, self.roll, self.yaw))
+    }
+
+    /// Requested translation in vessel space
+    pub fn translation(&self) -> Vector3 {
+        clamp_axes(self.translate)
+    }
+}
+
+fn clamp_axes(vector: Vector3) -> Vector3 {
+    let clamp = |value: Number| value.clamp(-Number::ONE, Number::ONE);
+    Vector3::new(clamp(vector.x), clamp(vector.y), clamp(vector.z))
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ReactionWheelConfig {
+    /// Newton meters around the part's X, Y and Z axes
+    pub torque: [f64; 3],
+    /// Used at full torque, and in proportion to the busiest axis otherwise
+    #[serde(default)]
+    pub resources: Vec<ResourceRateConfig>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReactionWheelDefinition {
+    /// Newton meters around each part space axis
+    pub torque: Vector3,
+    pub resources: Vec<ResourceRate>,
+}
+
+impl ReactionWheelDefinition {
+    pub fn new(config: &ReactionWheelConfig, resources: &Registry<ResourceDefinition>) -> Self {
+        let [x, y, z] = config.torque.map(Number::from_num);
+        Self {
+            torque: Vector3::new(x, y, z),
+            resources: config
+                .resources
+                .iter()
+                .map(|rate| ResourceRate::new(rate, resources))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct RcsConfig {
+    /// Newtons per thruster in vacuum
+    pub thrust: f64,
+    /// Specific impulse in seconds against pressure in atmospheres
+    pub isp: FloatCurve,
+    pub propellants: Vec<PropellantConfig>,
+    /// Taken from the model's `thruster` objects when not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thrusters: Option<Vec<ThrusterConfig>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ThrusterConfig {
+    /// In part space
+    pub position: [f64; 3],
+    /// Part space direction the thruster pushes the part in
+    pub direction: [f64; 3],
+}
+
+/// A single RCS nozzle, in part space
+#[derive(Debug, Clone, Copy)]
+pub struct Thruster {
+    pub position: Vector3,
+    /// Unit direction the thruster pushes the part in, opposite to its exhaust
+    pub direction: Vector3,
+}
+
+impl Thruster {
+    pub fn new(config: &ThrusterConfig) -> Self {
+        let [x, y, z] = config.position.map(Number::from_num);
+        let position = Vector3::new(x, y, z);
+        let [x, y, z] = config.direction.map(Number::from_num);
+        let direction = Vector3::new(x, y, z);
+        assert!(
+            direction.length() > Number::ZERO,
+            "thrusters need a direction to push in"
+        );
+        Self {
+            position,
+            direction: direction / direction.length(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RcsDefinition {
+    /// Specific impulse in seconds against pressure in atmospheres
+    pub isp: FloatCurve,
+    pub propellants: Vec<Propellant>,
+    /// Kilograms per second for each thruster at full thrust
+    pub max_mass_flow: Number,
+    pub thrusters: Vec<Thruster>,
+}
+
+impl RcsDefinition {
+    /// `model_thrusters` are used when the config does not list its own
+    pub fn new(
+        config: &RcsConfig,
+        model_thrusters: &[Thruster],
+        resources: &Registry<ResourceDefinition>,
+    ) -> Self {
+        let vacuum_isp = config.isp.evaluate(Number::ZERO);
+        Self {
+            isp: config.isp.clone(),
+            propellants: engine::propellants(&config.propellants, resources),
+            max_mass_flow: Number::from_num(config.thrust)
+                / (vacuum_isp * Number::from_num(STANDARD_GRAVITY)),
+            thrusters: match &config.thrusters {
+                Some(thrusters) => thrusters.iter().map(Thruster::new).collect(),
+                None => model_thrusters.to_vec(),
+            },
+        }
+    }
+
+    /// Newtons per thruster at `pressure` atmospheres
+    pub fn thrust(&self, pressure: Number) -> Number {
+        self.max_mass_flow * self.isp.evaluate(pressure) * Number::from_num(STANDARD_GRAVITY)
+    }
+}
+
+/// Runs every reaction wheel on `vessel` for `dt` seconds, returning their torque in vessel
+/// space
+pub fn run_reaction_wheels(vessel: &mut Vessel, data: &GameData, dt: Number) -> Vector3 {
+    let rotation = vessel.control.rotation();
+    let wheels = vessel
+        .parts
+        .iter()
+        .enumerate()
+        .filter_map(|(index, part)| Some((index, data.parts[part.part].reaction_wheel.as_ref()?)))
+        .collect::<Vec<_>>();
+
+    let mut commands = Vec::with_capacity(wheels.len());
+    let consumers = wheels
+        .iter()
+        .map(|&(index, wheel)| {
+            // Each axis of a wheel is limited on its own, so the command is split up in part
+            // space
+            let command = clamp_axes(
+                vessel.parts[index]
+                    .transform
+                    .inverse()
+                    .transform_direction(rotation),
+            );
+            let effort = command.x.abs().max(command.y.abs()).max(command.z.abs());
+            commands.push(command);
+            wheel
+                .resources
+                .iter()
+                .map(|used| Consumer {
+                    part: index,
+                    resource: used.resource,
+                    demand: used.rate * effort * dt,
+                })
+                .collect()
+        })
+        .collect::<Vec<_>>();
+    let supplied = flow::draw_together(vessel, data, &consumers);
+
+    wheels.iter().zip(commands).zip(supplied).fold(
+        Vector3::ZERO,
+        |torque, ((&(index, wheel), command), supplied)| {
+            torque
+                + vessel.parts[index]
+                    .transform
+                    .transform_direction(command * wheel.torque * supplied)
+        },
+    )
+}
+
+/// Tilts every gimballing engine towards the requested rotation, with `center_of_mass` in
+/// vessel space
+pub fn gimbal_engines(
+    vessel: &Vessel,
+    data: &GameData,
+    outputs: &mut [EngineOutput],
+    center_of_mass: Vector3,
+) {
+    let rotation = vessel.control.rotation();
+    for output in outputs {
+        let part = &vessel.parts[output.part];
+        let engine = data.parts[part.part].engine.as_ref().unwrap();
+        if engine.max_gimbal <= Number::ZERO {
+            continue;
+        }
+
+        let offset = part.transform.position() - center_of_mass;
+        // How well tilting the nozzle towards one of the part's sideways axes turns the vessel
+        // the requested way
+        let tilt_towards = |axis: Vector3| {
+            let torque = offset.cross(part.transform.transform_direction(axis));
+            let length = torque.length();
+            if length > Number::ZERO {
+                torque.dot(rotation) / length
+            } else {
+                Number::ZERO
+            }
+        };
+        let tilt = Vector3::new(
+            tilt_towards(Vector3::X),
+            Number::ZERO,
+            tilt_towards(Vector3::Z),
+        );
+        let amount = tilt.length();
+        if amount == Number::ZERO {
+            continue;
+        }
+        let (sin, cos) = sin_cos(engine.max_gimbal * amount.min(Number::ONE));
+        output.direction = Vector3::Y * cos + tilt / amount * sin;
+    }
+}
+
+/// Fires the RCS thrusters on `vessel` that help with the requested rotation and translation
+/// for `dt` seconds, returning their forces in vessel space around `center_of_mass`
+pub fn run_rcs(
+    vessel: &mut Vessel,
+    data: &GameData,
+    pressure: Number,
+    center_of_mass: Vector3,
+    dt: Number,
+) -> Forces {
+    let rotation = vessel.control.rotation();
+    let translation = vessel.control.translation();
+
+    let mut firing = Vec::new();
+    let mut consumers = Vec::new();
+    for (index, part) in vessel.parts.iter().enumerate() {
+        let Some(rcs) = &data.parts[part.part].rcs else {
+            continue;
+        };
+        let mut total = Number::ZERO;
+        let thrusters = rcs
+            .thrusters
+            .iter()
+            .map(|thruster| {
+                let position = part.transform.transform_point(thruster.position);
+                let direction = part.transform.transform_direction(thruster.direction);
+                let torque = (position - center_of_mass).cross(direction);
+                let torque_length = torque.length();
+                let mut fraction = direction.dot(translation);
+                if torque_length > Number::ZERO {
+                    fraction += torque.dot(rotation) / torque_length;
+                }
+                let fraction = fraction.clamp(Number::ZERO, Number::ONE);
+                total += fraction;
+                (position, direction, fraction)
+            })
+            .collect::<Vec<_>>();
+        consumers.push(engine::consumers(
+            &rcs.propellants,
+            index,
+            rcs.max_mass_flow * total * dt,
+        ));
+        firing.push((index, thrusters));
+    }
+    let supplied = flow::draw_together(vessel, data, &consumers);
+
+    let mut forces = Forces::ZERO;
+    for ((index, thrusters), supplied) in firing.into_iter().zip(supplied) {
+        let rcs = data.parts[vessel.parts[index].part].rcs.as_ref().unwrap();
+        let thrust = rcs.thrust(pressure) * supplied;
+        for (position, direction, fraction) in thrusters {
+            forces.add_at(direction * thrust * fraction, position, center_of_mass);
+        }
+    }
+    forces
+}
@@ -0,0 +1,308 @@
+use crate::{vector3::Vector3, Number, Vertex};
+use std::collections::HashMap;
+
+/// The smallest convex shape around a part's model, in part space, which is what collisions are
+/// tested against
+#[derive(Debug, Clone)]
+pub struct ConvexHull {
+    /// Corners of the hull, empty for parts that can't collide
+    pub vertices: Vec<Vector3>,
+    /// Triangles indexing into `vertices`, wound counterclockwise seen from outside, empty when
+    /// the corners are all in one plane
+    pub faces: Vec<[usize; 3]>,
+    /// Center of a sphere around every corner
+    pub center: Vector3,
+    /// Radius of a sphere around every corner
+    pub radius: Number,
+}
+
+impl ConvexHull {
+    /// Hull around the positions of every vertex of a model
+    pub fn from_vertices(vertices: &[Vertex]) -> Self {
+        let points = vertices
+            .iter()
+            .map(|vertex| {
+                let position = vertex.position;
+                [position.x as f64, position.y as f64, position.z as f64]
+            })
+            .collect::<Vec<_>>();
+        Self::from_points(&points)
+    }
+
+    /// Hull around `points`, built in f64 as the plane tests of nearly flat faces need more
+    /// precision than fixed point has
+    pub fn from_points(points: &[[f64; 3]]) -> Self {
+        let mut points = points.to_vec();
+        points.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        points.dedup_by(|a, b| distance_squared(*a, *b) < EPSILON * EPSILON);
+
+        let (vertices, faces) = match initial_tetrahedron(&points) {
+            Some(tetrahedron) => quickhull(&points, tetrahedron),
+            // Flat or smaller, so every point could be a corner
+            None => (points, Vec::new()),
+        };
+
+        let to_vector = |[x, y, z]: [f64; 3]| {
+            Vector3::new(
+                Number::from_num(x),
+                Number::from_num(y),
+                Number::from_num(z),
+            )
+        };
+        let (center, radius) = if vertices.is_empty() {
+            (Vector3::ZERO, Number::ZERO)
+        } else {
+            let mut min = vertices[0];
+            let mut max = vertices[0];
+            for vertex in &vertices {
+                for axis in 0..3 {
+                    min[axis] = min[axis].min(vertex[axis]);
+                    max[axis] = max[axis].max(vertex[axis]);
+                }
+            }
+            let center = [0, 1, 2].map(|axis| (min[axis] + max[axis]) / 2.0);
+            let radius = vertices
+                .iter()
+                .map(|&vertex| distance_squared(vertex, center))
+                .fold(0.0, f64::max)
+                .sqrt();
+            (to_vector(center), Number::from_num(radius))
+        };
+
+        Self {
+            vertices: vertices.into_iter().map(to_vector).collect(),
+            faces,
+            center,
+            radius,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    /// Square meters of the outside of the hull, zero when it's flat
+    pub fn surface_area(&self) -> Number {
+        self.faces
+            .iter()
+            .map(|&[a, b, c]| {
+                let [a, b, c] = [a, b, c].map(|index| self.vertices[index]);
+                (b - a).cross(c - a).length() / 2
+            })
+            .sum()
+    }
+
+    /// The corner furthest along `direction`
+    pub fn support(&self, direction: Vector3) -> Vector3 {
+        self.vertices
+            .iter()
+            .copied()
+            .max_by_key(|vertex| vertex.dot(direction))
+            .unwrap_or(Vector3::ZERO)
+    }
+}
+
+/// Meters closer than which points are treated as the same, or as lying on a plane
+const EPSILON: f64 = 1e-6;
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn distance_squared(a: [f64; 3], b: [f64; 3]) -> f64 {
+    let offset = sub(a, b);
+    dot(offset, offset)
+}
+
+/// Indices of four points spanning as much volume as is quick to find, or `None` when every
+/// point lies in one plane
+fn initial_tetrahedron(points: &[[f64; 3]]) -> Option<[usize; 4]> {
+    let furthest = |score: &dyn Fn([f64; 3]) -> f64| {
+        (0..points.len()).max_by(|&a, &b| score(points[a]).total_cmp(&score(points[b])))
+    };
+
+    let a = 0;
+    let b = furthest(&|point| distance_squared(point, points[a]))?;
+    let line = sub(points[b], points[a]);
+    let c = furthest(&|point| {
+        let offset = cross(line, sub(point, points[a]));
+        dot(offset, offset)
+    })?;
+    let normal = cross(line, sub(points[c], points[a]));
+    let d = furthest(&|point| dot(normal, sub(point, points[a])).abs())?;
+
+    let length = dot(normal, normal).sqrt();
+    let height = dot(normal, sub(points[d], points[a])).abs();
+    (length > EPSILON && height > EPSILON * length).then_some([a, b, c, d])
+}
+
+/// A hull face while the hull is growing
+struct Face {
+    indices: [usize; 3],
+    /// Unit length, pointing out of the hull
+    normal: [f64; 3],
+    /// Distance of the face's plane from the origin along `normal`
+    offset: f64,
+    /// Points above the face that haven't been added yet
+    outside: Vec<usize>,
+}
+
+impl Face {
+    fn new(points: &[[f64; 3]], indices: [usize; 3]) -> Self {
+        let [a, b, c] = indices.map(|index| points[index]);
+        let normal = cross(sub(b, a), sub(c, a));
+        let length = dot(normal, normal).sqrt();
+        // Slivers get a zero normal, so no point is ever above them
+        let normal = if length > 0.0 {
+            normal.map(|axis| axis / length)
+        } else {
+            [0.0; 3]
+        };
+        Self {
+            indices,
+            normal,
+            offset: dot(normal, a),
+            outside: Vec::new(),
+        }
+    }
+
+    fn height(&self, point: [f64; 3]) -> f64 {
+        dot(self.normal, point) - self.offset
+    }
+}
+
+/// Grows the hull from `tetrahedron` by repeatedly adding the point furthest outside a face,
+/// returning the corners and faces that are left
+fn quickhull(points: &[[f64; 3]], tetrahedron: [usize; 4]) -> (Vec<[f64; 3]>, Vec<[usize; 3]>) {
+    let [a, b, c, d] = tetrahedron;
+    let mut faces = Vec::<Option<Face>>::new();
+    // The face using each directed edge, to walk from a face to its neighbours
+    let mut edges = HashMap::new();
+
+    for (indices, opposite) in [
+        ([a, b, c], d),
+        ([a, c, d], b),
+        ([a, d, b], c),
+        ([b, d, c], a),
+    ] {
+        let mut face = Face::new(points, indices);
+        if face.height(points[opposite]) > 0.0 {
+            let [a, b, c] = indices;
+            face = Face::new(points, [a, c, b]);
+        }
+        add_face(&mut faces, &mut edges, face);
+    }
+    let first_faces = (0..faces.len()).collect::<Vec<_>>();
+    assign_outside(points, &mut faces, &first_faces, 0..points.len());
+
+    while let Some(start) = faces
+        .iter()
+        .position(|face| face.as_ref().is_some_and(|face| !face.outside.is_empty()))
+    {
+        let face = faces[start].as_ref().unwrap();
+        let eye = *face
+            .outside
+            .iter()
+            .max_by(|&&a, &&b| face.height(points[a]).total_cmp(&face.height(points[b])))
+            .unwrap();
+
+        // Walk out from the face to every connected face the eye sees, noting the edges where
+        // the seen region ends
+        let mut visible = vec![start];
+        let mut horizon = Vec::new();
+        let mut index = 0;
+        while index < visible.len() {
+            let [a, b, c] = faces[visible[index]].as_ref().unwrap().indices;
+            for (from, to) in [(a, b), (b, c), (c, a)] {
+                let neighbour = edges[&(to, from)];
+                if visible.contains(&neighbour) {
+                    continue;
+                }
+                let neighbour_face = faces[neighbour].as_ref().unwrap();
+                if neighbour_face.height(points[eye]) > EPSILON {
+                    visible.push(neighbour);
+                } else {
+                    horizon.push((from, to));
+                }
+            }
+            index += 1;
+        }
+
+        let mut orphans = Vec::new();
+        for &index in &visible {
+            let face = faces[index].take().unwrap();
+            let [a, b, c] = face.indices;
+            for edge in [(a, b), (b, c), (c, a)] {
+                if edges.get(&edge) == Some(&index) {
+                    edges.remove(&edge);
+                }
+            }
+            orphans.extend(face.outside.into_iter().filter(|&point| point != eye));
+        }
+        let new_faces = horizon
+            .into_iter()
+            .map(|(from, to)| add_face(&mut faces, &mut edges, Face::new(points, [from, to, eye])))
+            .collect::<Vec<_>>();
+        assign_outside(points, &mut faces, &new_faces, orphans);
+    }
+
+    // Only keep the points some face still uses
+    let mut remap = vec![None; points.len()];
+    let mut vertices = Vec::new();
+    let faces = faces
+        .into_iter()
+        .flatten()
+        .map(|face| {
+            face.indices.map(|index| {
+                *remap[index].get_or_insert_with(|| {
+                    vertices.push(points[index]);
+                    vertices.len() - 1
+                })
+            })
+        })
+        .collect();
+    (vertices, faces)
+}
+
+/// Adds a face, returning its index
+fn add_face(
+    faces: &mut Vec<Option<Face>>,
+    edges: &mut HashMap<(usize, usize), usize>,
+    face: Face,
+) -> usize {
+    let [a, b, c] = face.indices;
+    for edge in [(a, b), (b, c), (c, a)] {
+        edges.insert(edge, faces.len());
+    }
+    faces.push(Some(face));
+    faces.len() - 1
+}
+
+/// Hands each point to the first of `candidates` it's above, dropping the ones inside them all
+fn assign_outside(
+    points: &[[f64; 3]],
+    faces: &mut [Option<Face>],
+    candidates: &[usize],
+    unassigned: impl IntoIterator<Item = usize>,
+) {
+    for point in unassigned {
+        if let Some(&face) = candidates
+            .iter()
+            .find(|&&face| faces[face].as_ref().unwrap().height(points[point]) > EPSILON)
+        {
+            faces[face].as_mut().unwrap().outside.push(point);
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use crate::{
+    engine::{self, STANDARD_GRAVITY},
+    flow,
+    game_data::GameData,
+    ln, staging,
+    vector3::Vector3,
+    vessel::Vessel,
+    Number,
+};
+
+/// Seconds below which a tank is as good as dry, see [`time_to_empty_tank`]
+const SHORTEST_PROBE: Number = Number::lit("0.000001");
+
+/// What a single stage can do, burning at full throttle from ignition until it is dropped
+#[derive(Debug, Clone, Copy)]
+pub struct StageStats {
+    pub stage: usize,
+    /// Kilograms right after the stage is activated
+    pub start_mass: Number,
+    /// Kilograms once the stage has burnt out
+    pub end_mass: Number,
+    /// Newtons at ignition
+    pub thrust: Number,
+    /// Effective specific impulse of all engines together, in seconds
+    pub isp: Number,
+    /// Meters per second
+    pub delta_v: Number,
+    /// Seconds
+    pub burn_time: Number,
+    /// Thrust to weight ratio at ignition
+    pub twr: Number,
+    /// Thrust to weight ratio right before burnout
+    pub max_twr: Number,
+}
+
+/// Works out every stage of `vessel` that has not been activated yet, with weight measured
+/// against `surface_gravity` and engines running at `pressure` atmospheres
+///
+/// A stage burns until the engines the next stage drops have flamed out, or until every engine
+/// has when nothing is dropped. Parts separated by staging no longer count towards the mass.
+pub fn stage_stats(
+    vessel: &Vessel,
+    data: &GameData,
+    surface_gravity: Number,
+    pressure: Number,
+) -> Vec<StageStats> {
+    let mut vessel = vessel.clone();
+    vessel.throttle = Number::ONE;
+
+    let mut stages = Vec::with_capacity(staging::remaining_stages(&vessel));
+    while staging::remaining_stages(&vessel) > 0 {
+        let stage = vessel.current_stage;
+        staging::activate_next_stage(&mut vessel, data);
+        let dropped = staging::dropped_parts(&vessel, data, vessel.current_stage);
+        stages.push(burn_stage(
+            &mut vessel,
+            data,
+            stage,
+            &dropped,
+            surface_gravity,
+            pressure,
+        ));
+    }
+    stages
+}
+
+fn burn_stage(
+    vessel: &mut Vessel,
+    data: &GameData,
+    stage: usize,
+    dropped: &[bool],
+    surface_gravity: Number,
+    pressure: Number,
+) -> StageStats {
+    let engines = vessel
+        .parts
+        .iter()
+        .enumerate()
+        .filter(|(_, part)| part.activated && data.parts[part.part].engine.is_some())
+        .map(|(index, _)| index)
+        .collect::<Vec<_>>();
+    let watched = if engines.iter().any(|&part| dropped[part]) {
+        engines
+            .iter()
+            .copied()
+            .filter(|&part| dropped[part])
+            .collect()
+    } else {
+        engines.clone()
+    };
+
+    // The engines burn at a steady rate until one of their tanks runs dry, so the stage goes
+    // from one tank running dry to the next
+    let start_mass = vessel.mass(data);
+    let mut ignition_thrust = None;
+    let mut last_thrust = Number::ZERO;
+    let mut impulse = Number::ZERO;
+    let mut burn_time = Number::ZERO;
+    loop {
+        let burning = engines
+            .iter()
+            .copied()
+            .filter(|&part| engine::has_propellant(vessel, data, part))
+            .collect::<Vec<_>>();
+        if !watched.iter().any(|part| burning.contains(part)) {
+            break;
+        }
+        let Some(duration) = time_to_empty_tank(vessel, data, &burning) else {
+            break;
+        };
+
+        let mass = vessel.mass(data);
+        let outputs = engine::run_engines(vessel, data, pressure, duration);
+        if vessel.mass(data) >= mass {
+            break;
+        }
+        let thrust = outputs
+            .iter()
+            .fold(Vector3::ZERO, |sum, output| {
+                let direction = vessel.parts[output.part]
+                    .transform
+                    .transform_direction(output.direction);
+                sum + direction * output.thrust
+            })
+            .length();
+        ignition_thrust.get_or_insert(thrust);
+        last_thrust = thrust;
+        impulse += thrust * duration;
+        burn_time += duration;
+    }
+
+    let end_mass = vessel.mass(data);
+    let standard_gravity = Number::from_num(STANDARD_GRAVITY);
+    let isp = if end_mass < start_mass {
+        impulse / (start_mass - end_mass) / standard_gravity
+    } else {
+        Number::ZERO
+    };
+    let delta_v = if end_mass > Number::ZERO {
+        isp * standard_gravity * ln(start_mass / end_mass)
+    } else {
+        Number::ZERO
+    };
+
+    let weight = |mass: Number| mass * surface_gravity;
+    let ratio = |thrust: Number, mass: Number| {
+        if weight(mass) > Number::ZERO {
+            thrust / weight(mass)
+        } else {
+            Number::ZERO
+        }
+    };
+    let thrust = ignition_thrust.unwrap_or(Number::ZERO);
+    StageStats {
+        stage,
+        start_mass,
+        end_mass,
+        thrust,
+        isp,
+        delta_v,
+        burn_time,
+        twr: ratio(thrust, start_mass),
+        max_twr: ratio(last_thrust, end_mass),
+    }
+}
+
+/// Seconds until the first of the tanks feeding `engines` at full throttle runs dry
+///
+/// Draws only stay in proportion while every tank has something left, so the flow is worked
+/// out over ever shorter probes until none of them empties a tank.
+fn time_to_empty_tank(vessel: &Vessel, data: &GameData, engines: &[usize]) -> Option<Number> {
+    let mut probe = Number::ONE;
+    while probe > SHORTEST_PROBE {
+        let consumers = engines
+            .iter()
+            .flat_map(|&part| {
+                let engine = data.parts[vessel.parts[part].part].engine.as_ref().unwrap();
+                engine::consumers(&engine.propellants, part, engine.max_mass_flow * probe)
+            })
+            .collect::<Vec<_>>();
+        let solution = flow::solve(vessel, data, &consumers);
+        let starved = (0..consumers.len()).any(|index| solution.is_starved(index, &consumers));
+        let shortest = solution
+            .draws
+            .iter()
+            .filter(|draw| draw.amount > Number::ZERO)
+            .map(|draw| {
+                let stored = vessel.parts[draw.part].resource(draw.resource).unwrap();
+                stored.amount / solution.drawn(draw.part, draw.resource) * probe
+            })
+            .min();
+        match shortest {
+            Some(shortest) if !starved && shortest > probe => return Some(shortest),
+            None => return None,
+            Some(_) => probe /= 2,
+        }
+    }
+    None
+}
@@ -0,0 +1,242 @@
+use crate::{
+    flow::{self, Consumer},
+    game_data::GameData,
+    physics::Environment,
+    registry::Registry,
+    resource::{ResourceDefinition, ResourceId, ResourceRate, ResourceRateConfig},
+    vector3::Vector3,
+    vessel::Vessel,
+    Number,
+};
+use serde::{Deserialize, Serialize};
+
+/// Watts per square meter of sunlight solar panels are rated at, about what reaches Kerbin
+pub const REFERENCE_FLUX: Number = Number::lit("1360");
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct SolarPanelConfig {
+    /// Made facing the sun head on at [`REFERENCE_FLUX`]
+    pub resources: Vec<ResourceRateConfig>,
+    /// Part space direction the cells face
+    #[serde(default = "default_normal")]
+    pub normal: [f64; 3],
+    /// Part space axis a tracking panel turns around to face the sun, `None` for fixed panels
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pivot: Option<[f64; 3]>,
+    /// Whether the panel starts stowed, making nothing until it's deployed
+    #[serde(default)]
+    pub deployable: bool,
+}
+
+fn default_normal() -> [f64; 3] {
+    [0.0, 0.0, 1.0]
+}
+
+#[derive(Debug, Clone)]
+pub struct SolarPanelDefinition {
+    pub resources: Vec<ResourceRate>,
+    /// Unit length, in part space
+    pub normal: Vector3,
+    /// Unit length, in part space
+    pub pivot: Option<Vector3>,
+    pub deployable: bool,
+}
+
+impl SolarPanelDefinition {
+    pub fn new(config: &SolarPanelConfig, resources: &Registry<ResourceDefinition>) -> Self {
+        let unit = |[x, y, z]: [f64; 3]| {
+            let vector = Vector3::new(
+                Number::from_num(x),
+                Number::from_num(y),
+                Number::from_num(z),
+            );
+            vector / vector.length()
+        };
+        Self {
+            resources: config
+                .resources
+                .iter()
+                .map(|rate| ResourceRate::new(rate, resources))
+                .collect(),
+            normal: unit(config.normal),
+            pivot: config.pivot.map(unit),
+            deployable: config.deployable,
+        }
+    }
+
+    /// Fraction of the rated output made with the sun along the part space `sun_direction`,
+    /// which is unit length or zero
+    pub fn facing(&self, sun_direction: Vector3) -> Number {
+        match self.pivot {
+            // Turning around the pivot lines the cells up with what's left of the sun direction
+            // across it
+            Some(pivot) => (sun_direction - pivot * sun_direction.dot(pivot)).length(),
+            None => sun_direction.dot(self.normal).max(Number::ZERO),
+        }
+    }
+}
+
+/// Keeps an uncrewed vessel under control for as long as it has power
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ProbeCoreConfig {
+    /// Used all the time
+    pub resources: Vec<ResourceRateConfig>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProbeCoreDefinition {
+    pub resources: Vec<ResourceRate>,
+}
+
+impl ProbeCoreDefinition {
+    pub fn new(config: &ProbeCoreConfig, resources: &Registry<ResourceDefinition>) -> Self {
+        Self {
+            resources: config
+                .resources
+                .iter()
+                .map(|rate| ResourceRate::new(rate, resources))
+                .collect(),
+        }
+    }
+}
+
+/// Deploys or stows every deployable solar panel on `vessel`
+pub fn deploy_solar_panels(vessel: &mut Vessel, data: &GameData, deployed: bool) {
+    for part in &mut vessel.parts {
+        if data.parts[part.part]
+            .solar_panel
+            .as_ref()
+            .is_some_and(|panel| panel.deployable)
+        {
+            part.deployed = deployed;
+        }
+    }
+}
+
+/// Units per second of each resource the solar panels on `vessel` make in `environment`
+pub fn solar_output(
+    vessel: &Vessel,
+    data: &GameData,
+    environment: &Environment,
+) -> Vec<(ResourceId, Number)> {
+    let mut output = Vec::new();
+    for (index, part) in vessel.parts.iter().enumerate() {
+        let Some(panel) = &data.parts[part.part].solar_panel else {
+            continue;
+        };
+        if panel.deployable && !part.deployed {
+            continue;
+        }
+        let sun_direction = vessel
+            .part_transform(index)
+            .inverse()
+            .transform_direction(environment.sun_direction);
+        let fraction = panel.facing(sun_direction) * environment.solar_flux / REFERENCE_FLUX;
+        for made in &panel.resources {
+            add_rate(&mut output, made.resource, made.rate * fraction);
+        }
+    }
+    output
+}
+
+/// Units per second of each resource the probe cores on `vessel` use
+pub fn probe_core_usage(vessel: &Vessel, data: &GameData) -> Vec<(ResourceId, Number)> {
+    let mut usage = Vec::new();
+    for part in &vessel.parts {
+        if let Some(core) = &data.parts[part.part].probe_core {
+            for used in &core.resources {
+                add_rate(&mut usage, used.resource, used.rate);
+            }
+        }
+    }
+    usage
+}
+
+fn add_rate(rates: &mut Vec<(ResourceId, Number)>, resource: ResourceId, rate: Number) {
+    match rates.iter_mut().find(|(id, _)| *id == resource) {
+        Some((_, total)) => *total += rate,
+        None => rates.push((resource, rate)),
+    }
+}
+
+/// Powers the probe cores on `vessel` and charges it from its solar panels for `dt` seconds
+pub fn run_electric(vessel: &mut Vessel, data: &GameData, environment: &Environment, dt: Number) {
+    let consumers = vessel
+        .parts
+        .iter()
+        .enumerate()
+        .filter_map(|(index, part)| {
+            let core = data.parts[part.part].probe_core.as_ref()?;
+            Some(
+                core.resources
+                    .iter()
+                    .map(|used| Consumer {
+                        part: index,
+                        resource: used.resource,
+                        demand: used.rate * dt,
+                    })
+                    .collect(),
+            )
+        })
+        .collect::<Vec<_>>();
+    flow::draw_together(vessel, data, &consumers);
+    // Charging after the draw keeps full batteries topped up
+    for (resource, rate) in solar_output(vessel, data, environment) {
+        flow::fill(vessel, resource, rate * dt);
+    }
+}
+
+/// Charges and drains `vessel` over `duration` seconds of time warp, from the average of what
+/// its solar panels make in each of `environments` sampled along the way and what its probe
+/// cores use
+///
+/// Nothing gets steered on rails, so every rate is steady and each resource changes in a
+/// straight line until it runs out or fills up.
+pub fn warp(vessel: &mut Vessel, data: &GameData, environments: &[Environment], duration: Number) {
+    let mut rates = Vec::new();
+    let samples = Number::from_num(environments.len().max(1));
+    for environment in environments {
+        for (resource, rate) in solar_output(vessel, data, environment) {
+            add_rate(&mut rates, resource, rate / samples);
+        }
+    }
+    for (resource, rate) in probe_core_usage(vessel, data) {
+        add_rate(&mut rates, resource, -rate);
+    }
+    for (resource, rate) in rates {
+        if rate > Number::ZERO {
+            flow::fill(vessel, resource, rate * duration);
+        } else {
+            flow::drain(vessel, resource, -rate * duration);
+        }
+    }
+}
+
+/// Whether `vessel` can be steered, which it can unless it's run by probe cores that all lack
+/// something they need
+pub fn has_control(vessel: &Vessel, data: &GameData) -> bool {
+    let mut cores = vessel
+        .parts
+        .iter()
+        .enumerate()
+        .filter_map(|(index, part)| Some((index, data.parts[part.part].probe_core.as_ref()?)))
+        .peekable();
+    if cores.peek().is_none() {
+        return true;
+    }
+    cores.any(|(index, core)| {
+        let consumers = core
+            .resources
+            .iter()
+            .map(|used| Consumer {
+                part: index,
+                resource: used.resource,
+                demand: used.rate,
+            })
+            .collect::<Vec<_>>();
+        let solution = flow::solve(vessel, data, &consumers);
+        (0..consumers.len()).all(|consumer| solution.supplied[consumer] > Number::ZERO)
+    })
+}
@@ -0,0 +1,223 @@
+use crate::{
+    float_curve::FloatCurve,
+    flow::{self, Consumer},
+    game_data::GameData,
+    registry::Registry,
+    resource::{ResourceDefinition, ResourceId},
+    vector3::Vector3,
+    vessel::Vessel,
+    Number,
+};
+use serde::{Deserialize, Serialize};
+
+/// Meters per second squared, used to turn specific impulse into exhaust velocity
+pub const STANDARD_GRAVITY: f64 = 9.80665;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct EngineConfig {
+    /// Newtons in vacuum
+    pub max_thrust: f64,
+    /// Specific impulse in seconds against pressure in atmospheres
+    pub isp: FloatCurve,
+    pub propellants: Vec<PropellantConfig>,
+    #[serde(default)]
+    pub min_throttle: f64,
+    /// Degrees the nozzle can tilt away from the part's +Y axis
+    #[serde(default)]
+    pub max_gimbal: f64,
+    /// Watts put into the part at full thrust, and in proportion to thrust below that
+    #[serde(default)]
+    pub heat: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct PropellantConfig {
+    pub name: String,
+    /// Units of this resource burned relative to the other propellants
+    pub ratio: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Propellant {
+    pub resource: ResourceId,
+    /// Units of the resource per kilogram of exhaust
+    pub units_per_kilogram: Number,
+}
+
+/// Thrust is produced along the part's +Y axis, through the part's origin
+#[derive(Debug, Clone)]
+pub struct EngineDefinition {
+    pub max_thrust: Number,
+    /// Specific impulse in seconds against pressure in atmospheres
+    pub isp: FloatCurve,
+    pub propellants: Vec<Propellant>,
+    pub min_throttle: Number,
+    /// Kilograms per second at full throttle
+    pub max_mass_flow: Number,
+    /// Radians
+    pub max_gimbal: Number,
+    /// Watts at full thrust
+    pub heat: Number,
+}
+
+impl EngineDefinition {
+    pub fn new(config: &EngineConfig, resources: &Registry<ResourceDefinition>) -> Self {
+        let max_thrust = Number::from_num(config.max_thrust);
+        let vacuum_isp = config.isp.evaluate(Number::ZERO);
+        Self {
+            max_thrust,
+            max_mass_flow: max_thrust / (vacuum_isp * Number::from_num(STANDARD_GRAVITY)),
+            isp: config.isp.clone(),
+            propellants: propellants(&config.propellants, resources),
+            min_throttle: Number::from_num(config.min_throttle),
+            max_gimbal: Number::from_num(config.max_gimbal.to_radians()),
+            heat: Number::from_num(config.heat),
+        }
+    }
+
+    /// Specific impulse in seconds at `pressure` atmospheres
+    pub fn isp(&self, pressure: Number) -> Number {
+        self.isp.evaluate(pressure)
+    }
+
+    /// Thrust in newtons at full throttle and `pressure` atmospheres
+    pub fn thrust(&self, pressure: Number) -> Number {
+        self.max_mass_flow * self.isp(pressure) * Number::from_num(STANDARD_GRAVITY)
+    }
+
+    /// Fraction of the maximum fuel flow used at `throttle`
+    pub fn flow_fraction(&self, throttle: Number) -> Number {
+        if throttle <= Number::ZERO {
+            Number::ZERO
+        } else {
+            self.min_throttle + (Number::ONE - self.min_throttle) * throttle.min(Number::ONE)
+        }
+    }
+}
+
+/// Turns propellant ratios into units per kilogram of exhaust
+pub fn propellants(
+    configs: &[PropellantConfig],
+    resources: &Registry<ResourceDefinition>,
+) -> Vec<Propellant> {
+    let propellants = configs
+        .iter()
+        .map(|propellant| {
+            let resource = resources
+                .id(&propellant.name)
+                .unwrap_or_else(|| panic!("unknown propellant {}", propellant.name));
+            (resource, Number::from_num(propellant.ratio))
+        })
+        .collect::<Vec<_>>();
+    let mass_per_ratio = propellants
+        .iter()
+        .map(|&(resource, ratio)| ratio * resources[resource].density)
+        .sum::<Number>();
+    propellants
+        .into_iter()
+        .map(|(resource, ratio)| Propellant {
+            resource,
+            units_per_kilogram: ratio / mass_per_ratio,
+        })
+        .collect()
+}
+
+/// What burning `mass` kilograms of exhaust made of `propellants` asks of the part at index
+/// `part`
+pub fn consumers(propellants: &[Propellant], part: usize, mass: Number) -> Vec<Consumer> {
+    propellants
+        .iter()
+        .map(|propellant| Consumer {
+            part,
+            resource: propellant.resource,
+            demand: mass * propellant.units_per_kilogram,
+        })
+        .collect()
+}
+
+/// Whether the engine at index `part` can still reach some of every propellant it burns
+pub fn has_propellant(vessel: &Vessel, data: &GameData, part: usize) -> bool {
+    let Some(engine) = &data.parts[vessel.parts[part].part].engine else {
+        return false;
+    };
+    // Draws get scaled down in fixed point, so tanks can be left with crumbs of propellant that
+    // no engine could run on
+    const CRUMBS: Number = Number::lit("0.000001");
+    let consumers = consumers(&engine.propellants, part, Number::ONE);
+    flow::solve(vessel, data, &consumers)
+        .supplied
+        .iter()
+        .all(|&supplied| supplied > CRUMBS)
+}
+
+/// Newtons the activated engines that still have propellant make at full throttle
+pub fn available_thrust(vessel: &Vessel, data: &GameData, pressure: Number) -> Number {
+    vessel
+        .parts
+        .iter()
+        .enumerate()
+        .filter(|&(index, part)| part.activated && has_propellant(vessel, data, index))
+        .filter_map(|(_, part)| data.parts[part.part].engine.as_ref())
+        .map(|engine| engine.thrust(pressure))
+        .sum()
+}
+
+/// What an engine did during one tick
+#[derive(Debug, Clone, Copy)]
+pub struct EngineOutput {
+    pub part: usize,
+    /// Newtons
+    pub thrust: Number,
+    /// Part space direction the thrust pushes the part in
+    pub direction: Vector3,
+    /// The engine was throttled up but could not get all of its propellants
+    pub flameout: bool,
+}
+
+/// Burns propellant for every activated engine on `vessel` for `dt` seconds
+pub fn run_engines(
+    vessel: &mut Vessel,
+    data: &GameData,
+    pressure: Number,
+    dt: Number,
+) -> Vec<EngineOutput> {
+    let engines = vessel
+        .parts
+        .iter()
+        .enumerate()
+        .filter(|(_, part)| part.activated)
+        .filter_map(|(index, part)| Some((index, data.parts[part.part].engine.as_ref()?)))
+        .collect::<Vec<_>>();
+
+    let mut flow_fractions = Vec::with_capacity(engines.len());
+    let consumers = engines
+        .iter()
+        .map(|&(part, engine)| {
+            let flow_fraction = engine.flow_fraction(vessel.throttle);
+            flow_fractions.push(flow_fraction);
+            consumers(
+                &engine.propellants,
+                part,
+                engine.max_mass_flow * flow_fraction * dt,
+            )
+        })
+        .collect::<Vec<_>>();
+    let supplied_fractions = flow::draw_together(vessel, data, &consumers);
+
+    engines
+        .iter()
+        .zip(flow_fractions)
+        .zip(supplied_fractions)
+        .map(|((&(part, engine), flow_fraction), supplied_fraction)| {
+            let mass_flow = engine.max_mass_flow * flow_fraction * supplied_fraction;
+            EngineOutput {
+                part,
+                thrust: mass_flow * engine.isp(pressure) * Number::from_num(STANDARD_GRAVITY),
+                direction: Vector3::Y,
+                flameout: flow_fraction > Number::ZERO && supplied_fraction < Number::ONE,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,186 @@
+use crate::Number;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Keyframe {
+    pub time: Number,
+    pub value: Number,
+    /// Slope arriving at this key, worked out from the neighbouring keys when `None`
+    pub in_tangent: Option<Number>,
+    /// Slope leaving this key, worked out from the neighbouring keys when `None`
+    pub out_tangent: Option<Number>,
+}
+
+impl Keyframe {
+    pub fn new(time: Number, value: Number) -> Self {
+        Self {
+            time,
+            value,
+            in_tangent: None,
+            out_tangent: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Interpolation {
+    #[default]
+    Hermite,
+    Linear,
+}
+
+/// A piecewise curve through keyframes, holding the first and last values outside of them
+///
+/// In json a curve is either a list of `[time, value]` or `[time, value, in_tangent,
+/// out_tangent]` keys, which are interpolated with Hermite splines, or an object with
+/// `"interpolation"` and `"keys"` fields.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(try_from = "FloatCurveConfig", into = "FloatCurveConfig")]
+pub struct FloatCurve {
+    /// Sorted by time
+    keys: Vec<Keyframe>,
+    interpolation: Interpolation,
+}
+
+impl FloatCurve {
+    pub fn new(mut keys: Vec<Keyframe>, interpolation: Interpolation) -> Self {
+        assert!(!keys.is_empty(), "curves need at least one keyframe");
+        keys.sort_by_key(|key| key.time);
+        Self {
+            keys,
+            interpolation,
+        }
+    }
+
+    pub fn constant(value: Number) -> Self {
+        Self::new(
+            vec![Keyframe::new(Number::ZERO, value)],
+            Interpolation::Linear,
+        )
+    }
+
+    pub fn keys(&self) -> &[Keyframe] {
+        &self.keys
+    }
+
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+
+    pub fn evaluate(&self, time: Number) -> Number {
+        let index = self.keys.partition_point(|key| key.time <= time);
+        if index == 0 {
+            return self.keys[0].value;
+        }
+        if index == self.keys.len() {
+            return self.keys[index - 1].value;
+        }
+
+        let start = self.keys[index - 1];
+        let end = self.keys[index];
+        let duration = end.time - start.time;
+        let t = (time - start.time) / duration;
+        match self.interpolation {
+            Interpolation::Linear => start.value + (end.value - start.value) * t,
+            Interpolation::Hermite => {
+                let start_tangent = start
+                    .out_tangent
+                    .unwrap_or_else(|| self.automatic_tangent(index - 1));
+                let end_tangent = end
+                    .in_tangent
+                    .unwrap_or_else(|| self.automatic_tangent(index));
+                let t2 = t * t;
+                let t3 = t2 * t;
+                let two = Number::from_num(2);
+                let three = Number::from_num(3);
+                (two * t3 - three * t2 + Number::ONE) * start.value
+                    + (t3 - two * t2 + t) * duration * start_tangent
+                    + (three * t2 - two * t3) * end.value
+                    + (t3 - t2) * duration * end_tangent
+            }
+        }
+    }
+
+    /// Slope between the neighbours of the key at `index`, or towards its only neighbour at
+    /// either end of the curve
+    fn automatic_tangent(&self, index: usize) -> Number {
+        let before = self.keys[index.saturating_sub(1)];
+        let after = self.keys[(index + 1).min(self.keys.len() - 1)];
+        if after.time == before.time {
+            Number::ZERO
+        } else {
+            (after.value - before.value) / (after.time - before.time)
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum FloatCurveConfig {
+    Keys(Vec<Vec<f64>>),
+    Full {
+        #[serde(default)]
+        interpolation: Interpolation,
+        keys: Vec<Vec<f64>>,
+    },
+}
+
+impl TryFrom<FloatCurveConfig> for FloatCurve {
+    type Error = String;
+
+    fn try_from(config: FloatCurveConfig) -> Result<Self, Self::Error> {
+        let (keys, interpolation) = match config {
+            FloatCurveConfig::Keys(keys) => (keys, Interpolation::Hermite),
+            FloatCurveConfig::Full {
+                interpolation,
+                keys,
+            } => (keys, interpolation),
+        };
+        if keys.is_empty() {
+            return Err("curves need at least one keyframe".into());
+        }
+        let keys = keys
+            .into_iter()
+            .map(|key| match *key.as_slice() {
+                [time, value] => Ok(Keyframe::new(Number::from_num(time), Number::from_num(value))),
+                [time, value, in_tangent, out_tangent] => Ok(Keyframe {
+                    time: Number::from_num(time),
+                    value: Number::from_num(value),
+                    in_tangent: Some(Number::from_num(in_tangent)),
+                    out_tangent: Some(Number::from_num(out_tangent)),
+                }),
+                _ => Err(format!(
+                    "keyframes are [time, value] or [time, value, in_tangent, out_tangent], got {key:?}"
+                )),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self::new(keys, interpolation))
+    }
+}
+
+impl From<FloatCurve> for FloatCurveConfig {
+    fn from(curve: FloatCurve) -> Self {
+        let keys = curve
+            .keys
+            .iter()
+            .enumerate()
+            .map(|(index, key)| {
+                let mut values = vec![key.time.to_num(), key.value.to_num()];
+                if key.in_tangent.is_some() || key.out_tangent.is_some() {
+                    let automatic = curve.automatic_tangent(index);
+                    values.push(key.in_tangent.unwrap_or(automatic).to_num());
+                    values.push(key.out_tangent.unwrap_or(automatic).to_num());
+                }
+                values
+            })
+            .collect();
+        match curve.interpolation {
+            Interpolation::Hermite => Self::Keys(keys),
+            interpolation => Self::Full {
+                interpolation,
+                keys,
+            },
+        }
+    }
+}
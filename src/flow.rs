@@ -0,0 +1,249 @@
+use crate::{
+    game_data::GameData,
+    resource::{FlowMode, PartResource, ResourceId},
+    vessel::Vessel,
+    Number,
+};
+use std::collections::VecDeque;
+
+/// A part asking for `demand` units of `resource` this tick
+#[derive(Debug, Clone, Copy)]
+pub struct Consumer {
+    pub part: usize,
+    pub resource: ResourceId,
+    pub demand: Number,
+}
+
+/// Units of `resource` taken out of the part at index `part`
+#[derive(Debug, Clone, Copy)]
+pub struct Draw {
+    pub part: usize,
+    pub resource: ResourceId,
+    pub amount: Number,
+}
+
+#[derive(Debug, Clone)]
+pub struct FlowSolution {
+    /// What each consumer received, in the same order as they were given to [`solve`]
+    pub supplied: Vec<Number>,
+    pub draws: Vec<Draw>,
+}
+
+impl FlowSolution {
+    /// Whether the consumer at `index` got less than it asked for
+    pub fn is_starved(&self, index: usize, consumers: &[Consumer]) -> bool {
+        self.supplied[index] < consumers[index].demand
+    }
+
+    /// Total drawn from the part at index `part`
+    pub fn drawn(&self, part: usize, resource: ResourceId) -> Number {
+        self.draws
+            .iter()
+            .filter(|draw| draw.part == part && draw.resource == resource)
+            .map(|draw| draw.amount)
+            .sum()
+    }
+
+    pub fn apply(&self, vessel: &mut Vessel) {
+        for draw in &self.draws {
+            let stored = vessel.parts[draw.part].resource_mut(draw.resource).unwrap();
+            stored.amount = (stored.amount - draw.amount).max(Number::ZERO);
+        }
+    }
+}
+
+/// Works out which parts supply each consumer without changing the vessel
+///
+/// Consumers are served in order, so earlier consumers get priority when resources run short.
+/// Parts within the same priority group are drained in proportion to what they hold, so they
+/// run dry together.
+pub fn solve(vessel: &Vessel, data: &GameData, consumers: &[Consumer]) -> FlowSolution {
+    let mut remaining = vessel
+        .parts
+        .iter()
+        .map(|part| part.resources.clone())
+        .collect::<Vec<_>>();
+    let mut supplied = Vec::with_capacity(consumers.len());
+    let mut draws = Vec::new();
+
+    for consumer in consumers {
+        let mut demand = consumer.demand.max(Number::ZERO);
+        for group in source_groups(
+            vessel,
+            consumer.part,
+            data.resources[consumer.resource].config.flow_mode,
+        ) {
+            if demand == Number::ZERO {
+                break;
+            }
+
+            let available = group
+                .iter()
+                .map(|&part| stored_amount(&remaining[part], consumer.resource))
+                .sum::<Number>();
+            if available == Number::ZERO {
+                continue;
+            }
+
+            let taken = demand.min(available);
+            let mut left = taken;
+            let sources = group
+                .iter()
+                .copied()
+                .filter(|&part| stored_amount(&remaining[part], consumer.resource) > Number::ZERO)
+                .collect::<Vec<_>>();
+            for (i, &part) in sources.iter().enumerate() {
+                let stored = remaining[part]
+                    .iter_mut()
+                    .find(|stored| stored.resource == consumer.resource)
+                    .unwrap();
+                // The last source takes whatever rounding left over
+                let amount = if i + 1 == sources.len() {
+                    left.min(stored.amount)
+                } else {
+                    (stored.amount * taken / available)
+                        .min(stored.amount)
+                        .min(left)
+                };
+                stored.amount -= amount;
+                left -= amount;
+                draws.push(Draw {
+                    part,
+                    resource: consumer.resource,
+                    amount,
+                });
+            }
+            demand -= taken - left;
+        }
+        supplied.push(consumer.demand.max(Number::ZERO) - demand);
+    }
+
+    FlowSolution { supplied, draws }
+}
+
+/// Draws resources for groups of consumers that each need all of their resources together,
+/// like an engine burning fuel and oxidizer
+///
+/// Every group only takes as much of each resource as its scarcest one allows. Returns the
+/// fraction of its demand each group received.
+pub fn draw_together(
+    vessel: &mut Vessel,
+    data: &GameData,
+    groups: &[Vec<Consumer>],
+) -> Vec<Number> {
+    let mut consumers = groups.concat();
+    let solution = solve(vessel, data, &consumers);
+
+    let mut fractions = Vec::with_capacity(groups.len());
+    let mut index = 0;
+    for group in groups {
+        let mut fraction = Number::ONE;
+        for consumer in group {
+            if consumer.demand > Number::ZERO {
+                fraction = fraction.min(solution.supplied[index] / consumer.demand);
+            }
+            index += 1;
+        }
+        fractions.push(fraction);
+    }
+
+    let mut index = 0;
+    for (group, &fraction) in groups.iter().zip(&fractions) {
+        for consumer in &mut consumers[index..index + group.len()] {
+            consumer.demand *= fraction;
+        }
+        index += group.len();
+    }
+    solve(vessel, data, &consumers).apply(vessel);
+    fractions
+}
+
+/// Puts up to `amount` units of `resource` into every part of `vessel` with room for it, in
+/// proportion to the room each has so they fill up together, returning how much fit
+pub fn fill(vessel: &mut Vessel, resource: ResourceId, amount: Number) -> Number {
+    let room = |stored: &PartResource| stored.capacity - stored.amount;
+    spread(vessel, resource, amount, room, Number::ONE)
+}
+
+/// Takes up to `amount` units of `resource` out of every part of `vessel` holding it, in
+/// proportion to what each holds so they run dry together, returning how much was taken
+pub fn drain(vessel: &mut Vessel, resource: ResourceId, amount: Number) -> Number {
+    let held = |stored: &PartResource| stored.amount;
+    spread(vessel, resource, amount, held, -Number::ONE)
+}
+
+/// Moves a share of `amount` into every stored `resource` when `sign` is 1, or out of it when
+/// it's -1, shared out by `share` which also limits how much each one can move, returning the
+/// total moved
+fn spread(
+    vessel: &mut Vessel,
+    resource: ResourceId,
+    amount: Number,
+    share: impl Fn(&PartResource) -> Number,
+    sign: Number,
+) -> Number {
+    let total = vessel
+        .parts
+        .iter()
+        .filter_map(|part| part.resource(resource))
+        .map(&share)
+        .sum::<Number>();
+    if total <= Number::ZERO || amount <= Number::ZERO {
+        return Number::ZERO;
+    }
+    let moved = amount.min(total);
+    let mut left = moved;
+    for stored in vessel
+        .parts
+        .iter_mut()
+        .filter_map(|part| part.resource_mut(resource))
+    {
+        let part_share = (share(stored) * moved / total).min(share(stored)).min(left);
+        stored.amount = (stored.amount + part_share * sign).clamp(Number::ZERO, stored.capacity);
+        left -= part_share;
+    }
+    moved - left
+}
+
+fn stored_amount(resources: &[PartResource], resource: ResourceId) -> Number {
+    resources
+        .iter()
+        .find(|stored| stored.resource == resource)
+        .map_or(Number::ZERO, |stored| stored.amount)
+}
+
+/// Parts a consumer can draw from, grouped by priority with the first group drained first
+fn source_groups(vessel: &Vessel, consumer: usize, flow_mode: FlowMode) -> Vec<Vec<usize>> {
+    match flow_mode {
+        FlowMode::NoFlow => vec![vec![consumer]],
+        FlowMode::AllVessel => vec![(0..vessel.parts.len()).collect()],
+        FlowMode::StackPriority => {
+            let mut distances = vec![None; vessel.parts.len()];
+            distances[consumer] = Some(0);
+            let mut queue = VecDeque::from([consumer]);
+            while let Some(part) = queue.pop_front() {
+                // Resources can be taken from a part that blocks crossfeed, but not through it
+                if part != consumer && !vessel.parts[part].crossfeed {
+                    continue;
+                }
+                let distance = distances[part].unwrap();
+                for neighbour in vessel.neighbours(part) {
+                    if distances[neighbour].is_none() {
+                        distances[neighbour] = Some(distance + 1);
+                        queue.push_back(neighbour);
+                    }
+                }
+            }
+
+            let farthest = distances.iter().flatten().copied().max().unwrap_or(0);
+            (0..=farthest)
+                .rev()
+                .map(|distance| {
+                    (0..vessel.parts.len())
+                        .filter(|&part| distances[part] == Some(distance))
+                        .collect()
+                })
+                .collect()
+        }
+    }
+}
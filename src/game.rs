@@ -1,10 +1,24 @@
 use crate::{
-    motor::Transform, vector3::Vector3, Camera, GpuCamera, GpuMesh, GpuMeshes, GpuVertices, Mesh,
-    Number, Vertex,
+    body::{self, Body, BodyId},
+    contact,
+    control::ControlInput,
+    electric,
+    game_data::GameData,
+    joint,
+    light::Sunlight,
+    motor::Transform,
+    part::PartInstance,
+    physics::Environment,
+    planet_mesh::{LodSettings, PlanetMesh},
+    sas::{Reference, Sas, SasMode},
+    save::SaveFile,
+    staging, thermal,
+    vector3::Vector3,
+    vessel::Vessel,
+    Camera, GpuCamera, GpuMesh, GpuMeshes, GpuVertices, Mesh, Number, Vertex,
 };
 use encase::{ShaderSize, ShaderType, StorageBuffer, UniformBuffer};
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::ops::Range;
 use std::path::Path;
 use std::sync::Arc;
 use winit::window::Window;
@@ -18,8 +32,30 @@ pub struct Game {
     camera: Camera,
     camera_buffer: wgpu::Buffer,
     camera_bind_group: wgpu::BindGroup,
-    meshes: HashMap<String, Mesh>,
+    data: GameData,
+    meshes: Vec<Mesh>,
+    /// Seconds since the epoch the bodies' orbits start from
+    universal_time: Number,
+    /// Where every body in `data` is right now
+    bodies: Vec<Body>,
+    /// What the vessels fly around, the body of the launch site they spawned at
+    body: Option<BodyId>,
+    /// Where the camera sits in the body-fixed frame of `body`, keeping up with the ground
+    camera_anchor: Transform,
+    vessels: Vec<Vessel>,
+    /// What the player is pressing for the vessel being flown
+    control: ControlInput,
+    sas: Option<Sas>,
+    /// Surface of `body` around the camera
+    planet: Option<PlanetMesh>,
+    planet_textures: Option<wgpu::BindGroup>,
     vertices: Vec<Vertex>,
+    /// How many of `vertices` belong to parts, with the planet's after them
+    part_vertex_count: usize,
+    /// Body-fixed origin and vertices of every chunk of `planet` in `vertices`
+    planet_chunks: Vec<(Vector3, Range<u32>)>,
+    /// How many of `vertices` are already in `vertices_buffer`
+    uploaded_vertex_count: usize,
     mesh_buffer: wgpu::Buffer,
     vertices_buffer: wgpu::Buffer,
     mesh_bind_group_layout: wgpu::BindGroupLayout,
@@ -28,16 +64,6 @@ pub struct Game {
     render_pipeline: wgpu::RenderPipeline,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(deny_unknown_fields)]
-struct MeshConfig {
-    name: String,
-    model: String,
-    title: String,
-    texture_color_map: String,
-    discription: String,
-}
-
 impl Game {
     pub async fn new(window: Arc<Window>) -> Self {
         let size = window.inner_size();
@@ -93,7 +119,7 @@ impl Game {
                 label: Some("Camera Bind Group Layout"),
                 entries: &[wgpu::BindGroupLayoutEntry {
                     binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Uniform,
                         has_dynamic_offset: false,
@@ -247,8 +273,21 @@ impl Game {
             },
             camera_buffer,
             camera_bind_group,
-            meshes: HashMap::new(),
+            data: GameData::new(),
+            meshes: Vec::new(),
+            universal_time: Number::ZERO,
+            bodies: Vec::new(),
+            body: None,
+            camera_anchor: Transform::IDENTITY,
+            vessels: Vec::new(),
+            control: ControlInput::NEUTRAL,
+            sas: None,
+            planet: None,
+            planet_textures: None,
             vertices: Vec::new(),
+            part_vertex_count: 0,
+            planet_chunks: Vec::new(),
+            uploaded_vertex_count: 0,
             mesh_buffer,
             vertices_buffer,
             mesh_bind_group_layout,
@@ -257,61 +296,137 @@ impl Game {
             render_pipeline,
         };
         app.load_game(Path::new("GameData"));
-        
-        let fuel_tank = app.meshes.get_mut("mk1-fl-100").unwrap();
-        fuel_tank.transform = fuel_tank.transform.apply(Transform::translation(Vector3::Y * Number::from_num(-2)));
+
+        let cockpit = app.data.parts.id("mk1-cockpit").unwrap();
+        let fuel_tank = app.data.parts.id("mk1-fl-100").unwrap();
+        let decoupler = app.data.parts.id("mk1-td-10").unwrap();
+        let engine = app.data.parts.id("mk1-lv-60").unwrap();
+        let mut vessel = Vessel::new(cockpit, Transform::IDENTITY, &app.data);
+        let upper_tank = vessel.attach(
+            0,
+            fuel_tank,
+            Transform::translation(Vector3::Y * Number::from_num(-2)),
+            &app.data,
+        );
+        let decoupler = vessel.attach(
+            upper_tank,
+            decoupler,
+            Transform::translation(Vector3::Y * Number::from_num(-3.1)),
+            &app.data,
+        );
+        let lower_tank = vessel.attach(
+            decoupler,
+            fuel_tank,
+            Transform::translation(Vector3::Y * Number::from_num(-4.2)),
+            &app.data,
+        );
+        vessel.attach(
+            lower_tank,
+            engine,
+            Transform::translation(Vector3::Y * Number::from_num(-6.2)),
+            &app.data,
+        );
+        staging::assign_stages(&mut vessel, &app.data);
+        vessel.throttle = Number::ONE;
+        let site = &app.data.launch_sites[app.data.launch_sites.id("launch-pad").unwrap()];
+        body::center_on(&mut app.bodies, site.body);
+        let body = &app.bodies[site.body.index()];
+        site.spawn(&mut vessel, body, &app.data);
+        // Looking at the vessel from a few meters away
+        let camera = Transform::translation(
+            -(Vector3::Z * Number::from_num(5) + Vector3::Y * Number::from_num(2)),
+        )
+        .apply(vessel.transform);
+        app.camera_anchor = body.to_body_fixed_transform(camera);
+        app.body = Some(site.body);
+        app.vessels.push(vessel);
+
+        let image = match &app.data.bodies[site.body].texture_color_map {
+            Some(path) => image::load_from_memory(&std::fs::read(path).unwrap()).unwrap(),
+            // Plain gray for bodies without a map of their own
+            None => image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+                1,
+                1,
+                image::Rgba([128, 128, 128, 255]),
+            )),
+        };
+        // Longitude wraps around, so the planet's texture coordinates can reach past its edges
+        app.planet_textures = Some(app.load_texture(&image, wgpu::AddressMode::Repeat));
+        app.planet = Some(PlanetMesh::new(LodSettings::default()));
         app
     }
 
     fn load_game(&mut self, path: &Path) {
-        if path.is_dir() {
-            for entry in std::fs::read_dir(path).unwrap() {
-                let entry = entry.unwrap();
-                let path = entry.path();
-                self.load_game(&path)
-            }
-        } else if path
-            .extension()
-            .is_some_and(|path| path.to_str() == Some("json"))
-        {
-            let part: MeshConfig =
-                serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
-            self.load_mesh(
-                &part.name,
-                &path.parent().unwrap().join(part.model),
-                &path.parent().unwrap().join(part.texture_color_map),
-                Transform::translation(Vector3::Z * Number::from_num(5) + Vector3::Y * Number::from_num(2)),
-            );
+        let data = GameData::load(path);
+        for (_, part) in data.parts.iter() {
+            let mesh = self.load_mesh(&part.vertices, &part.texture_color_map);
+            self.meshes.push(mesh);
         }
+        self.part_vertex_count = self.vertices.len();
+        self.bodies = body::propagate(&data.bodies, self.universal_time);
+        self.data = data;
     }
 
-    fn load_mesh(
-        &mut self,
-        name: &str,
-        path: &Path,
-        texture_color_map_path: &Path,
-        transform: Transform,
-    ) -> usize {
-        let index = self.meshes.len();
-        let start_vertex_index = self.vertices.len() as _;
+    /// Stages the vessel being flown, adding anything it drops to the world
+    pub fn activate_next_stage(&mut self) {
+        let Some(vessel) = self.vessels.first_mut() else {
+            return;
+        };
+        let separated = staging::activate_next_stage(vessel, &self.data);
+        self.vessels.extend(separated);
+    }
 
-        let object = obj::Obj::load(path).unwrap();
+    /// Steers the vessel being flown
+    pub fn set_control(&mut self, control: ControlInput) {
+        self.control = control;
+    }
 
-        assert_eq!(object.data.objects.len(), 1);
-        assert_eq!(object.data.objects[0].groups.len(), 1);
-        let vertices = object.data.objects[0].groups[0]
-            .polys
-            .iter()
-            .flat_map(|poly| &poly.0)
-            .map(|index| Vertex {
-                position: object.data.position[index.0].into(),
-                normal: object.data.normal[index.2.unwrap()].into(),
-                texture_coords: object.data.texture[index.1.unwrap()].into(),
-            })
-            .collect::<Vec<_>>();
+    /// Turns stability assist on, holding the current attitude, or off again
+    pub fn toggle_sas(&mut self) {
+        self.sas = match (self.sas, self.vessels.first()) {
+            (None, Some(vessel)) => Some(Sas::new(SasMode::Hold(vessel.transform))),
+            _ => None,
+        };
+    }
+
+    /// Deploys the solar panels of the vessel being flown, or stows them if any are out
+    pub fn toggle_solar_panels(&mut self) {
+        let Some(vessel) = self.vessels.first_mut() else {
+            return;
+        };
+        let deployed = vessel.parts.iter().any(|part| part.deployed);
+        electric::deploy_solar_panels(vessel, &self.data, !deployed);
+    }
+
+    pub fn save(&self, path: &Path) {
+        SaveFile::new(&self.vessels, &self.data).save(path);
+    }
+
+    pub fn load_save(&mut self, path: &Path) {
+        self.vessels = SaveFile::load(path).vessels(&self.data);
+    }
+
+    fn load_mesh(&mut self, vertices: &[Vertex], texture_color_map_path: &Path) -> Mesh {
+        let start_vertex_index = self.vertices.len() as _;
 
         let image =
             image::load_from_memory(&std::fs::read(texture_color_map_path).unwrap()).unwrap();
+        let textures = self.load_texture(&image, wgpu::AddressMode::ClampToEdge);
+
+        self.vertices.extend_from_slice(vertices);
+
+        Mesh {
+            start_vertex_index,
+            triangle_count: vertices.len() as _,
+            textures,
+        }
+    }
+
+    fn load_texture(
+        &self,
+        image: &image::DynamicImage,
+        address_mode_u: wgpu::AddressMode,
+    ) -> wgpu::BindGroup {
         let rgba_image = image.flipv().to_rgba8();
 
         let texture = self.device.create_texture(&wgpu::TextureDescriptor {
@@ -340,7 +455,7 @@ impl Game {
         );
         let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
         let texture_sampler = self.device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_u,
             address_mode_v: wgpu::AddressMode::ClampToEdge,
             address_mode_w: wgpu::AddressMode::ClampToEdge,
             mag_filter: wgpu::FilterMode::Linear,
@@ -349,7 +464,7 @@ impl Game {
             ..Default::default()
         });
 
-        let textures = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+        self.device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("Mesh Texture Bind Group"),
             layout: &self.texture_bind_group_layout,
             entries: &[
@@ -362,27 +477,7 @@ impl Game {
                     resource: wgpu::BindingResource::Sampler(&texture_sampler),
                 },
             ],
-        });
-
-        if self
-            .meshes
-            .insert(
-                name.into(),
-                Mesh {
-                    start_vertex_index,
-                    triangle_count: vertices.len() as _,
-                    textures,
-                    transform,
-                },
-            )
-            .is_some()
-        {
-            panic!("{name} part defined multiple times");
-        }
-
-        self.vertices.extend(vertices);
-
-        index
+        })
     }
 
     pub fn resize(&mut self, width: u32, height: u32) {
@@ -410,10 +505,17 @@ impl Game {
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
 
+        let sunlight = Sunlight::new(&self.data.bodies, &self.bodies);
+
         // Upload Camera
         {
-            let Camera { transform: _ } = self.camera;
+            let Camera { transform } = self.camera;
             let size = output.texture.size();
+            let sun_direction = sunlight
+                .direction(transform.position())
+                .map_or(Vector3::ZERO, |direction| {
+                    transform.inverse().transform_direction(direction)
+                });
 
             UniformBuffer::new(
                 self.queue
@@ -423,10 +525,41 @@ impl Game {
             )
             .write(&GpuCamera {
                 aspect: size.width as f32 / size.height as f32,
+                sun_direction: cgmath::Vector3::new(
+                    sun_direction.x.to_num(),
+                    sun_direction.y.to_num(),
+                    sun_direction.z.to_num(),
+                ),
             })
             .unwrap();
         }
 
+        let instances = self
+            .vessels
+            .iter()
+            .flat_map(Vessel::instances)
+            .collect::<Vec<_>>();
+
+        // Planet chunks are drawn first since nothing sorts by depth
+        let mut chunks = Vec::new();
+        if let Some(body) = self.body.map(|body| &self.bodies[body.index()]) {
+            let camera_position = self.camera.transform.position();
+            let inverse_camera_rotation = self.camera.transform.rotation_part().inverse();
+            for (origin, vertices) in &self.planet_chunks {
+                // Offsetting from the camera before turning keeps the far away body's position
+                // from swamping the chunk's
+                let origin = body.to_world_position(*origin);
+                let offset = origin - camera_position;
+                let transform = body
+                    .rotation
+                    .rotation_part()
+                    .apply(Transform::translation(offset))
+                    .apply(inverse_camera_rotation);
+                let exposure = sunlight.solar_exposure(origin);
+                chunks.push((transform, exposure, vertices.clone()));
+            }
+        }
+
         // Upload meshes and vertices
         {
             let mut recreate_bind_group = false;
@@ -435,18 +568,21 @@ impl Game {
             {
                 let inverse_camera = self.camera.transform.inverse();
                 let meshes = GpuMeshes {
-                    meshes: &self
-                        .meshes
-                        .values()
-                        .map(
-                            |&Mesh {
-                                 start_vertex_index: _,
-                                 triangle_count: _,
-                                 textures: _,
-                                 transform,
-                             }| GpuMesh {
-                                transform: transform.apply(inverse_camera).into(),
-                            },
+                    meshes: &chunks
+                        .iter()
+                        .map(|&(transform, exposure, _)| GpuMesh {
+                            transform: transform.into(),
+                            exposure: exposure.to_num(),
+                        })
+                        .chain(
+                            instances
+                                .iter()
+                                .map(|&PartInstance { part: _, transform }| GpuMesh {
+                                    transform: transform.apply(inverse_camera).into(),
+                                    exposure: sunlight
+                                        .solar_exposure(transform.position())
+                                        .to_num(),
+                                }),
                         )
                         .collect::<Vec<_>>(),
                 };
@@ -471,13 +607,12 @@ impl Game {
                 .unwrap();
             }
 
-            // Upload vertices
-            {
-                let vertices = GpuVertices {
+            // Upload vertices, only the ones that changed since the last frame
+            if self.uploaded_vertex_count < self.vertices.len() {
+                let size = GpuVertices {
                     vertices: &self.vertices,
-                };
-
-                let size = vertices.size();
+                }
+                .size();
                 if size.get() > self.vertices_buffer.size() {
                     self.vertices_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
                         label: Some("Vertices Buffer"),
@@ -486,15 +621,22 @@ impl Game {
                         mapped_at_creation: false,
                     });
                     recreate_bind_group = true;
+                    self.uploaded_vertex_count = 0;
                 }
+
+                let vertices = GpuVertices {
+                    vertices: &self.vertices[self.uploaded_vertex_count..],
+                };
+                let offset = self.uploaded_vertex_count as u64 * Vertex::SHADER_SIZE.get();
                 StorageBuffer::new(
                     self.queue
-                        .write_buffer_with(&self.vertices_buffer, 0, size)
+                        .write_buffer_with(&self.vertices_buffer, offset, vertices.size())
                         .unwrap()
                         .as_mut(),
                 )
                 .write(&vertices)
                 .unwrap();
+                self.uploaded_vertex_count = self.vertices.len();
             }
 
             if recreate_bind_group {
@@ -544,7 +686,15 @@ impl Game {
             render_pass.set_pipeline(&self.render_pipeline);
             render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
             render_pass.set_bind_group(1, &self.mesh_bind_group, &[]);
-            for (i, mesh) in self.meshes.values().enumerate() {
+            if let Some(textures) = &self.planet_textures {
+                render_pass.set_bind_group(2, textures, &[]);
+                for (i, (_, _, vertices)) in chunks.iter().enumerate() {
+                    render_pass.draw(vertices.clone(), i as u32..i as u32 + 1);
+                }
+            }
+            for (i, instance) in instances.iter().enumerate() {
+                let mesh = &self.meshes[instance.part.index()];
+                let i = chunks.len() + i;
                 render_pass.set_bind_group(2, &mesh.textures, &[]);
                 render_pass.draw(
                     mesh.start_vertex_index..mesh.start_vertex_index + mesh.triangle_count,
@@ -560,6 +710,11 @@ impl Game {
 
     pub fn update(&mut self, _time: std::time::Duration, dt: std::time::Duration) {
         let ts = Number::from_num(dt.as_secs_f64());
+        self.universal_time += ts;
+        self.bodies = body::propagate(&self.data.bodies, self.universal_time);
+        if let Some(body) = self.body {
+            body::center_on(&mut self.bodies, body);
+        }
 
         // let position = Vector3::new(
         //     Number::from_num((time.as_secs_f64() * 2.0).sin() * 4.0),
@@ -568,13 +723,62 @@ impl Game {
         // );
         // self.camera.transform = Motor::translation(position);
 
-        let cockpit = self.meshes.get_mut("mk1-cockpit").unwrap();
-        cockpit.transform = cockpit
-            .transform
-            .pre_apply(Transform::rotation_xy(ts * Number::from_num(0)))
-            .pre_apply(Transform::rotation_xz(ts * Number::from_num(1)))
-            .pre_apply(Transform::rotation_yz(ts * Number::from_num(0)))
-            .normalized();
+        let body = self.body.map(|body| &self.bodies[body.index()]);
+        let sunlight = Sunlight::new(&self.data.bodies, &self.bodies);
+        let environment = |vessel: &Vessel| {
+            let position = vessel.transform.position();
+            body.map_or(Environment::VACUUM, |body| body.environment(position))
+                .with_sunlight(&sunlight, position)
+        };
+        if let Some(body) = body {
+            self.camera.transform = body.to_world_transform(self.camera_anchor);
+            if let Some(planet) = &mut self.planet {
+                let changed = planet.update(
+                    body,
+                    body.to_body_fixed_position(self.camera.transform.position()),
+                );
+                // Chunks go after the parts' vertices, so only they need uploading again
+                if changed {
+                    self.vertices.truncate(self.part_vertex_count);
+                    self.planet_chunks.clear();
+                    for chunk in planet.chunks() {
+                        let start = self.vertices.len() as u32;
+                        self.vertices.extend_from_slice(&chunk.vertices);
+                        self.planet_chunks
+                            .push((chunk.origin, start..self.vertices.len() as u32));
+                    }
+                    self.uploaded_vertex_count =
+                        self.uploaded_vertex_count.min(self.part_vertex_count);
+                }
+            }
+        }
 
+        if let Some(vessel) = self.vessels.first_mut() {
+            vessel.control = self.control;
+            if let Some(sas) = &mut self.sas {
+                let rotating = self.control.rotation() != Vector3::ZERO;
+                if rotating {
+                    // Hold whatever attitude the player lets go at
+                    sas.set_mode(SasMode::Hold(vessel.transform));
+                } else {
+                    let reference = body.map_or(Reference::ORIGIN, Body::reference);
+                    sas.update(vessel, &reference, ts);
+                }
+            }
+        }
+        let mut broken_off = Vec::new();
+        for vessel in &mut self.vessels {
+            let environment = environment(vessel);
+            let engines = vessel.update(&self.data, &environment, ts);
+            // Before flexing, which can break parts off and leave the engine outputs pointing
+            // at the wrong ones
+            thermal::heat(vessel, &self.data, &engines, &environment, ts);
+            broken_off.extend(joint::flex(vessel, &self.data, &engines, &environment, ts));
+            broken_off.extend(thermal::destroy_overheated(vessel, &self.data));
+        }
+        self.vessels.retain(|vessel| !vessel.parts.is_empty());
+        self.vessels.extend(broken_off);
+        let ground = body.map(Body::ground);
+        contact::resolve_contacts(&mut self.vessels, &self.data, ground.as_ref());
     }
 }
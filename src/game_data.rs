@@ -0,0 +1,118 @@
+use crate::{
+    body::{BodyConfig, BodyDefinition},
+    launch_site::{LaunchSiteConfig, LaunchSiteDefinition},
+    part::{PartConfig, PartDefinition},
+    registry::Registry,
+    resource::{ResourceConfig, ResourceDefinition},
+};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Any json file under `GameData`, told apart by its `"type"` field
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Config {
+    Body(Box<BodyConfig>),
+    LaunchSite(LaunchSiteConfig),
+    Part(Box<PartConfig>),
+    Resource(ResourceConfig),
+}
+
+/// All definitions loaded from `GameData`, everything the simulation needs without a GPU
+pub struct GameData {
+    pub bodies: Registry<BodyDefinition>,
+    pub launch_sites: Registry<LaunchSiteDefinition>,
+    pub parts: Registry<PartDefinition>,
+    pub resources: Registry<ResourceDefinition>,
+}
+
+impl GameData {
+    pub fn new() -> Self {
+        Self {
+            bodies: Registry::new(),
+            launch_sites: Registry::new(),
+            parts: Registry::new(),
+            resources: Registry::new(),
+        }
+    }
+
+    /// Recursively loads every json file under `path`
+    pub fn load(path: &Path) -> Self {
+        let mut configs = Vec::new();
+        collect_configs(path, &mut configs);
+
+        let mut data = Self::new();
+        let mut bodies = Vec::new();
+        let mut launch_sites = Vec::new();
+        let mut parts = Vec::new();
+        for (path, config) in configs {
+            match config {
+                Config::Body(body) => bodies.push((path, body)),
+                Config::LaunchSite(launch_site) => launch_sites.push(launch_site),
+                Config::Part(part) => parts.push((path, part)),
+                Config::Resource(resource) => {
+                    let name = resource.name.clone();
+                    data.resources
+                        .insert(&name, ResourceDefinition::new(resource));
+                }
+            }
+        }
+        // Parts refer to resources by name, so they are loaded once every resource is known
+        for (path, part) in parts {
+            let name = part.name.clone();
+            let definition = PartDefinition::load(*part, path.parent().unwrap(), &data.resources);
+            data.parts.insert(&name, definition);
+        }
+        // Bodies refer to their parent by name, so each one is loaded after its parent
+        while !bodies.is_empty() {
+            let ready = bodies
+                .iter()
+                .position(|(_, body)| {
+                    body.parent
+                        .as_ref()
+                        .is_none_or(|parent| data.bodies.id(parent).is_some())
+                })
+                .unwrap_or_else(|| {
+                    panic!(
+                        "{} orbits a body that doesn't exist or orbits it back",
+                        bodies[0].1.name
+                    )
+                });
+            let (path, body) = bodies.remove(ready);
+            let name = body.name.clone();
+            let parent = body.parent.as_ref().map(|parent| {
+                let id = data.bodies.id(parent).unwrap();
+                (id, &data.bodies[id])
+            });
+            let definition = BodyDefinition::new(*body, parent, path.parent().unwrap());
+            data.bodies.insert(&name, definition);
+        }
+        for launch_site in launch_sites {
+            let name = launch_site.name.clone();
+            let definition = LaunchSiteDefinition::new(launch_site, &data.bodies);
+            data.launch_sites.insert(&name, definition);
+        }
+        data
+    }
+}
+
+impl Default for GameData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn collect_configs(path: &Path, configs: &mut Vec<(PathBuf, Config)>) {
+    if path.is_dir() {
+        for entry in std::fs::read_dir(path).unwrap() {
+            let entry = entry.unwrap();
+            collect_configs(&entry.path(), configs);
+        }
+    } else if path
+        .extension()
+        .is_some_and(|path| path.to_str() == Some("json"))
+    {
+        let config = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        configs.push((path.into(), config));
+    }
+}
@@ -0,0 +1,164 @@
+use crate::{atan2, motor::Transform, sin_cos, vector3::Vector3, Number};
+
+/// Most rounds of refining the latitude when converting from Cartesian coordinates, each one
+/// gaining about as many digits as the flattening has leading zeros
+const LATITUDE_ITERATIONS: usize = 32;
+/// Radians of change in the latitude below which it counts as converged
+const LATITUDE_TOLERANCE: Number = Number::lit("0.000000001");
+
+/// Where something is relative to a body's surface
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Geodetic {
+    /// Radians north of the equator, along the surface normal
+    pub latitude: Number,
+    /// Radians east of +X in the body-fixed frame
+    pub longitude: Number,
+    /// Meters above sea level along the surface normal
+    pub altitude: Number,
+}
+
+impl Geodetic {
+    pub fn new(latitude: Number, longitude: Number, altitude: Number) -> Self {
+        Self {
+            latitude,
+            longitude,
+            altitude,
+        }
+    }
+}
+
+/// The shape of a body's sea level, squashed along its north pole
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ellipsoid {
+    /// Meters
+    pub equatorial_radius: Number,
+    /// How much shorter the polar radius is, as a fraction of the equatorial one
+    pub flattening: Number,
+}
+
+impl Ellipsoid {
+    pub fn sphere(radius: Number) -> Self {
+        Self {
+            equatorial_radius: radius,
+            flattening: Number::ZERO,
+        }
+    }
+
+    /// Meters from the center to either pole
+    pub fn polar_radius(&self) -> Number {
+        self.equatorial_radius * (Number::ONE - self.flattening)
+    }
+
+    /// Square of the eccentricity of a meridian
+    fn eccentricity_squared(&self) -> Number {
+        self.flattening * (Number::from_num(2) - self.flattening)
+    }
+
+    /// Meters from the surface to the polar axis along the normal at `sin_latitude`
+    fn normal_radius(&self, sin_latitude: Number) -> Number {
+        self.equatorial_radius
+            / (Number::ONE - self.eccentricity_squared() * sin_latitude * sin_latitude).sqrt()
+    }
+
+    /// Body-fixed position of a point
+    pub fn to_cartesian(&self, geodetic: Geodetic) -> Vector3 {
+        let (sin_latitude, cos_latitude) = sin_cos(geodetic.latitude);
+        let (sin_longitude, cos_longitude) = sin_cos(geodetic.longitude);
+        let normal_radius = self.normal_radius(sin_latitude);
+        let horizontal = (normal_radius + geodetic.altitude) * cos_latitude;
+        Vector3::new(
+            horizontal * cos_longitude,
+            horizontal * sin_longitude,
+            (normal_radius * (Number::ONE - self.eccentricity_squared()) + geodetic.altitude)
+                * sin_latitude,
+        )
+    }
+
+    /// Latitude, longitude and altitude of a body-fixed position
+    pub fn to_geodetic(&self, position: Vector3) -> Geodetic {
+        let horizontal = (position.x * position.x + position.y * position.y).sqrt();
+        let longitude = atan2(position.y, position.x);
+        // Fixed point iteration on where the normal through the point crosses the polar axis,
+        // which is exact straight away for spheres and works at the poles too
+        let mut latitude = atan2(position.z, horizontal);
+        for _ in 0..LATITUDE_ITERATIONS {
+            let (sin_latitude, _) = sin_cos(latitude);
+            let offset =
+                self.eccentricity_squared() * self.normal_radius(sin_latitude) * sin_latitude;
+            let previous = latitude;
+            latitude = atan2(position.z + offset, horizontal);
+            if (latitude - previous).abs() < LATITUDE_TOLERANCE {
+                break;
+            }
+        }
+        let (sin_latitude, cos_latitude) = sin_cos(latitude);
+        // Distance along the normal past the surface, equivalent to the usual formula but
+        // without dividing by the cosine near the poles
+        let altitude = horizontal * cos_latitude + position.z * sin_latitude
+            - self.equatorial_radius * self.equatorial_radius / self.normal_radius(sin_latitude);
+        Geodetic::new(latitude, longitude, altitude)
+    }
+
+    /// Outwards surface normal at a latitude and longitude, in the body-fixed frame
+    pub fn up(&self, latitude: Number, longitude: Number) -> Vector3 {
+        let (sin_latitude, cos_latitude) = sin_cos(latitude);
+        let (sin_longitude, cos_longitude) = sin_cos(longitude);
+        Vector3::new(
+            cos_latitude * cos_longitude,
+            cos_latitude * sin_longitude,
+            sin_latitude,
+        )
+    }
+
+    /// From the local north-east-up frame at a point to the body-fixed frame
+    ///
+    /// +X points north, +Y up and +Z east, so that a vessel with its nose along +Y stands
+    /// upright on the spot.
+    pub fn north_east_up(&self, geodetic: Geodetic) -> Transform {
+        // Turns north, up and east onto +Z, +X and +Y, where they are at latitude and longitude
+        // zero
+        let axes = Transform::rotation(Vector3::ONE / Number::from_num(3).sqrt(), -Number::TAU / 3);
+        axes.apply(Transform::rotation(Vector3::Y, -geodetic.latitude))
+            .apply(Transform::rotation(Vector3::Z, geodetic.longitude))
+            .apply(Transform::translation(self.to_cartesian(geodetic)))
+    }
+}
+
+/// Meters along the surface of a sphere between two points, ignoring their altitude
+pub fn great_circle_distance(radius: Number, from: Geodetic, to: Geodetic) -> Number {
+    // Going through the chord in meters rather than the usual haversine keeps close points
+    // precise, where the haversine would be too small for fixed point numbers
+    let sphere = Ellipsoid::sphere(radius);
+    let on_surface = |geodetic: Geodetic| {
+        sphere.to_cartesian(Geodetic {
+            altitude: Number::ZERO,
+            ..geodetic
+        })
+    };
+    let half_chord = (on_surface(to) - on_surface(from)).length() / 2;
+    radius
+        * 2
+        * atan2(
+            half_chord,
+            (radius * radius - half_chord * half_chord).sqrt(),
+        )
+}
+
+/// Heading to set off on from `from` to follow the great circle to `to`, in radians clockwise
+/// from north
+pub fn bearing(from: Geodetic, to: Geodetic) -> Number {
+    let (sin_from, cos_from) = sin_cos(from.latitude);
+    let (sin_to, cos_to) = sin_cos(to.latitude);
+    let (sin_longitude, cos_longitude) = sin_cos(to.longitude - from.longitude);
+    let bearing = atan2(
+        sin_longitude * cos_to,
+        cos_from * sin_to - sin_from * cos_to * cos_longitude,
+    );
+    bearing.rem_euclid(Number::TAU)
+}
+
+/// From a frame facing `heading` radians clockwise from north to the north-east-up frame, with
+/// its +X forward and +Y still up
+pub fn heading(heading: Number) -> Transform {
+    Transform::rotation(Vector3::Y, -heading)
+}
@@ -0,0 +1,385 @@
+use crate::{
+    convex_hull::ConvexHull, engine::EngineOutput, game_data::GameData, matrix3::Matrix3,
+    motor::Transform, physics::Environment, vector3::Vector3, vessel::Vessel, Number,
+};
+use serde::{Deserialize, Serialize};
+
+/// Largest fraction of a joint's oscillation period one substep may take before the springs
+/// get unstable
+const MAX_STEP_ANGLE: Number = Number::lit("0.5");
+
+/// Stops very stiff joints on very light parts from taking forever
+const MAX_SUBSTEPS: usize = 200;
+
+/// How a part is held onto its parent
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields, default)]
+pub struct JointConfig {
+    /// Point in part space the part attaches to its parent at, the top of its hull when not given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node: Option<[f64; 3]>,
+    /// Newtons per meter the joint stretches
+    pub stiffness: f64,
+    /// Newton meters per radian the joint bends
+    pub angular_stiffness: f64,
+    /// Fraction of critical damping, 1 stops the joint swinging without slowing it down more
+    pub damping: f64,
+    /// Newtons the joint can carry before it breaks
+    pub break_force: f64,
+    /// Newton meters the joint can carry before it breaks
+    pub break_torque: f64,
+}
+
+impl Default for JointConfig {
+    fn default() -> Self {
+        Self {
+            node: None,
+            stiffness: 1e6,
+            angular_stiffness: 1e6,
+            damping: 0.5,
+            break_force: 5e5,
+            break_torque: 5e5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct JointDefinition {
+    /// In part space
+    pub node: Vector3,
+    pub stiffness: Number,
+    pub angular_stiffness: Number,
+    pub damping: Number,
+    pub break_force: Number,
+    pub break_torque: Number,
+}
+
+impl JointDefinition {
+    pub fn new(config: &JointConfig, hull: &ConvexHull) -> Self {
+        let node = match config.node {
+            Some([x, y, z]) => Vector3::new(
+                Number::from_num(x),
+                Number::from_num(y),
+                Number::from_num(z),
+            ),
+            None if hull.is_empty() => Vector3::ZERO,
+            None => Vector3::new(hull.center.x, hull.support(Vector3::Y).y, hull.center.z),
+        };
+        Self {
+            node,
+            stiffness: Number::from_num(config.stiffness),
+            angular_stiffness: Number::from_num(config.angular_stiffness),
+            damping: Number::from_num(config.damping),
+            break_force: Number::from_num(config.break_force),
+            break_torque: Number::from_num(config.break_torque),
+        }
+    }
+}
+
+/// How far a part has moved away from where it sits on a rigid vessel, all in vessel space
+#[derive(Debug, Clone, Copy)]
+pub struct Flex {
+    /// The part's center of mass, which it flexes around
+    pub center: Vector3,
+    pub offset: Vector3,
+    /// Axis times radians, small enough that the order of rotations doesn't matter
+    pub rotation: Vector3,
+    pub velocity: Vector3,
+    pub angular_velocity: Vector3,
+}
+
+impl Flex {
+    pub fn new(center: Vector3) -> Self {
+        Self {
+            center,
+            offset: Vector3::ZERO,
+            rotation: Vector3::ZERO,
+            velocity: Vector3::ZERO,
+            angular_velocity: Vector3::ZERO,
+        }
+    }
+
+    /// From where the part would be on a rigid vessel to where it is, in vessel space
+    pub fn transform(&self) -> Transform {
+        let angle = self.rotation.length();
+        let rotation = if angle > Number::ZERO {
+            Transform::rotation(self.rotation / angle, angle)
+        } else {
+            Transform::IDENTITY
+        };
+        Transform::translation(-self.center)
+            .apply(rotation)
+            .apply(Transform::translation(self.center + self.offset))
+    }
+
+    /// How far a vessel space point on the part has moved
+    fn displacement(&self, point: Vector3) -> Vector3 {
+        self.offset + self.rotation.cross(point - self.center)
+    }
+
+    fn velocity_at(&self, point: Vector3) -> Vector3 {
+        self.velocity + self.angular_velocity.cross(point - self.center)
+    }
+}
+
+/// A part as a rigid body of its own while its joints are simulated
+struct PartBody {
+    mass: Number,
+    /// In vessel space
+    inertia: Matrix3,
+    inverse_inertia: Matrix3,
+    /// Force through the center of mass and torque around it, in vessel space
+    force: Vector3,
+    torque: Vector3,
+}
+
+/// Flexes the joints of `vessel` under the loads of the last `dt` seconds, returning the pieces
+/// that broke off
+///
+/// Only does anything once [`Vessel::set_flexible`] turned flexing on. Engine thrust and drag
+/// pull on the parts they act on, and spinning pulls everything outwards, while steering acts on
+/// the vessel as a whole.
+pub fn flex(
+    vessel: &mut Vessel,
+    data: &GameData,
+    engines: &[EngineOutput],
+    environment: &Environment,
+    dt: Number,
+) -> Vec<Vessel> {
+    if !vessel.is_flexible() {
+        return Vec::new();
+    }
+
+    let mass_properties = vessel.mass_properties(data);
+    let to_vessel = vessel.transform.inverse();
+    let air_velocity = to_vessel.transform_direction(vessel.velocity - environment.air_velocity);
+    let angular_velocity = to_vessel.transform_direction(vessel.angular_velocity);
+    let speed = air_velocity.length();
+
+    let mut bodies = vessel
+        .parts
+        .iter()
+        .enumerate()
+        .map(|(index, part)| {
+            let definition = &data.parts[part.part];
+            let mass = vessel.part_mass(index, data);
+            let scale = if definition.mass > Number::ZERO {
+                mass / definition.mass
+            } else {
+                Number::ZERO
+            };
+            let rotation = part.transform.rotation_matrix();
+            let inertia = rotation * (definition.inertia * scale) * rotation.transpose();
+            PartBody {
+                mass,
+                inertia,
+                inverse_inertia: inertia.inverse().unwrap_or(Matrix3::ZERO),
+                force: -air_velocity * (environment.density * speed * definition.drag / 2),
+                torque: Vector3::ZERO,
+            }
+        })
+        .collect::<Vec<_>>();
+    let centers = vessel
+        .parts
+        .iter()
+        .map(|part| part.flex.unwrap().center)
+        .collect::<Vec<_>>();
+    for output in engines {
+        let transform = vessel.parts[output.part].transform;
+        let force = transform.transform_direction(output.direction) * output.thrust;
+        let body = &mut bodies[output.part];
+        body.force += force;
+        body.torque += (transform.position() - centers[output.part]).cross(force);
+    }
+
+    // Whatever accelerates the whole vessel moves every part along with it, so only what's
+    // left over flexes the joints
+    let total_force = bodies
+        .iter()
+        .fold(Vector3::ZERO, |total, body| total + body.force);
+    let total_torque = bodies
+        .iter()
+        .zip(&centers)
+        .fold(Vector3::ZERO, |total, (body, &center)| {
+            total + (center - mass_properties.center_of_mass).cross(body.force) + body.torque
+        });
+    let acceleration = if mass_properties.mass > Number::ZERO {
+        total_force / mass_properties.mass
+    } else {
+        Vector3::ZERO
+    };
+    let angular_acceleration = mass_properties
+        .inertia
+        .inverse()
+        .map_or(Vector3::ZERO, |inverse| inverse * total_torque);
+    for (body, &center) in bodies.iter_mut().zip(&centers) {
+        let offset = center - mass_properties.center_of_mass;
+        body.force -= (acceleration
+            + angular_acceleration.cross(offset)
+            + angular_velocity.cross(angular_velocity.cross(offset)))
+            * body.mass;
+        body.torque -= body.inertia * angular_acceleration;
+    }
+
+    let joints = (1..vessel.parts.len())
+        .map(|child| Joint::new(vessel, data, &bodies, child))
+        .collect::<Vec<_>>();
+    let fastest = joints
+        .iter()
+        .map(|joint| joint.frequency)
+        .max()
+        .unwrap_or(Number::ZERO);
+    let substeps = (dt * fastest / MAX_STEP_ANGLE)
+        .ceil()
+        .to_num::<usize>()
+        .clamp(1, MAX_SUBSTEPS);
+    let step = dt / Number::from_num(substeps);
+
+    let mut flexes = vessel
+        .parts
+        .iter()
+        .map(|part| part.flex.unwrap())
+        .collect::<Vec<_>>();
+    let mut broken = vec![false; vessel.parts.len()];
+    for _ in 0..substeps {
+        let mut forces = bodies
+            .iter()
+            .map(|body| (body.force, body.torque))
+            .collect::<Vec<_>>();
+        for joint in &joints {
+            let (force, torque) = joint.load(&flexes);
+            if force.length() > joint.break_force || torque.length() > joint.break_torque {
+                broken[joint.child] = true;
+            }
+            let lever = joint.node - flexes[joint.child].center;
+            forces[joint.child].0 += force;
+            forces[joint.child].1 += lever.cross(force) + torque;
+            let lever = joint.node - flexes[joint.parent].center;
+            forces[joint.parent].0 -= force;
+            forces[joint.parent].1 -= lever.cross(force) + torque;
+        }
+        for ((flex, body), (force, torque)) in flexes.iter_mut().zip(&bodies).zip(forces) {
+            if body.mass == Number::ZERO {
+                continue;
+            }
+            flex.velocity += force / body.mass * step;
+            flex.angular_velocity += body.inverse_inertia * torque * step;
+            flex.offset += flex.velocity * step;
+            flex.rotation += flex.angular_velocity * step;
+        }
+    }
+    for (part, flex) in vessel.parts.iter_mut().zip(flexes) {
+        part.flex = Some(flex);
+    }
+
+    // Detaching only moves the parts after the cut, so cutting from the back keeps the earlier
+    // indices valid
+    let mut pieces = Vec::new();
+    for child in (1..vessel.parts.len()).rev() {
+        if broken[child] {
+            let mut piece = vessel.detach(child, data);
+            settle_flex(&mut piece, data);
+            pieces.push(piece);
+        }
+    }
+    settle_flex(vessel, data);
+    pieces
+}
+
+/// Moves the average motion of every part's flex into the vessel itself, so a piece that broke
+/// off keeps the velocity it was flexing with
+fn settle_flex(vessel: &mut Vessel, data: &GameData) {
+    let mut mass = Number::ZERO;
+    let mut offset = Vector3::ZERO;
+    let mut velocity = Vector3::ZERO;
+    for (index, part) in vessel.parts.iter().enumerate() {
+        let flex = part.flex.unwrap();
+        let part_mass = vessel.part_mass(index, data);
+        mass += part_mass;
+        offset += flex.offset * part_mass;
+        velocity += flex.velocity * part_mass;
+    }
+    if mass == Number::ZERO {
+        return;
+    }
+    let (offset, velocity) = (offset / mass, velocity / mass);
+    for part in &mut vessel.parts {
+        let flex = part.flex.as_mut().unwrap();
+        flex.offset -= offset;
+        flex.velocity -= velocity;
+    }
+    vessel.velocity += vessel.transform.transform_direction(velocity);
+    vessel.transform = Transform::translation(offset).apply(vessel.transform);
+}
+
+/// The joint holding a part onto its parent, while it's being simulated
+struct Joint {
+    child: usize,
+    parent: usize,
+    /// In vessel space
+    node: Vector3,
+    stiffness: Number,
+    angular_stiffness: Number,
+    damping: Number,
+    angular_damping: Number,
+    break_force: Number,
+    break_torque: Number,
+    /// Radians per second the joint swings at, the quicker of stretching and bending
+    frequency: Number,
+}
+
+impl Joint {
+    fn new(vessel: &Vessel, data: &GameData, bodies: &[PartBody], child: usize) -> Self {
+        let part = &vessel.parts[child];
+        let parent = part.parent.unwrap();
+        let definition = &data.parts[part.part].joint;
+
+        // The two parts swing against each other like a single body of their reduced mass
+        let reduced = |a: Number, b: Number| {
+            if a + b > Number::ZERO {
+                a * b / (a + b)
+            } else {
+                Number::ZERO
+            }
+        };
+        let moment = |index: usize| bodies[index].inertia.trace() / 3;
+        let mass = reduced(bodies[child].mass, bodies[parent].mass);
+        let inertia = reduced(moment(child), moment(parent));
+        let frequency = |stiffness: Number, mass: Number| {
+            if mass > Number::ZERO {
+                (stiffness / mass).sqrt()
+            } else {
+                Number::ZERO
+            }
+        };
+
+        Self {
+            child,
+            parent,
+            node: part.transform.transform_point(definition.node),
+            stiffness: definition.stiffness,
+            angular_stiffness: definition.angular_stiffness,
+            damping: definition.damping * 2 * (definition.stiffness * mass).sqrt(),
+            angular_damping: definition.damping
+                * 2
+                * (definition.angular_stiffness * inertia).sqrt(),
+            break_force: definition.break_force,
+            break_torque: definition.break_torque,
+            frequency: frequency(definition.stiffness, mass)
+                .max(frequency(definition.angular_stiffness, inertia)),
+        }
+    }
+
+    /// Force and torque the joint pulls the child back with, the parent getting the opposite
+    fn load(&self, flexes: &[Flex]) -> (Vector3, Vector3) {
+        let (child, parent) = (&flexes[self.child], &flexes[self.parent]);
+        let stretch = child.displacement(self.node) - parent.displacement(self.node);
+        let stretching = child.velocity_at(self.node) - parent.velocity_at(self.node);
+        let bend = child.rotation - parent.rotation;
+        let bending = child.angular_velocity - parent.angular_velocity;
+        (
+            -stretch * self.stiffness - stretching * self.damping,
+            -bend * self.angular_stiffness - bending * self.angular_damping,
+        )
+    }
+}
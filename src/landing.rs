@@ -0,0 +1,224 @@
+use crate::{
+    body::Body,
+    engine,
+    game_data::GameData,
+    sas::{Sas, SasMode},
+    vector3::Vector3,
+    vessel::Vessel,
+    Number,
+};
+
+/// Fraction of the full thrust braking is planned with, leaving the rest for steering and
+/// mistakes
+const BRAKING_MARGIN: Number = Number::lit("0.85");
+
+/// Tangent of how far the vessel may lean over while hovering, about 30 degrees
+const MAX_TILT: Number = Number::lit("0.577");
+
+/// Meters above the ground a vessel needs to start burning at full thrust to stop right at it,
+/// `None` when the engines can't beat gravity
+pub fn suicide_burn_altitude(
+    speed: Number,
+    thrust: Number,
+    mass: Number,
+    gravity: Number,
+) -> Option<Number> {
+    let deceleration = thrust / mass - gravity;
+    (deceleration > Number::ZERO).then(|| speed * speed / (deceleration * 2))
+}
+
+/// How the landing autopilot should set the vessel down
+#[derive(Debug, Clone, Copy)]
+pub struct LandingSettings {
+    /// Meters per second the vessel touches down at
+    pub touchdown_speed: Number,
+    /// Meters from the vessel's origin down to the bottom of its landing legs or engines
+    pub height: Number,
+    /// Latitude and longitude in radians to fly to before setting down
+    pub target: Option<(Number, Number)>,
+    /// Meters above the ground to fly at on the way to the target
+    pub hover_altitude: Number,
+    /// Meters per second to fly sideways at on the way to the target
+    pub hover_speed: Number,
+    /// Meters from the target that count as being over it
+    pub precision: Number,
+}
+
+impl LandingSettings {
+    /// Lands wherever the vessel comes down
+    pub fn new(height: Number) -> Self {
+        Self {
+            touchdown_speed: Number::ONE,
+            height,
+            target: None,
+            hover_altitude: Number::from_num(50),
+            hover_speed: Number::from_num(20),
+            precision: Number::from_num(2),
+        }
+    }
+
+    /// Flies to `latitude` and `longitude` before landing
+    pub fn with_target(self, latitude: Number, longitude: Number) -> Self {
+        Self {
+            target: Some((latitude, longitude)),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LandingPhase {
+    /// Falling with the engines off until it's time for the suicide burn
+    Coast,
+    /// Killing the velocity on the way down, slowing to the touchdown speed at the ground
+    Braking,
+    /// Holding the hover altitude while flying over to the target
+    Hover,
+    Landed,
+}
+
+/// Sets a vessel down on an airless body
+#[derive(Debug, Clone, Copy)]
+pub struct LandingAutopilot {
+    pub settings: LandingSettings,
+    phase: LandingPhase,
+    sas: Sas,
+    /// Whether the vessel is over its target, or has none
+    over_target: bool,
+}
+
+impl LandingAutopilot {
+    pub fn new(settings: LandingSettings) -> Self {
+        Self {
+            settings,
+            phase: LandingPhase::Coast,
+            sas: Sas::new(SasMode::Direction(Vector3::ZERO)),
+            over_target: settings.target.is_none(),
+        }
+    }
+
+    pub fn phase(&self) -> LandingPhase {
+        self.phase
+    }
+
+    /// Steers and throttles `vessel` for the next `dt` seconds
+    pub fn update(&mut self, vessel: &mut Vessel, data: &GameData, body: &Body, dt: Number) {
+        let position = vessel.transform.position() - body.position;
+        let up = position / position.length();
+        let height = body.height_above_ground(vessel.transform.position()) - self.settings.height;
+        // Relative to the ground turning underneath
+        let velocity = body.surface_velocity(vessel.transform.position(), vessel.velocity);
+        let speed = velocity.length();
+        let falling = velocity.dot(up) < Number::ZERO;
+        let gravity = body.gravity(vessel.transform.position()).length();
+
+        let mass = vessel.mass(data);
+        let thrust = engine::available_thrust(
+            vessel,
+            data,
+            body.environment(vessel.transform.position()).pressure,
+        );
+
+        if height <= Number::ZERO {
+            self.phase = LandingPhase::Landed;
+        }
+        // Flying to the target happens at the hover altitude, so brake down to it instead
+        let floor = if self.over_target {
+            Number::ZERO
+        } else {
+            self.settings.hover_altitude
+        };
+        // Leaning a little towards up keeps the vessel upright once it's barely moving
+        let retrograde = up * self.settings.touchdown_speed - velocity;
+
+        let (direction, acceleration) = match self.phase {
+            LandingPhase::Coast => {
+                let start = suicide_burn_altitude(speed, thrust, mass, body.surface_gravity())
+                    .map_or(Number::MAX, |altitude| altitude / BRAKING_MARGIN);
+                if height - floor <= start && (falling || height <= floor) {
+                    self.phase = LandingPhase::Braking;
+                }
+                (if falling { retrograde } else { up }, Number::ZERO)
+            }
+            LandingPhase::Braking => {
+                if !self.over_target && height - floor <= Number::ONE {
+                    self.phase = LandingPhase::Hover;
+                }
+                // Just enough deceleration along the velocity to be down to the touchdown speed
+                // at the floor, which is further away the more sideways the vessel is moving
+                let above_floor = (height - floor).max(Number::lit("0.1"));
+                let descent = if speed > Number::ZERO {
+                    (-velocity.dot(up) / speed).max(Number::ZERO)
+                } else {
+                    Number::ZERO
+                };
+                let touchdown = self.settings.touchdown_speed;
+                let needed = descent
+                    * ((speed * speed - touchdown * touchdown) / (above_floor * 2) + gravity);
+                (retrograde, needed)
+            }
+            LandingPhase::Hover => {
+                let acceleration =
+                    self.hover_acceleration(body, position, velocity, height, gravity);
+                (acceleration, acceleration.length())
+            }
+            LandingPhase::Landed => (up, Number::ZERO),
+        };
+
+        // Only burn once pointing roughly the right way
+        let forward = vessel.transform.transform_direction(Vector3::Y);
+        let aligned = forward.dot(direction) > direction.length() * Number::lit("0.9");
+        vessel.throttle = if thrust > Number::ZERO && aligned {
+            (acceleration * mass / thrust).clamp(Number::ZERO, Number::ONE)
+        } else {
+            Number::ZERO
+        };
+        self.sas.mode = SasMode::Direction(direction);
+        self.sas.update(vessel, &body.reference(), dt);
+    }
+
+    /// Acceleration that holds the hover altitude and carries the vessel over to its target,
+    /// ending the hover once it's there
+    fn hover_acceleration(
+        &mut self,
+        body: &Body,
+        position: Vector3,
+        velocity: Vector3,
+        height: Number,
+        gravity: Number,
+    ) -> Vector3 {
+        let up = position / position.length();
+        let climb = ((self.settings.hover_altitude - height) / 4)
+            .clamp(-self.settings.hover_speed, self.settings.hover_speed);
+        let vertical_speed = velocity.dot(up);
+        let horizontal_velocity = velocity - up * vertical_speed;
+        let vertical = (gravity + climb - vertical_speed).max(Number::ZERO);
+        let sideways_limit = vertical * MAX_TILT;
+
+        let mut wanted = Vector3::ZERO;
+        if let Some((latitude, longitude)) = self.settings.target {
+            let offset =
+                body.surface_position(latitude, longitude, Number::ZERO) - body.position - position;
+            let offset = offset - up * offset.dot(up);
+            let distance = offset.length();
+            if distance <= self.settings.precision && horizontal_velocity.length() < Number::ONE {
+                self.over_target = true;
+                self.phase = LandingPhase::Braking;
+            } else if distance > Number::ZERO {
+                // Slow enough to stop over the target leaning over half as far as allowed
+                let speed = (gravity * MAX_TILT * distance)
+                    .sqrt()
+                    .min(distance / 2)
+                    .min(self.settings.hover_speed);
+                wanted = offset / distance * speed;
+            }
+        }
+
+        let mut sideways = (wanted - horizontal_velocity) / Number::from_num(2);
+        let length = sideways.length();
+        if length > sideways_limit {
+            sideways = sideways / length * sideways_limit;
+        }
+        up * vertical + sideways
+    }
+}
@@ -0,0 +1,97 @@
+use crate::{
+    body::{Body, BodyDefinition, BodyId},
+    game_data::GameData,
+    geodetic::{self, Geodetic},
+    motor::Transform,
+    registry::{Id, Registry},
+    vector3::Vector3,
+    vessel::Vessel,
+    Number,
+};
+use serde::{Deserialize, Serialize};
+
+pub type LaunchSiteId = Id<LaunchSiteDefinition>;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct LaunchSiteConfig {
+    pub name: String,
+    pub title: String,
+    /// Name of the body the site is on
+    pub body: String,
+    /// Degrees north of the equator
+    pub latitude: f64,
+    /// Degrees east of the body's prime meridian
+    pub longitude: f64,
+    /// Degrees clockwise from north that a vessel's +X axis faces once spawned
+    #[serde(default)]
+    pub heading: f64,
+    /// Meters above the ground the lowest part is spawned at
+    #[serde(default)]
+    pub altitude: f64,
+}
+
+/// A spot on a body's surface where vessels are spawned
+pub struct LaunchSiteDefinition {
+    pub config: LaunchSiteConfig,
+    pub body: BodyId,
+    /// Radians, and meters above the ground
+    pub position: Geodetic,
+    /// Radians clockwise from north
+    pub heading: Number,
+}
+
+impl LaunchSiteDefinition {
+    pub fn new(config: LaunchSiteConfig, bodies: &Registry<BodyDefinition>) -> Self {
+        let body = bodies
+            .id(&config.body)
+            .unwrap_or_else(|| panic!("{} is on unknown body {}", config.name, config.body));
+        let angle = |degrees: f64| Number::from_num(degrees.to_radians());
+        Self {
+            body,
+            position: Geodetic::new(
+                angle(config.latitude),
+                angle(config.longitude),
+                Number::from_num(config.altitude),
+            ),
+            heading: angle(config.heading),
+            config,
+        }
+    }
+
+    /// Stands `vessel` upright on the site, its lowest part `altitude` meters above the ground,
+    /// and sets it turning along with `body`, which must be the site's
+    pub fn spawn(&self, vessel: &mut Vessel, body: &Body, data: &GameData) {
+        // Lift the vessel so that nothing reaches below its origin along its +Y axis
+        let lowest = vessel
+            .parts
+            .iter()
+            .flat_map(|part| {
+                let hull = &data.parts[part.part].hull;
+                let corners = if hull.is_empty() {
+                    vec![part.transform.position()]
+                } else {
+                    let transform = part.transform;
+                    hull.vertices
+                        .iter()
+                        .map(|&vertex| transform.transform_point(vertex))
+                        .collect()
+                };
+                corners.into_iter().map(|corner| corner.y)
+            })
+            .min()
+            .unwrap_or(Number::ZERO);
+        vessel.transform = Transform::translation(Vector3::Y * -lowest)
+            .apply(geodetic::heading(self.heading))
+            .apply(body.north_east_up(Geodetic {
+                altitude: body.terrain_height(self.position.latitude, self.position.longitude)
+                    + self.position.altitude,
+                ..self.position
+            }));
+        let center_of_mass = vessel
+            .transform
+            .transform_point(vessel.mass_properties(data).center_of_mass);
+        vessel.velocity = body.rotation_velocity(center_of_mass);
+        vessel.angular_velocity = body.angular_velocity;
+    }
+}
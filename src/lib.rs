@@ -1,11 +1,43 @@
 use az::CastFrom;
 use encase::ShaderType;
-use fixed::{types::extra::{U16, U32}, FixedI128};
+use fixed::{types::extra::U32, FixedI128};
 use motor::{GpuTransform, Transform};
 
+pub mod ascent;
+pub mod biome;
+pub mod body;
+pub mod collision;
+pub mod contact;
+pub mod control;
+pub mod convex_hull;
+pub mod delta_v;
+pub mod electric;
+pub mod engine;
+pub mod float_curve;
+pub mod flow;
 pub mod game;
+pub mod game_data;
+pub mod geodetic;
+pub mod joint;
+pub mod landing;
+pub mod launch_site;
+pub mod light;
+pub mod matrix3;
 pub mod motor;
+pub mod orbit;
+pub mod part;
+pub mod physics;
+pub mod planet_mesh;
+pub mod registry;
+pub mod resource;
+pub mod sas;
+pub mod save;
+pub mod situation;
+pub mod staging;
+pub mod terrain;
+pub mod thermal;
 pub mod vector3;
+pub mod vessel;
 
 pub type Number = FixedI128<U32>;
 
@@ -16,18 +48,21 @@ pub struct Camera {
 #[derive(ShaderType)]
 pub struct GpuCamera {
     pub aspect: f32,
+    /// Unit length towards the star in camera space, zero without one
+    pub sun_direction: cgmath::Vector3<f32>,
 }
 
 pub struct Mesh {
     pub start_vertex_index: u32,
     pub triangle_count: u32,
     pub textures: wgpu::BindGroup,
-    pub transform: Transform,
 }
 
 #[derive(ShaderType)]
 pub struct GpuMesh {
     pub transform: GpuTransform,
+    /// How much sunlight reaches the mesh, 0 in a body's shadow
+    pub exposure: f32,
 }
 
 #[derive(ShaderType)]
@@ -36,7 +71,7 @@ pub struct GpuMeshes<'a> {
     pub meshes: &'a [GpuMesh],
 }
 
-#[derive(ShaderType)]
+#[derive(Debug, Clone, Copy, ShaderType)]
 pub struct Vertex {
     pub position: cgmath::Vector3<f32>,
     pub normal: cgmath::Vector3<f32>,
@@ -54,3 +89,15 @@ fn sin_cos(mut x: Number) -> (Number, Number) {
     let (sin, cos) = f64::cast_from(x).sin_cos();
     (Number::from_num(sin), Number::from_num(cos))
 }
+
+fn atan2(y: Number, x: Number) -> Number {
+    Number::from_num(f64::cast_from(y).atan2(f64::cast_from(x)))
+}
+
+fn exp(x: Number) -> Number {
+    Number::from_num(f64::cast_from(x).exp())
+}
+
+fn ln(x: Number) -> Number {
+    Number::from_num(f64::cast_from(x).ln())
+}
@@ -0,0 +1,75 @@
+use crate::{
+    body::{Body, BodyDefinition},
+    registry::Registry,
+    vector3::Vector3,
+    Number,
+};
+
+/// Light from the star at the root of the body hierarchy, and the bodies that cast shadows
+#[derive(Debug, Clone)]
+pub struct Sunlight {
+    /// World space position and watts given off, `None` when the root body doesn't shine
+    star: Option<(Vector3, Number)>,
+    /// World space centers and radii of every other body
+    occluders: Vec<(Vector3, Number)>,
+}
+
+impl Sunlight {
+    /// `bodies` is where every body in `definitions` is right now, indexed like it
+    pub fn new(definitions: &Registry<BodyDefinition>, bodies: &[Body]) -> Self {
+        let star = definitions
+            .iter()
+            .find(|(_, definition)| definition.parent.is_none())
+            .filter(|(_, definition)| definition.luminosity > Number::ZERO)
+            .map(|(id, _)| id);
+        let occluders = definitions
+            .iter()
+            .filter(|&(id, _)| Some(id) != star)
+            .map(|(id, _)| (bodies[id.index()].position, bodies[id.index()].radius))
+            .collect();
+        let star = star.map(|id| (bodies[id.index()].position, definitions[id].luminosity));
+        Self { star, occluders }
+    }
+
+    /// Unit length world space direction from a world space position towards the star
+    pub fn direction(&self, position: Vector3) -> Option<Vector3> {
+        let (star, _) = self.star?;
+        let offset = star - position;
+        Some(offset / offset.length())
+    }
+
+    /// Watts per square meter of sunlight facing the star at a world space position, leaving out
+    /// anything in the way
+    pub fn flux(&self, position: Vector3) -> Number {
+        let Some((star, luminosity)) = self.star else {
+            return Number::ZERO;
+        };
+        let distance = (star - position).length();
+        // Dividing one step at a time keeps the square of the distance from overflowing
+        luminosity / (Number::from_num(4) * Number::PI) / distance / distance
+    }
+
+    /// How much of the star a world space position sees, 1 in full sunlight and 0 in a body's
+    /// shadow
+    pub fn solar_exposure(&self, position: Vector3) -> Number {
+        let Some((star, _)) = self.star else {
+            return Number::ZERO;
+        };
+        let offset = star - position;
+        let distance = offset.length();
+        let direction = offset / distance;
+        let eclipsed = self.occluders.iter().any(|&(center, radius)| {
+            // Closest point to the center along the ray towards the star, which misses bodies
+            // behind the position or beyond the star
+            let along = (center - position).dot(direction);
+            along > Number::ZERO
+                && along < distance
+                && (position + direction * along - center).length() < radius
+        });
+        if eclipsed {
+            Number::ZERO
+        } else {
+            Number::ONE
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use crate::{vector3::Vector3, Number};
+use std::ops::{Add, AddAssign, Div, Mul, Sub};
+
+/// A 3x3 matrix stored as its columns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Matrix3 {
+    pub x: Vector3,
+    pub y: Vector3,
+    pub z: Vector3,
+}
+
+impl Matrix3 {
+    pub const ZERO: Matrix3 = Self::from_columns(Vector3::ZERO, Vector3::ZERO, Vector3::ZERO);
+    pub const IDENTITY: Matrix3 = Self::from_columns(Vector3::X, Vector3::Y, Vector3::Z);
+
+    #[inline]
+    pub const fn from_columns(x: Vector3, y: Vector3, z: Vector3) -> Self {
+        Self { x, y, z }
+    }
+
+    #[inline]
+    pub const fn from_diagonal(diagonal: Vector3) -> Self {
+        Self::from_columns(
+            Vector3::new(diagonal.x, Number::ZERO, Number::ZERO),
+            Vector3::new(Number::ZERO, diagonal.y, Number::ZERO),
+            Vector3::new(Number::ZERO, Number::ZERO, diagonal.z),
+        )
+    }
+
+    /// The matrix `a * b^T`
+    #[inline]
+    pub fn outer(a: Vector3, b: Vector3) -> Self {
+        Self::from_columns(a * b.x, a * b.y, a * b.z)
+    }
+
+    #[inline]
+    pub fn row(self, index: usize) -> Vector3 {
+        match index {
+            0 => Vector3::new(self.x.x, self.y.x, self.z.x),
+            1 => Vector3::new(self.x.y, self.y.y, self.z.y),
+            2 => Vector3::new(self.x.z, self.y.z, self.z.z),
+            _ => panic!("row index {index} out of range"),
+        }
+    }
+
+    #[inline]
+    pub fn transpose(self) -> Self {
+        Self::from_columns(self.row(0), self.row(1), self.row(2))
+    }
+
+    #[inline]
+    pub fn trace(self) -> Number {
+        self.x.x + self.y.y + self.z.z
+    }
+
+    #[inline]
+    pub fn determinant(self) -> Number {
+        self.x.dot(self.y.cross(self.z))
+    }
+
+    /// Returns `None` when the matrix is singular
+    pub fn inverse(self) -> Option<Self> {
+        let determinant = self.determinant();
+        if determinant == Number::ZERO {
+            return None;
+        }
+        let rows = Self::from_columns(
+            self.y.cross(self.z),
+            self.z.cross(self.x),
+            self.x.cross(self.y),
+        );
+        // Dividing rather than multiplying by the reciprocal keeps large determinants from
+        // rounding the whole inverse down to zero
+        Some(rows.transpose() / determinant)
+    }
+}
+
+impl Add for Matrix3 {
+    type Output = Matrix3;
+
+    #[inline]
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::from_columns(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+impl AddAssign for Matrix3 {
+    #[inline]
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Matrix3 {
+    type Output = Matrix3;
+
+    #[inline]
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::from_columns(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+impl Mul<Number> for Matrix3 {
+    type Output = Matrix3;
+
+    #[inline]
+    fn mul(self, rhs: Number) -> Self::Output {
+        Self::from_columns(self.x * rhs, self.y * rhs, self.z * rhs)
+    }
+}
+
+impl Div<Number> for Matrix3 {
+    type Output = Matrix3;
+
+    #[inline]
+    fn div(self, rhs: Number) -> Self::Output {
+        Self::from_columns(self.x / rhs, self.y / rhs, self.z / rhs)
+    }
+}
+
+impl Mul<Vector3> for Matrix3 {
+    type Output = Vector3;
+
+    #[inline]
+    fn mul(self, rhs: Vector3) -> Self::Output {
+        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
+    }
+}
+
+impl Mul<Matrix3> for Matrix3 {
+    type Output = Matrix3;
+
+    #[inline]
+    fn mul(self, rhs: Matrix3) -> Self::Output {
+        Self::from_columns(self * rhs.x, self * rhs.y, self * rhs.z)
+    }
+}
@@ -1,6 +1,7 @@
-use crate::{sin_cos, vector3::Vector3, Number};
+use crate::{atan2, matrix3::Matrix3, sin_cos, vector3::Vector3, Number};
 use az::Cast;
 use encase::ShaderType;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Point {
@@ -18,6 +19,20 @@ impl Point {
         e123: Number::ZERO,
     };
 
+    /// A point at infinity, which is only affected by the rotational part of a transform
+    pub fn direction(value: Vector3) -> Self {
+        Self {
+            e012: value.z,
+            e013: -value.y,
+            e023: value.x,
+            e123: Number::ZERO,
+        }
+    }
+
+    pub fn into_direction(self) -> Vector3 {
+        Vector3::new(self.e023, -self.e013, self.e012)
+    }
+
     pub fn transform(self, motor: Transform) -> Self {
         let a = motor.s;
         let b = motor.e12;
@@ -125,7 +140,7 @@ impl From<Point> for Vector3 {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Transform {
     pub s: Number,
     pub e12: Number,
@@ -241,6 +256,72 @@ impl Transform {
         }
     }
 
+    /// Rotation by `angle` radians around `axis` (which must be normalized), right handed
+    pub fn rotation(axis: Vector3, angle: Number) -> Self {
+        let (sin, cos) = sin_cos(angle * Number::from_num(0.5));
+        Self {
+            s: cos,
+            e12: sin * axis.z,
+            e13: -sin * axis.y,
+            e23: sin * axis.x,
+            e01: Number::ZERO,
+            e02: Number::ZERO,
+            e03: Number::ZERO,
+            e0123: Number::ZERO,
+        }
+    }
+
+    pub fn transform_point(self, point: Vector3) -> Vector3 {
+        Point::from(point).transform(self).into()
+    }
+
+    pub fn transform_direction(self, direction: Vector3) -> Vector3 {
+        Point::direction(direction).transform(self).into_direction()
+    }
+
+    /// Where this transform moves the origin to
+    pub fn position(self) -> Vector3 {
+        self.transform_point(Vector3::ZERO)
+    }
+
+    /// Just the rotational part of this transform, turning around the origin
+    pub fn rotation_part(self) -> Self {
+        Self {
+            s: self.s,
+            e12: self.e12,
+            e13: self.e13,
+            e23: self.e23,
+            e01: Number::ZERO,
+            e02: Number::ZERO,
+            e03: Number::ZERO,
+            e0123: Number::ZERO,
+        }
+    }
+
+    /// Axis of the rotational part scaled by its angle in radians, taking the shorter way around
+    pub fn rotation_vector(self) -> Vector3 {
+        let mut cos = self.s;
+        let mut axis = Vector3::new(self.e23, -self.e13, self.e12);
+        if cos < Number::ZERO {
+            cos = -cos;
+            axis = -axis;
+        }
+        let sin = axis.length();
+        if sin == Number::ZERO {
+            return Vector3::ZERO;
+        }
+        axis / sin * (atan2(sin, cos) * Number::from_num(2))
+    }
+
+    /// The rotational part of this transform as a matrix acting on directions
+    pub fn rotation_matrix(self) -> Matrix3 {
+        Matrix3::from_columns(
+            self.transform_direction(Vector3::X),
+            self.transform_direction(Vector3::Y),
+            self.transform_direction(Vector3::Z),
+        )
+    }
+
     pub fn apply(self, other: Self) -> Self {
         let a = self.s;
         let b = self.e12;
@@ -0,0 +1,203 @@
+use crate::{atan2, sin_cos, vector3::Vector3, Number};
+
+/// Enough for any orbit short of escaping to land within the tolerance
+const KEPLER_ITERATIONS: usize = 32;
+
+/// Radians of eccentric anomaly
+const KEPLER_TOLERANCE: Number = Number::lit("0.000000001");
+
+/// A Keplerian orbit around the center of a body
+///
+/// Angles are in radians, measured against the body's equator (its XY plane) and its +X axis.
+#[derive(Debug, Clone, Copy)]
+pub struct Orbit {
+    /// Gravitational parameter of the body, cubic meters per second squared
+    pub mu: Number,
+    /// Meters, negative for orbits that escape
+    pub semi_major_axis: Number,
+    pub eccentricity: Number,
+    pub inclination: Number,
+    pub longitude_of_ascending_node: Number,
+    pub argument_of_periapsis: Number,
+    pub true_anomaly: Number,
+}
+
+impl Orbit {
+    /// The orbit through `position` at `velocity`, both relative to the body's center
+    pub fn from_state(position: Vector3, velocity: Vector3, mu: Number) -> Self {
+        let radius = position.length();
+        let speed_squared = velocity.sqr_length();
+        let energy = speed_squared / 2 - mu / radius;
+        let semi_major_axis = if energy == Number::ZERO {
+            Number::MAX
+        } else {
+            -mu / (energy * 2)
+        };
+
+        let angular_momentum = position.cross(velocity);
+        let eccentricity_vector =
+            (position * (speed_squared - mu / radius) - velocity * position.dot(velocity)) / mu;
+        let eccentricity = eccentricity_vector.length();
+        let inclination = atan2(
+            Vector3::new(angular_momentum.x, angular_momentum.y, Number::ZERO).length(),
+            angular_momentum.z,
+        );
+
+        // Points at the ascending node, or nowhere for orbits along the equator
+        let node = Vector3::Z.cross(angular_momentum);
+        let longitude_of_ascending_node = if node.sqr_length() > Number::ZERO {
+            wrap(atan2(node.y, node.x))
+        } else {
+            Number::ZERO
+        };
+
+        let length = angular_momentum.length();
+        let normal = if length > Number::ZERO {
+            angular_momentum / length
+        } else {
+            Vector3::Z
+        };
+        // Angle from `from` to `to` going around the orbit normal
+        let angle =
+            |from: Vector3, to: Vector3| wrap(atan2(normal.dot(from.cross(to)), from.dot(to)));
+        let reference = if node.sqr_length() > Number::ZERO {
+            node
+        } else {
+            Vector3::X
+        };
+        // Circular orbits have no periapsis, so measure from the ascending node instead
+        let periapsis = if eccentricity > Number::from_num(1e-9) {
+            eccentricity_vector
+        } else {
+            reference
+        };
+
+        Self {
+            mu,
+            semi_major_axis,
+            eccentricity,
+            inclination,
+            longitude_of_ascending_node,
+            argument_of_periapsis: angle(reference, periapsis),
+            true_anomaly: angle(periapsis, position),
+        }
+    }
+
+    /// Whether the orbit leaves the body for good
+    pub fn is_escaping(&self) -> bool {
+        self.semi_major_axis < Number::ZERO || self.semi_major_axis == Number::MAX
+    }
+
+    /// Distance from the body's center at the lowest point
+    pub fn periapsis(&self) -> Number {
+        self.semi_major_axis * (Number::ONE - self.eccentricity)
+    }
+
+    /// Distance from the body's center at the highest point, `None` for escaping orbits
+    pub fn apoapsis(&self) -> Option<Number> {
+        (!self.is_escaping()).then(|| self.semi_major_axis * (Number::ONE + self.eccentricity))
+    }
+
+    /// Seconds per orbit, `None` for escaping orbits
+    pub fn period(&self) -> Option<Number> {
+        (!self.is_escaping()).then(|| Number::TAU / self.mean_motion())
+    }
+
+    /// Radians of mean anomaly per second
+    pub fn mean_motion(&self) -> Number {
+        let semi_major_axis = self.semi_major_axis.abs();
+        // Dividing twice keeps the cube of the semi major axis from overflowing
+        (self.mu / semi_major_axis).sqrt() / semi_major_axis
+    }
+
+    /// Mean anomaly at the current position, for orbits that don't escape
+    pub fn mean_anomaly(&self) -> Number {
+        let e = self.eccentricity;
+        let (sin, cos) = sin_cos(self.true_anomaly);
+        let eccentric_anomaly = atan2((Number::ONE - e * e).sqrt() * sin, e + cos);
+        let (sin_eccentric, _) = sin_cos(eccentric_anomaly);
+        wrap(eccentric_anomaly - e * sin_eccentric)
+    }
+
+    /// The same orbit at another mean anomaly, for orbits that don't escape
+    pub fn at_mean_anomaly(&self, mean_anomaly: Number) -> Self {
+        let e = self.eccentricity;
+        let mean_anomaly = wrap(mean_anomaly);
+        // Newton's method on Kepler's equation, starting further round for eccentric orbits
+        // where it would otherwise overshoot
+        let mut eccentric_anomaly = if e > Number::lit("0.8") {
+            Number::PI
+        } else {
+            mean_anomaly
+        };
+        for _ in 0..KEPLER_ITERATIONS {
+            let (sin, cos) = sin_cos(eccentric_anomaly);
+            let step = (eccentric_anomaly - e * sin - mean_anomaly) / (Number::ONE - e * cos);
+            eccentric_anomaly -= step;
+            if step.abs() < KEPLER_TOLERANCE {
+                break;
+            }
+        }
+        let (sin_half, cos_half) = sin_cos(eccentric_anomaly / 2);
+        Self {
+            true_anomaly: wrap(
+                atan2(
+                    (Number::ONE + e).sqrt() * sin_half,
+                    (Number::ONE - e).sqrt() * cos_half,
+                ) * 2,
+            ),
+            ..*self
+        }
+    }
+
+    /// Where the vessel will be `seconds` later, for orbits that don't escape
+    pub fn after(&self, seconds: Number) -> Self {
+        // Wrapping the time first keeps the angle from overflowing over long time warps
+        let seconds = seconds.rem_euclid(Number::TAU / self.mean_motion());
+        self.at_mean_anomaly(self.mean_anomaly() + self.mean_motion() * seconds)
+    }
+
+    /// Position and velocity relative to the body's center
+    pub fn state(&self) -> (Vector3, Vector3) {
+        let e = self.eccentricity;
+        let semi_latus_rectum = self.semi_major_axis * (Number::ONE - e * e);
+        let (sin_anomaly, cos_anomaly) = sin_cos(self.true_anomaly);
+        let radius = semi_latus_rectum / (Number::ONE + e * cos_anomaly);
+        let speed = (self.mu / semi_latus_rectum).sqrt();
+
+        // Towards periapsis, and 90 degrees further along the orbit
+        let (sin_node, cos_node) = sin_cos(self.longitude_of_ascending_node);
+        let (sin_inclination, cos_inclination) = sin_cos(self.inclination);
+        let (sin_argument, cos_argument) = sin_cos(self.argument_of_periapsis);
+        let periapsis = Vector3::new(
+            cos_node * cos_argument - sin_node * sin_argument * cos_inclination,
+            sin_node * cos_argument + cos_node * sin_argument * cos_inclination,
+            sin_argument * sin_inclination,
+        );
+        let ahead = Vector3::new(
+            -cos_node * sin_argument - sin_node * cos_argument * cos_inclination,
+            -sin_node * sin_argument + cos_node * cos_argument * cos_inclination,
+            cos_argument * sin_inclination,
+        );
+
+        (
+            (periapsis * cos_anomaly + ahead * sin_anomaly) * radius,
+            (ahead * (e + cos_anomaly) - periapsis * sin_anomaly) * speed,
+        )
+    }
+
+    /// Seconds until the vessel next reaches periapsis, `None` for escaping orbits
+    pub fn time_to_periapsis(&self) -> Option<Number> {
+        (!self.is_escaping()).then(|| wrap(Number::TAU - self.mean_anomaly()) / self.mean_motion())
+    }
+
+    /// Seconds until the vessel next reaches apoapsis, `None` for escaping orbits
+    pub fn time_to_apoapsis(&self) -> Option<Number> {
+        (!self.is_escaping()).then(|| wrap(Number::PI - self.mean_anomaly()) / self.mean_motion())
+    }
+}
+
+/// Brings an angle into `0..TAU`
+fn wrap(angle: Number) -> Number {
+    angle.rem_euclid(Number::TAU)
+}
@@ -0,0 +1,389 @@
+use crate::{
+    control::{RcsConfig, RcsDefinition, ReactionWheelConfig, ReactionWheelDefinition, Thruster},
+    convex_hull::ConvexHull,
+    electric::{ProbeCoreConfig, ProbeCoreDefinition, SolarPanelConfig, SolarPanelDefinition},
+    engine::{EngineConfig, EngineDefinition},
+    joint::{JointConfig, JointDefinition},
+    matrix3::Matrix3,
+    motor::Transform,
+    registry::{Id, Registry},
+    resource::{PartResource, PartResourceConfig, ResourceDefinition},
+    staging::{DecouplerConfig, DecouplerDefinition},
+    thermal::{AblatorConfig, AblatorDefinition, ThermalConfig, ThermalDefinition},
+    vector3::Vector3,
+    Number, Vertex,
+};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+pub type PartId = Id<PartDefinition>;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct PartConfig {
+    pub name: String,
+    pub model: String,
+    pub title: String,
+    pub texture_color_map: String,
+    pub discription: String,
+    /// Dry mass in kilograms
+    pub mass: f64,
+    /// Principal moments of inertia around the part's center of mass, computed from the model
+    /// volume when not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inertia: Option<[f64; 3]>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub resources: Vec<PartResourceConfig>,
+    /// Whether resources can flow through this part to the parts beyond it
+    #[serde(default = "default_crossfeed")]
+    pub crossfeed: bool,
+    /// Drag coefficient times reference area, in square meters
+    #[serde(default = "default_drag")]
+    pub drag: f64,
+    /// Meters per second of impact the part survives
+    #[serde(default = "default_crash_tolerance")]
+    pub crash_tolerance: f64,
+    /// Fraction of the impact speed the part bounces back with
+    #[serde(default = "default_restitution")]
+    pub restitution: f64,
+    /// Coefficient of friction against whatever the part touches
+    #[serde(default = "default_friction")]
+    pub friction: f64,
+    /// How the part is held onto its parent
+    #[serde(default)]
+    pub joint: JointConfig,
+    /// How the part heats up and cools down
+    #[serde(default)]
+    pub thermal: ThermalConfig,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub engine: Option<EngineConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decoupler: Option<DecouplerConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reaction_wheel: Option<ReactionWheelConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rcs: Option<RcsConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub solar_panel: Option<SolarPanelConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub probe_core: Option<ProbeCoreConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ablator: Option<AblatorConfig>,
+}
+
+fn default_crossfeed() -> bool {
+    true
+}
+
+fn default_drag() -> f64 {
+    0.2
+}
+
+fn default_crash_tolerance() -> f64 {
+    8.0
+}
+
+fn default_restitution() -> f64 {
+    0.2
+}
+
+fn default_friction() -> f64 {
+    0.6
+}
+
+/// Everything shared by every instance of a part: its config, geometry and texture
+pub struct PartDefinition {
+    pub config: PartConfig,
+    pub vertices: Vec<Vertex>,
+    /// In part space, built once from `vertices`
+    pub hull: ConvexHull,
+    pub texture_color_map: PathBuf,
+    pub mass: Number,
+    /// In part space
+    pub center_of_mass: Vector3,
+    /// Around `center_of_mass`, in part space
+    pub inertia: Matrix3,
+    /// Drag coefficient times reference area, in square meters
+    pub drag: Number,
+    /// Meters per second of impact the part survives
+    pub crash_tolerance: Number,
+    pub restitution: Number,
+    pub friction: Number,
+    pub joint: JointDefinition,
+    pub thermal: ThermalDefinition,
+    /// What a freshly built part holds
+    pub resources: Vec<PartResource>,
+    pub engine: Option<EngineDefinition>,
+    pub decoupler: Option<DecouplerDefinition>,
+    pub reaction_wheel: Option<ReactionWheelDefinition>,
+    pub rcs: Option<RcsDefinition>,
+    pub solar_panel: Option<SolarPanelDefinition>,
+    pub probe_core: Option<ProbeCoreDefinition>,
+    pub ablator: Option<AblatorDefinition>,
+}
+
+impl PartDefinition {
+    pub fn new(
+        config: PartConfig,
+        model: Model,
+        texture_color_map: PathBuf,
+        resources: &Registry<ResourceDefinition>,
+    ) -> Self {
+        let mass = Number::from_num(config.mass);
+        let drag = Number::from_num(config.drag);
+        let crash_tolerance = Number::from_num(config.crash_tolerance);
+        let restitution = Number::from_num(config.restitution);
+        let friction = Number::from_num(config.friction);
+        let (center_of_mass, mesh_inertia) = mesh_mass_properties(&model.vertices, mass);
+        let inertia = match config.inertia {
+            Some([x, y, z]) => Matrix3::from_diagonal(Vector3::new(
+                Number::from_num(x),
+                Number::from_num(y),
+                Number::from_num(z),
+            )),
+            None => mesh_inertia,
+        };
+        let engine = config.engine.as_ref().map(|engine| {
+            assert!(
+                engine.isp.evaluate(Number::ZERO) > Number::ZERO,
+                "{} needs a positive vacuum isp for its engine",
+                config.name
+            );
+            EngineDefinition::new(engine, resources)
+        });
+        let decoupler = config.decoupler.as_ref().map(DecouplerDefinition::new);
+        let reaction_wheel = config
+            .reaction_wheel
+            .as_ref()
+            .map(|wheel| ReactionWheelDefinition::new(wheel, resources));
+        let rcs = config.rcs.as_ref().map(|rcs| {
+            assert!(
+                rcs.isp.evaluate(Number::ZERO) > Number::ZERO,
+                "{} needs a positive vacuum isp for its rcs",
+                config.name
+            );
+            RcsDefinition::new(rcs, &model.thrusters, resources)
+        });
+        let solar_panel = config
+            .solar_panel
+            .as_ref()
+            .map(|panel| SolarPanelDefinition::new(panel, resources));
+        let probe_core = config
+            .probe_core
+            .as_ref()
+            .map(|core| ProbeCoreDefinition::new(core, resources));
+        let ablator = config.ablator.as_ref().map(|ablator| {
+            assert!(
+                ablator.heat > 0.0,
+                "{} needs its ablator to carry off some heat",
+                config.name
+            );
+            AblatorDefinition::new(ablator, resources)
+        });
+        let resources = config
+            .resources
+            .iter()
+            .map(|resource| {
+                let capacity = Number::from_num(resource.capacity);
+                PartResource {
+                    resource: resources.id(&resource.name).unwrap_or_else(|| {
+                        panic!("{} uses unknown resource {}", config.name, resource.name)
+                    }),
+                    amount: resource.amount.map_or(capacity, Number::from_num),
+                    capacity,
+                }
+            })
+            .collect();
+        let hull = ConvexHull::from_vertices(&model.vertices);
+        let joint = JointDefinition::new(&config.joint, &hull);
+        let thermal = ThermalDefinition::new(&config.thermal, hull.surface_area());
+        Self {
+            config,
+            vertices: model.vertices,
+            hull,
+            texture_color_map,
+            mass,
+            center_of_mass,
+            inertia,
+            drag,
+            crash_tolerance,
+            restitution,
+            friction,
+            joint,
+            thermal,
+            resources,
+            engine,
+            decoupler,
+            reaction_wheel,
+            rcs,
+            solar_panel,
+            probe_core,
+            ablator,
+        }
+    }
+
+    /// Loads the model referenced by `config`, with paths relative to `directory`
+    pub fn load(
+        config: PartConfig,
+        directory: &Path,
+        resources: &Registry<ResourceDefinition>,
+    ) -> Self {
+        let model = load_model(&directory.join(&config.model));
+        let texture_color_map = directory.join(&config.texture_color_map);
+        Self::new(config, model, texture_color_map, resources)
+    }
+}
+
+/// A single placed copy of a part definition
+#[derive(Debug, Clone, Copy)]
+pub struct PartInstance {
+    pub part: PartId,
+    pub transform: Transform,
+}
+
+/// The geometry of a part as loaded from an OBJ file
+#[derive(Debug, Clone, Default)]
+pub struct Model {
+    /// Triangles to render
+    pub vertices: Vec<Vertex>,
+    /// One for every object named `thruster...`, placed at its center and pushing against the
+    /// way its faces point
+    pub thrusters: Vec<Thruster>,
+}
+
+pub fn load_model(path: &Path) -> Model {
+    let object = obj::Obj::load(path).unwrap();
+
+    let mut model = Model::default();
+    for mesh in &object.data.objects {
+        let polys = mesh.groups.iter().flat_map(|group| &group.polys);
+        if mesh.name.starts_with("thruster") {
+            let mut sum = Vector3::ZERO;
+            let mut count = 0;
+            let mut normal = Vector3::ZERO;
+            for poly in polys {
+                let positions = poly
+                    .0
+                    .iter()
+                    .map(|index| {
+                        Vector3::from(cgmath::Vector3::from(object.data.position[index.0]))
+                    })
+                    .collect::<Vec<_>>();
+                for i in 1..positions.len() - 1 {
+                    normal += (positions[i] - positions[0]).cross(positions[i + 1] - positions[0]);
+                }
+                sum = positions.iter().fold(sum, |sum, &position| sum + position);
+                count += positions.len();
+            }
+            assert!(
+                normal.length() > Number::ZERO,
+                "{} in {} has no faces to point it",
+                mesh.name,
+                path.display()
+            );
+            model.thrusters.push(Thruster {
+                position: sum / Number::from_num(count),
+                // The exhaust leaves through the faces, pushing the part the other way
+                direction: -normal / normal.length(),
+            });
+        } else {
+            model
+                .vertices
+                .extend(polys.flat_map(|poly| &poly.0).map(|index| Vertex {
+                    position: object.data.position[index.0].into(),
+                    normal: object.data.normal[index.2.unwrap()].into(),
+                    texture_coords: object.data.texture[index.1.unwrap()].into(),
+                }));
+        }
+    }
+    model
+}
+
+/// Center of mass and inertia tensor (around the center of mass) of the closed triangle mesh
+/// `vertices` filled uniformly with `mass`
+///
+/// Falls back to a solid box filling the mesh's bounds when it encloses no volume, which is
+/// still a point mass for a single point, and to one at the origin without any vertices.
+pub fn mesh_mass_properties(vertices: &[Vertex], mass: Number) -> (Vector3, Matrix3) {
+    // Sum signed tetrahedra between the origin and every triangle, in f64 as the
+    // intermediate products of small triangles underflow the fixed point precision
+    let mut volume = 0.0;
+    let mut first_moment = [0.0; 3];
+    let mut covariance = [[0.0; 3]; 3];
+    for triangle in vertices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| {
+            let position = triangle[i].position;
+            [position.x as f64, position.y as f64, position.z as f64]
+        });
+        let determinant = a[0] * (b[1] * c[2] - b[2] * c[1]) - a[1] * (b[0] * c[2] - b[2] * c[0])
+            + a[2] * (b[0] * c[1] - b[1] * c[0]);
+        volume += determinant / 6.0;
+        for i in 0..3 {
+            first_moment[i] += determinant / 24.0 * (a[i] + b[i] + c[i]);
+            for j in 0..3 {
+                covariance[i][j] += determinant / 120.0
+                    * (2.0 * (a[i] * a[j] + b[i] * b[j] + c[i] * c[j])
+                        + a[i] * b[j]
+                        + a[j] * b[i]
+                        + a[i] * c[j]
+                        + a[j] * c[i]
+                        + b[i] * c[j]
+                        + b[j] * c[i]);
+            }
+        }
+    }
+
+    if volume.abs() < 1e-9 {
+        return bounding_box_mass_properties(vertices, mass);
+    }
+
+    let density = mass.to_num::<f64>() / volume;
+    let center = first_moment.map(|moment| moment / volume);
+    let mut inertia = [[0.0; 3]; 3];
+    for i in 0..3 {
+        for j in 0..3 {
+            // Move the covariance to the center of mass before turning it into an inertia tensor
+            inertia[i][j] = -density * (covariance[i][j] - volume * center[i] * center[j]);
+        }
+    }
+    let trace = -(inertia[0][0] + inertia[1][1] + inertia[2][2]);
+    for (i, row) in inertia.iter_mut().enumerate() {
+        row[i] += trace;
+    }
+
+    let column = |j: usize| {
+        Vector3::new(
+            Number::from_num(inertia[0][j]),
+            Number::from_num(inertia[1][j]),
+            Number::from_num(inertia[2][j]),
+        )
+    };
+    (
+        Vector3::new(
+            Number::from_num(center[0]),
+            Number::from_num(center[1]),
+            Number::from_num(center[2]),
+        ),
+        Matrix3::from_columns(column(0), column(1), column(2)),
+    )
+}
+
+fn bounding_box_mass_properties(vertices: &[Vertex], mass: Number) -> (Vector3, Matrix3) {
+    let Some(first) = vertices.first() else {
+        return (Vector3::ZERO, Matrix3::ZERO);
+    };
+    let (min, max) =
+        vertices
+            .iter()
+            .fold((first.position, first.position), |(min, max), vertex| {
+                (
+                    min.zip(vertex.position, f32::min),
+                    max.zip(vertex.position, f32::max),
+                )
+            });
+    let center = Vector3::from((min + max) / 2.0);
+    let size = max - min;
+    let [x, y, z] = [size.x, size.y, size.z].map(|size| Number::from_num(size * size));
+    let inertia = Vector3::new(y + z, x + z, x + y) * mass / Number::from_num(12);
+    (center, Matrix3::from_diagonal(inertia))
+}
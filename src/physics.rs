@@ -0,0 +1,128 @@
+use crate::{
+    light::Sunlight,
+    motor::Transform,
+    vector3::Vector3,
+    vessel::{MassProperties, Vessel},
+    Number,
+};
+
+/// Conditions around a vessel
+#[derive(Debug, Clone, Copy)]
+pub struct Environment {
+    /// Acceleration in world space
+    pub gravity: Vector3,
+    /// Atmospheres
+    pub pressure: Number,
+    /// Kilograms per cubic meter of air
+    pub density: Number,
+    /// Kelvin of the air, only meaningful where there's some
+    pub air_temperature: Number,
+    /// World space velocity the air moves at, turning along with the body it surrounds
+    pub air_velocity: Vector3,
+    /// Unit length world space direction towards the star, zero without one
+    pub sun_direction: Vector3,
+    /// Watts per square meter of sunlight reaching the vessel, zero in a body's shadow
+    pub solar_flux: Number,
+}
+
+impl Environment {
+    pub const VACUUM: Self = Self {
+        gravity: Vector3::ZERO,
+        pressure: Number::ZERO,
+        density: Number::ZERO,
+        air_temperature: Number::ZERO,
+        air_velocity: Vector3::ZERO,
+        sun_direction: Vector3::ZERO,
+        solar_flux: Number::ZERO,
+    };
+
+    /// The same conditions with the sunlight at a world space position added
+    pub fn with_sunlight(self, sunlight: &Sunlight, position: Vector3) -> Self {
+        Self {
+            sun_direction: sunlight.direction(position).unwrap_or(Vector3::ZERO),
+            solar_flux: sunlight.flux(position) * sunlight.solar_exposure(position),
+            ..self
+        }
+    }
+}
+
+/// Force and torque around the center of mass, both in world space
+#[derive(Debug, Clone, Copy)]
+pub struct Forces {
+    pub force: Vector3,
+    pub torque: Vector3,
+}
+
+impl Forces {
+    pub const ZERO: Self = Self {
+        force: Vector3::ZERO,
+        torque: Vector3::ZERO,
+    };
+
+    /// Adds `force` applied at `point`, where both `point` and `center_of_mass` are in world space
+    pub fn add_at(&mut self, force: Vector3, point: Vector3, center_of_mass: Vector3) {
+        self.force += force;
+        self.torque += (point - center_of_mass).cross(force);
+    }
+}
+
+/// Instantly changes the velocity of `vessel` as if `impulse` was applied at `point`, both in
+/// world space
+pub fn apply_impulse(
+    vessel: &mut Vessel,
+    mass_properties: &MassProperties,
+    impulse: Vector3,
+    point: Vector3,
+) {
+    if mass_properties.mass > Number::ZERO {
+        vessel.velocity += impulse / mass_properties.mass;
+    }
+    let center_of_mass = vessel
+        .transform
+        .transform_point(mass_properties.center_of_mass);
+    if let Some(inverse_inertia) = mass_properties
+        .transformed_inertia(vessel.transform)
+        .inverse()
+    {
+        vessel.angular_velocity += inverse_inertia * (point - center_of_mass).cross(impulse);
+    }
+}
+
+/// Advances `vessel` by `dt` seconds with semi-implicit Euler, rotating it around its center of
+/// mass
+pub fn integrate(
+    vessel: &mut Vessel,
+    mass_properties: &MassProperties,
+    forces: &Forces,
+    gravity: Vector3,
+    dt: Number,
+) {
+    if mass_properties.mass > Number::ZERO {
+        vessel.velocity += (forces.force / mass_properties.mass + gravity) * dt;
+    }
+
+    let inertia = mass_properties.transformed_inertia(vessel.transform);
+    if let Some(inverse_inertia) = inertia.inverse() {
+        let angular_momentum = inertia * vessel.angular_velocity;
+        let gyroscopic = vessel.angular_velocity.cross(angular_momentum);
+        vessel.angular_velocity += inverse_inertia * (forces.torque - gyroscopic) * dt;
+    }
+
+    let center_of_mass = vessel
+        .transform
+        .transform_point(mass_properties.center_of_mass);
+    let angular_speed = vessel.angular_velocity.length();
+    let rotation = if angular_speed > Number::ZERO {
+        Transform::rotation(vessel.angular_velocity / angular_speed, angular_speed * dt)
+    } else {
+        Transform::IDENTITY
+    };
+    vessel.transform = vessel
+        .transform
+        .apply(Transform::translation(-center_of_mass))
+        .apply(rotation)
+        .apply(Transform::translation(
+            center_of_mass + vessel.velocity * dt,
+        ))
+        .normalized();
+}
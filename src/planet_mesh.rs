@@ -0,0 +1,393 @@
+use crate::{atan2, body::Body, geodetic::Geodetic, vector3::Vector3, Number, Vertex};
+use std::collections::{HashMap, HashSet};
+
+/// How finely a planet gets split up around the camera
+#[derive(Debug, Clone, Copy)]
+pub struct LodSettings {
+    /// Quads along each edge of a chunk, a power of two
+    pub resolution: u32,
+    /// Most times a cube face gets split in four
+    pub max_level: u32,
+    /// Chunks split once the camera is closer than this many times their width
+    pub split_distance: Number,
+}
+
+impl Default for LodSettings {
+    fn default() -> Self {
+        Self {
+            resolution: 16,
+            max_level: 14,
+            split_distance: Number::from_num(2),
+        }
+    }
+}
+
+/// A square piece of one of the six faces of the cube the planet is inflated from
+///
+/// Face coordinates u and v run from -1 to 1 across each face, and a chunk at `level` is one of
+/// 2^level by 2^level squares, counted by `x` along u and `y` along v.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ChunkId {
+    pub face: usize,
+    pub level: u32,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl ChunkId {
+    /// A whole face
+    pub fn root(face: usize) -> Self {
+        Self {
+            face,
+            level: 0,
+            x: 0,
+            y: 0,
+        }
+    }
+
+    /// Width in face coordinates
+    pub fn size(self) -> Number {
+        Number::from_num(2) >> self.level
+    }
+
+    /// Face coordinates of the corner with the lowest u and v
+    pub fn min(self) -> (Number, Number) {
+        let size = self.size();
+        (
+            size * Number::from_num(self.x) - Number::ONE,
+            size * Number::from_num(self.y) - Number::ONE,
+        )
+    }
+
+    pub fn children(self) -> [ChunkId; 4] {
+        [(0, 0), (1, 0), (0, 1), (1, 1)].map(|(x, y)| ChunkId {
+            face: self.face,
+            level: self.level + 1,
+            x: self.x * 2 + x,
+            y: self.y * 2 + y,
+        })
+    }
+
+    /// The chunk at `level` holding a point on `face`
+    fn containing(face: usize, level: u32, u: Number, v: Number) -> Self {
+        let last = (1u32 << level) - 1;
+        let index = |coordinate: Number| {
+            ((coordinate + Number::ONE) / (Number::from_num(2) >> level))
+                .floor()
+                .clamp(Number::ZERO, Number::from_num(last))
+                .to_num::<u32>()
+        };
+        Self {
+            face,
+            level,
+            x: index(u),
+            y: index(v),
+        }
+    }
+}
+
+/// Outwards normal of a cube face, then the axes u and v grow along, with u × v along the
+/// normal so that the grid winds counterclockwise seen from outside
+fn face_axes(face: usize) -> [Vector3; 3] {
+    let [x, y, z] = [Vector3::X, Vector3::Y, Vector3::Z];
+    match face {
+        0 => [x, y, z],
+        1 => [-x, z, y],
+        2 => [y, z, x],
+        3 => [-y, x, z],
+        4 => [z, x, y],
+        _ => [-z, y, x],
+    }
+}
+
+/// Point on the cube at face coordinates, which can reach past the face's edges
+fn cube_point(face: usize, u: Number, v: Number) -> Vector3 {
+    let [normal, u_axis, v_axis] = face_axes(face);
+    normal + u_axis * u + v_axis * v
+}
+
+/// The face a point on or around the cube is over, and its face coordinates there
+fn face_of(point: Vector3) -> (usize, Number, Number) {
+    let face = (0..6)
+        .max_by_key(|&face| face_axes(face)[0].dot(point))
+        .unwrap();
+    let [normal, u_axis, v_axis] = face_axes(face);
+    let distance = normal.dot(point);
+    (
+        face,
+        u_axis.dot(point) / distance,
+        v_axis.dot(point) / distance,
+    )
+}
+
+/// Latitude and longitude of the body-fixed direction through a point on the cube
+fn latitude_longitude(point: Vector3) -> (Number, Number) {
+    let latitude = atan2(point.z, (point.x * point.x + point.y * point.y).sqrt());
+    let longitude = atan2(point.y, point.x);
+    (latitude, longitude)
+}
+
+/// Body-fixed ground under a point on the cube
+fn ground_point(body: &Body, point: Vector3) -> Vector3 {
+    let (latitude, longitude) = latitude_longitude(point);
+    let height = body.terrain_height(latitude, longitude);
+    body.ellipsoid()
+        .to_cartesian(Geodetic::new(latitude, longitude, height))
+}
+
+/// Texture coordinates of a triangle's corners from their latitude and longitude, which a
+/// corner on a pole doesn't have
+///
+/// The longitude jumps a whole turn across the antimeridian, so corners are unwrapped to
+/// within half a turn of the first one off a pole, reaching past the edge of the texture if
+/// need be, and a corner on a pole takes the average longitude of the others.
+fn triangle_texture_coords(corners: [(Number, Option<Number>); 3]) -> [cgmath::Vector2<f32>; 3] {
+    let reference = corners
+        .iter()
+        .find_map(|&(_, longitude)| longitude)
+        .unwrap_or(Number::ZERO);
+    let unwrapped = corners.map(|(_, longitude)| {
+        longitude.map(|longitude| {
+            reference + (longitude - reference + Number::PI).rem_euclid(Number::TAU) - Number::PI
+        })
+    });
+    let off_pole = unwrapped.iter().flatten().count();
+    let average = if off_pole > 0 {
+        unwrapped.iter().flatten().sum::<Number>() / Number::from_num(off_pole)
+    } else {
+        reference
+    };
+    [0, 1, 2].map(|corner| {
+        let (latitude, _) = corners[corner];
+        let longitude = unwrapped[corner].unwrap_or(average);
+        cgmath::Vector2::new(
+            ((longitude + Number::PI) / Number::TAU).to_num::<f32>(),
+            ((Number::FRAC_PI_2 - latitude) / Number::PI).to_num::<f32>(),
+        )
+    })
+}
+
+/// The mesh of one leaf of the quadtree
+pub struct Chunk {
+    pub id: ChunkId,
+    /// Level of the leaf across each edge when it's coarser than this chunk, for the edges at
+    /// the lowest v, highest u, highest v and lowest u in that order
+    pub coarser_neighbours: [Option<u32>; 4],
+    /// Body-fixed, what `vertices` are relative to
+    pub origin: Vector3,
+    /// Body-fixed ground under each grid point, row by row with u growing along each row
+    pub positions: Vec<Vector3>,
+    /// Triangles relative to `origin`, ready for the vertex storage buffer
+    pub vertices: Vec<Vertex>,
+}
+
+impl Chunk {
+    /// Samples the terrain on a grid of `resolution` quads across, moving the points along
+    /// edges with a coarser neighbour onto that neighbour's edge so that no cracks open up
+    pub fn new(
+        id: ChunkId,
+        coarser_neighbours: [Option<u32>; 4],
+        body: &Body,
+        resolution: u32,
+    ) -> Self {
+        let (u0, v0) = id.min();
+        let step = id.size() / Number::from_num(resolution);
+        let coordinate = |start: Number, index: u32| start + step * Number::from_num(index);
+        let count = resolution as usize + 1;
+
+        let mut positions = Vec::with_capacity(count * count);
+        let mut normals = Vec::with_capacity(count * count);
+        let mut angles = Vec::with_capacity(count * count);
+        for j in 0..=resolution {
+            for i in 0..=resolution {
+                let (u, v) = (coordinate(u0, i), coordinate(v0, j));
+                let point = cube_point(id.face, u, v);
+                let (latitude, longitude) = latitude_longitude(point);
+                let edge = [
+                    (j == 0, 0, u),
+                    (i == resolution, 1, v),
+                    (j == resolution, 2, u),
+                    (i == 0, 3, v),
+                ]
+                .into_iter()
+                .find_map(|(on_edge, edge, along)| {
+                    let level = coarser_neighbours[edge].filter(|_| on_edge)?;
+                    Some((edge, along, level))
+                });
+                let position = match edge {
+                    Some((edge, along, level)) => {
+                        // Where the coarser neighbour's own grid points fall along the edge
+                        let coarse_step =
+                            (Number::from_num(2) >> level) / Number::from_num(resolution);
+                        let before = ((along + Number::ONE) / coarse_step).floor() * coarse_step
+                            - Number::ONE;
+                        let fraction = (along - before) / coarse_step;
+                        let point_at = |along: Number| {
+                            let (u, v) = if edge % 2 == 0 {
+                                (along, v)
+                            } else {
+                                (u, along)
+                            };
+                            ground_point(body, cube_point(id.face, u, v))
+                        };
+                        let start = point_at(before);
+                        if fraction == Number::ZERO {
+                            start
+                        } else {
+                            start + (point_at(before + coarse_step) - start) * fraction
+                        }
+                    }
+                    None => ground_point(body, point),
+                };
+                positions.push(position);
+                normals.push(body.surface_normal(latitude, longitude));
+                let on_pole = point.x == Number::ZERO && point.y == Number::ZERO;
+                angles.push((latitude, (!on_pole).then_some(longitude)));
+            }
+        }
+
+        let origin = positions[positions.len() / 2];
+        let vertex = |index: usize, texture_coords: cgmath::Vector2<f32>| {
+            let position = positions[index] - origin;
+            let normal = normals[index];
+            Vertex {
+                position: cgmath::Vector3::new(
+                    position.x.to_num(),
+                    position.y.to_num(),
+                    position.z.to_num(),
+                ),
+                normal: cgmath::Vector3::new(
+                    normal.x.to_num(),
+                    normal.y.to_num(),
+                    normal.z.to_num(),
+                ),
+                texture_coords,
+            }
+        };
+        let mut vertices = Vec::with_capacity(resolution as usize * resolution as usize * 6);
+        for j in 0..resolution as usize {
+            for i in 0..resolution as usize {
+                let corner = j * count + i;
+                let [a, b, c, d] = [corner, corner + 1, corner + count + 1, corner + count];
+                for triangle in [[a, b, c], [a, c, d]] {
+                    let texture_coords =
+                        triangle_texture_coords(triangle.map(|index| angles[index]));
+                    vertices.extend(
+                        (0..3).map(|corner| vertex(triangle[corner], texture_coords[corner])),
+                    );
+                }
+            }
+        }
+
+        Self {
+            id,
+            coarser_neighbours,
+            origin,
+            positions,
+            vertices,
+        }
+    }
+}
+
+/// A planet's surface as a quadtree on each face of a cube, finest close to the camera
+pub struct PlanetMesh {
+    pub settings: LodSettings,
+    chunks: HashMap<ChunkId, Chunk>,
+}
+
+impl PlanetMesh {
+    pub fn new(settings: LodSettings) -> Self {
+        Self {
+            settings,
+            chunks: HashMap::new(),
+        }
+    }
+
+    /// Every chunk making up the surface right now, in no particular order
+    pub fn chunks(&self) -> impl Iterator<Item = &Chunk> {
+        self.chunks.values()
+    }
+
+    /// Splits chunks close to `camera`, a body-fixed position, merges ones far away again and
+    /// builds the mesh of every chunk that changed, returning whether any did
+    pub fn update(&mut self, body: &Body, camera: Vector3) -> bool {
+        let mut leaves = HashSet::new();
+        for face in 0..6 {
+            self.split(body, camera, ChunkId::root(face), &mut leaves);
+        }
+        self.balance(&mut leaves);
+
+        let count = self.chunks.len();
+        self.chunks.retain(|id, _| leaves.contains(id));
+        let mut changed = self.chunks.len() != count;
+        for &id in &leaves {
+            let coarser = [0, 1, 2, 3].map(|edge| {
+                let neighbour = self.neighbour(&leaves, id, edge)?;
+                (neighbour.level < id.level).then_some(neighbour.level)
+            });
+            if self
+                .chunks
+                .get(&id)
+                .is_none_or(|chunk| chunk.coarser_neighbours != coarser)
+            {
+                let chunk = Chunk::new(id, coarser, body, self.settings.resolution);
+                self.chunks.insert(id, chunk);
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    fn split(&self, body: &Body, camera: Vector3, id: ChunkId, leaves: &mut HashSet<ChunkId>) {
+        let (u, v) = id.min();
+        let half = id.size() / 2;
+        let center = cube_point(id.face, u + half, v + half);
+        let center = center / center.length() * body.radius;
+        let width = body.radius * id.size();
+        if id.level < self.settings.max_level
+            && (center - camera).length() < width * self.settings.split_distance
+        {
+            for child in id.children() {
+                self.split(body, camera, child, leaves);
+            }
+        } else {
+            leaves.insert(id);
+        }
+    }
+
+    /// Splits leaves until no two neighbours are more than a level apart, which keeps the
+    /// stitched edges close to the terrain
+    fn balance(&self, leaves: &mut HashSet<ChunkId>) {
+        loop {
+            let too_coarse = leaves.iter().find_map(|&id| {
+                (0..4)
+                    .filter_map(|edge| self.neighbour(leaves, id, edge))
+                    .find(|neighbour| neighbour.level + 1 < id.level)
+            });
+            let Some(too_coarse) = too_coarse else {
+                return;
+            };
+            leaves.remove(&too_coarse);
+            leaves.extend(too_coarse.children());
+        }
+    }
+
+    /// The leaf just across the middle of an edge, which may be on another face
+    fn neighbour(&self, leaves: &HashSet<ChunkId>, id: ChunkId, edge: usize) -> Option<ChunkId> {
+        let (u0, v0) = id.min();
+        let size = id.size();
+        let half = size / 2;
+        // Less than the smallest chunk, so it never reaches past the neighbour
+        let nudge = (Number::from_num(2) >> self.settings.max_level) / 4;
+        let (u, v) = match edge {
+            0 => (u0 + half, v0 - nudge),
+            1 => (u0 + size + nudge, v0 + half),
+            2 => (u0 + half, v0 + size + nudge),
+            _ => (u0 - nudge, v0 + half),
+        };
+        let (face, u, v) = face_of(cube_point(id.face, u, v));
+        (0..=self.settings.max_level)
+            .map(|level| ChunkId::containing(face, level, u, v))
+            .find(|id| leaves.contains(id))
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::ops::Index;
+
+/// Index of a definition inside a [`Registry`]
+pub struct Id<T> {
+    index: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Id<T> {
+    pub fn index(self) -> usize {
+        self.index
+    }
+}
+
+impl<T> Clone for Id<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Id<T> {}
+
+impl<T> PartialEq for Id<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+    }
+}
+
+impl<T> Eq for Id<T> {}
+
+impl<T> Hash for Id<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for Id<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Id").field(&self.index).finish()
+    }
+}
+
+/// Definitions loaded from `GameData`, looked up by their unique name
+pub struct Registry<T> {
+    items: Vec<T>,
+    names: Vec<String>,
+    ids: HashMap<String, Id<T>>,
+}
+
+impl<T> Registry<T> {
+    pub fn new() -> Self {
+        Self {
+            items: Vec::new(),
+            names: Vec::new(),
+            ids: HashMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, name: &str, item: T) -> Id<T> {
+        let id = Id {
+            index: self.items.len(),
+            _marker: PhantomData,
+        };
+        if self.ids.insert(name.into(), id).is_some() {
+            panic!("{name} defined multiple times");
+        }
+        self.items.push(item);
+        self.names.push(name.into());
+        id
+    }
+
+    pub fn id(&self, name: &str) -> Option<Id<T>> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: Id<T>) -> &str {
+        &self.names[id.index]
+    }
+
+    pub fn get(&self, id: Id<T>) -> &T {
+        &self.items[id.index]
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Id<T>, &T)> {
+        self.items.iter().enumerate().map(|(index, item)| {
+            (
+                Id {
+                    index,
+                    _marker: PhantomData,
+                },
+                item,
+            )
+        })
+    }
+}
+
+impl<T> Default for Registry<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Index<Id<T>> for Registry<T> {
+    type Output = T;
+
+    fn index(&self, id: Id<T>) -> &Self::Output {
+        self.get(id)
+    }
+}
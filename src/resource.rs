@@ -0,0 +1,91 @@
+use crate::{
+    registry::{Id, Registry},
+    Number,
+};
+use serde::{Deserialize, Serialize};
+
+pub type ResourceId = Id<ResourceDefinition>;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ResourceConfig {
+    pub name: String,
+    pub title: String,
+    /// Kilograms per unit
+    pub density: f64,
+    #[serde(default)]
+    pub flow_mode: FlowMode,
+}
+
+/// Which parts a consumer may draw a resource from
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum FlowMode {
+    /// Only from the consuming part itself
+    NoFlow,
+    /// From every part reachable through parts allowing crossfeed, farthest parts first
+    #[default]
+    StackPriority,
+    /// From every part on the vessel, ignoring crossfeed
+    AllVessel,
+}
+
+pub struct ResourceDefinition {
+    pub config: ResourceConfig,
+    pub density: Number,
+}
+
+impl ResourceDefinition {
+    pub fn new(config: ResourceConfig) -> Self {
+        Self {
+            density: Number::from_num(config.density),
+            config,
+        }
+    }
+}
+
+/// How much of a resource a part can hold, as declared in its config
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct PartResourceConfig {
+    pub name: String,
+    pub capacity: f64,
+    /// Defaults to `capacity`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount: Option<f64>,
+}
+
+/// A resource stored in a part
+#[derive(Debug, Clone, Copy)]
+pub struct PartResource {
+    pub resource: ResourceId,
+    pub amount: Number,
+    pub capacity: Number,
+}
+
+/// A resource used continuously, as declared in a part config
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ResourceRateConfig {
+    pub name: String,
+    /// Units per second
+    pub rate: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ResourceRate {
+    pub resource: ResourceId,
+    /// Units per second
+    pub rate: Number,
+}
+
+impl ResourceRate {
+    pub fn new(config: &ResourceRateConfig, resources: &Registry<ResourceDefinition>) -> Self {
+        Self {
+            resource: resources
+                .id(&config.name)
+                .unwrap_or_else(|| panic!("unknown resource {}", config.name)),
+            rate: Number::from_num(config.rate),
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use crate::{atan2, motor::Transform, vector3::Vector3, vessel::Vessel, Number};
+
+/// A PID controller running on every axis of a vector at once
+#[derive(Debug, Clone, Copy)]
+pub struct Pid {
+    pub proportional: Number,
+    pub integral: Number,
+    pub derivative: Number,
+    /// The accumulated error is kept within this on every axis so it can't wind up while the
+    /// output is saturated
+    pub integral_limit: Number,
+    accumulated: Vector3,
+    previous_error: Option<Vector3>,
+}
+
+impl Pid {
+    pub fn new(proportional: Number, integral: Number, derivative: Number) -> Self {
+        Self {
+            proportional,
+            integral,
+            derivative,
+            integral_limit: Number::ONE,
+            accumulated: Vector3::ZERO,
+            previous_error: None,
+        }
+    }
+
+    /// Forgets the accumulated and previous error
+    pub fn reset(&mut self) {
+        self.accumulated = Vector3::ZERO;
+        self.previous_error = None;
+    }
+
+    pub fn update(&mut self, error: Vector3, dt: Number) -> Vector3 {
+        let clamp = |value: Number| value.clamp(-self.integral_limit, self.integral_limit);
+        let accumulated = self.accumulated + error * dt;
+        self.accumulated = Vector3::new(
+            clamp(accumulated.x),
+            clamp(accumulated.y),
+            clamp(accumulated.z),
+        );
+        let derivative = match self.previous_error {
+            Some(previous) if dt > Number::ZERO => (error - previous) / dt,
+            _ => Vector3::ZERO,
+        };
+        self.previous_error = Some(error);
+        error * self.proportional + self.accumulated * self.integral + derivative * self.derivative
+    }
+}
+
+/// What the stability assist points the vessel at
+#[derive(Debug, Clone, Copy)]
+pub enum SasMode {
+    /// Match the rotation of a world space transform
+    Hold(Transform),
+    Prograde,
+    Retrograde,
+    Normal,
+    AntiNormal,
+    RadialOut,
+    RadialIn,
+    /// Point at a world space position
+    Target(Vector3),
+    /// Point along a world space direction
+    Direction(Vector3),
+}
+
+/// What the direction modes are measured against, usually the body being orbited
+#[derive(Debug, Clone, Copy)]
+pub struct Reference {
+    /// In world space
+    pub position: Vector3,
+    /// In world space
+    pub velocity: Vector3,
+}
+
+impl Reference {
+    pub const ORIGIN: Self = Self {
+        position: Vector3::ZERO,
+        velocity: Vector3::ZERO,
+    };
+}
+
+/// Stability assist, turning a vessel to face its target through two PID controllers
+///
+/// Every mode apart from [`SasMode::Hold`] points the vessel's +Y axis and leaves roll alone.
+#[derive(Debug, Clone, Copy)]
+pub struct Sas {
+    pub mode: SasMode,
+    /// Turns the attitude error in radians into the wanted angular velocity
+    pub attitude: Pid,
+    /// Turns the angular velocity error into control input
+    pub rate: Pid,
+    /// Radians per second the vessel may turn at
+    pub max_rate: Number,
+}
+
+impl Sas {
+    pub fn new(mode: SasMode) -> Self {
+        Self {
+            mode,
+            attitude: Pid::new(Number::ONE, Number::ZERO, Number::ZERO),
+            rate: Pid::new(Number::from_num(4), Number::from_num(0.5), Number::ZERO),
+            max_rate: Number::from_num(0.5),
+        }
+    }
+
+    pub fn set_mode(&mut self, mode: SasMode) {
+        self.mode = mode;
+        self.attitude.reset();
+        self.rate.reset();
+    }
+
+    /// Rotation vector in vessel space that would turn `vessel` to face its target
+    pub fn attitude_error(&self, vessel: &Vessel, reference: &Reference) -> Vector3 {
+        let rotation = vessel.transform.rotation_part();
+        let position = vessel.transform.position() - reference.position;
+        let velocity = vessel.velocity - reference.velocity;
+        let normal = position.cross(velocity);
+        let direction = match self.mode {
+            SasMode::Hold(target) => {
+                let error = rotation.inverse().apply(target.rotation_part());
+                return rotation
+                    .inverse()
+                    .transform_direction(error.rotation_vector());
+            }
+            SasMode::Prograde => velocity,
+            SasMode::Retrograde => -velocity,
+            SasMode::Normal => normal,
+            SasMode::AntiNormal => -normal,
+            SasMode::RadialOut => velocity.cross(normal),
+            SasMode::RadialIn => normal.cross(velocity),
+            SasMode::Target(target) => target - vessel.transform.position(),
+            SasMode::Direction(direction) => direction,
+        };
+        if direction.sqr_length() == Number::ZERO {
+            return Vector3::ZERO;
+        }
+
+        let forward = rotation.transform_direction(Vector3::Y);
+        let direction = direction / direction.length();
+        let mut axis = forward.cross(direction);
+        let sin = axis.length();
+        let cos = forward.dot(direction);
+        if sin == Number::ZERO {
+            if cos > Number::ZERO {
+                return Vector3::ZERO;
+            }
+            // Facing exactly away, so any sideways axis will do
+            axis = rotation.transform_direction(Vector3::X);
+        } else {
+            axis /= sin;
+        }
+        rotation
+            .inverse()
+            .transform_direction(axis * atan2(sin, cos))
+    }
+
+    /// Sets the rotation part of the control input of `vessel` for the next `dt` seconds
+    pub fn update(&mut self, vessel: &mut Vessel, reference: &Reference, dt: Number) {
+        let error = self.attitude_error(vessel, reference);
+        let mut wanted_rate = self.attitude.update(error, dt);
+        let wanted_speed = wanted_rate.length();
+        if wanted_speed > self.max_rate {
+            wanted_rate = wanted_rate / wanted_speed * self.max_rate;
+        }
+
+        let angular_velocity = vessel
+            .transform
+            .inverse()
+            .transform_direction(vessel.angular_velocity);
+        let command = self.rate.update(wanted_rate - angular_velocity, dt);
+        vessel.control.pitch = command.x;
+        vessel.control.roll = command.y;
+        vessel.control.yaw = command.z;
+    }
+}
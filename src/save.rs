@@ -0,0 +1,161 @@
+use crate::{
+    control::ControlInput,
+    game_data::GameData,
+    motor::Transform,
+    resource::PartResource,
+    thermal,
+    vector3::Vector3,
+    vessel::{Vessel, VesselPart},
+    Number,
+};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Definitions are referred to by name, as ids are only stable within one load of `GameData`
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct SaveFile {
+    pub vessels: Vec<VesselSave>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct VesselSave {
+    pub transform: Transform,
+    pub velocity: Vector3,
+    pub angular_velocity: Vector3,
+    pub parts: Vec<VesselPartSave>,
+    pub throttle: Number,
+    pub current_stage: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct VesselPartSave {
+    pub part: String,
+    pub transform: Transform,
+    pub parent: Option<usize>,
+    pub resources: Vec<PartResourceSave>,
+    pub crossfeed: bool,
+    pub stage: Option<usize>,
+    pub activated: bool,
+    #[serde(default)]
+    pub deployed: bool,
+    /// Kelvin
+    #[serde(default = "default_temperature")]
+    pub temperature: Number,
+}
+
+fn default_temperature() -> Number {
+    thermal::ROOM_TEMPERATURE
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct PartResourceSave {
+    pub resource: String,
+    pub amount: Number,
+    pub capacity: Number,
+}
+
+impl SaveFile {
+    pub fn new(vessels: &[Vessel], data: &GameData) -> Self {
+        Self {
+            vessels: vessels
+                .iter()
+                .map(|vessel| VesselSave::new(vessel, data))
+                .collect(),
+        }
+    }
+
+    pub fn vessels(&self, data: &GameData) -> Vec<Vessel> {
+        self.vessels
+            .iter()
+            .map(|vessel| vessel.vessel(data))
+            .collect()
+    }
+
+    pub fn load(path: &Path) -> Self {
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    pub fn save(&self, path: &Path) {
+        std::fs::write(path, serde_json::to_string_pretty(self).unwrap()).unwrap();
+    }
+}
+
+impl VesselSave {
+    pub fn new(vessel: &Vessel, data: &GameData) -> Self {
+        Self {
+            transform: vessel.transform,
+            velocity: vessel.velocity,
+            angular_velocity: vessel.angular_velocity,
+            parts: vessel
+                .parts
+                .iter()
+                .map(|part| VesselPartSave {
+                    part: data.parts.name(part.part).into(),
+                    transform: part.transform,
+                    parent: part.parent,
+                    resources: part
+                        .resources
+                        .iter()
+                        .map(|stored| PartResourceSave {
+                            resource: data.resources.name(stored.resource).into(),
+                            amount: stored.amount,
+                            capacity: stored.capacity,
+                        })
+                        .collect(),
+                    crossfeed: part.crossfeed,
+                    stage: part.stage,
+                    activated: part.activated,
+                    deployed: part.deployed,
+                    temperature: part.temperature,
+                })
+                .collect(),
+            throttle: vessel.throttle,
+            current_stage: vessel.current_stage,
+        }
+    }
+
+    pub fn vessel(&self, data: &GameData) -> Vessel {
+        Vessel {
+            transform: self.transform,
+            velocity: self.velocity,
+            angular_velocity: self.angular_velocity,
+            parts: self
+                .parts
+                .iter()
+                .map(|part| VesselPart {
+                    part: data
+                        .parts
+                        .id(&part.part)
+                        .unwrap_or_else(|| panic!("unknown part {}", part.part)),
+                    transform: part.transform,
+                    parent: part.parent,
+                    resources: part
+                        .resources
+                        .iter()
+                        .map(|stored| PartResource {
+                            resource: data
+                                .resources
+                                .id(&stored.resource)
+                                .unwrap_or_else(|| panic!("unknown resource {}", stored.resource)),
+                            amount: stored.amount,
+                            capacity: stored.capacity,
+                        })
+                        .collect(),
+                    crossfeed: part.crossfeed,
+                    stage: part.stage,
+                    activated: part.activated,
+                    deployed: part.deployed,
+                    temperature: part.temperature,
+                    flex: None,
+                })
+                .collect(),
+            throttle: self.throttle,
+            control: ControlInput::NEUTRAL,
+            current_stage: self.current_stage,
+        }
+    }
+}
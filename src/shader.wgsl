@@ -1,5 +1,6 @@
 struct Camera {
     aspect: f32,
+    sun_direction: vec3<f32>,
 }
 
 @group(0)
@@ -8,6 +9,7 @@ var<uniform> camera: Camera;
 
 struct Mesh {
     transform: Transform,
+    exposure: f32,
 }
 
 struct Meshes {
@@ -38,6 +40,7 @@ struct VertexOutput {
     @location(1) position: vec3<f32>,
     @location(2) normal: vec3<f32>,
     @location(3) texture_coords: vec2<f32>,
+    @location(4) exposure: f32,
 }
 
 @vertex
@@ -62,6 +65,7 @@ fn vs_main(
     out.position = position;
     out.normal = normal;
     out.texture_coords = vertex.texture_coords;
+    out.exposure = mesh.exposure;
 
     return out;
 }
@@ -71,10 +75,15 @@ var texture: texture_2d<f32>;
 @group(2) @binding(1)
 var texture_sampler: sampler;
 
+// Light that reaches surfaces facing away from the star, so they aren't pitch black
+const AMBIENT: f32 = 0.05;
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let light = dot(in.normal, -normalize(in.position)) * 0.5 + 0.5;
-    return textureSample(texture, texture_sampler, in.texture_coords);
+    let sunlight = max(dot(normalize(in.normal), camera.sun_direction), 0.0) * in.exposure;
+    let light = AMBIENT + (1.0 - AMBIENT) * sunlight;
+    let color = textureSample(texture, texture_sampler, in.texture_coords);
+    return vec4<f32>(color.rgb * light, color.a);
 }
 
 struct Point {
@@ -0,0 +1,84 @@
+use crate::{body::Body, game_data::GameData, vessel::Vessel, Number};
+use serde::{Deserialize, Serialize};
+
+/// Meters above the ground or sea the lowest corner of a vessel can be while it still counts as
+/// resting on it, a little more than contacts leave between them
+const SURFACE_HEIGHT: Number = Number::lit("0.1");
+
+/// Where a vessel is relative to the body it's around
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Situation {
+    Landed,
+    /// Down in an ocean
+    Splashed,
+    /// In the atmosphere, below the body's flying high altitude
+    FlyingLow,
+    FlyingHigh,
+    /// Above the atmosphere, below the body's space high altitude
+    SpaceLow,
+    SpaceHigh,
+}
+
+/// Meters above sea level that split the low and high situations of a body
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SituationAltitudes {
+    pub flying_high: Number,
+    pub space_high: Number,
+}
+
+/// The situation of `vessel` around `body`
+pub fn situation(vessel: &Vessel, data: &GameData, body: &Body) -> Situation {
+    let geodetic = body.to_geodetic(vessel.transform.position());
+    // How close the lowest corner is to the ground, and to sea level
+    let (above_ground, above_sea) = vessel
+        .parts
+        .iter()
+        .enumerate()
+        .flat_map(|(index, part)| {
+            let hull = &data.parts[part.part].hull;
+            let transform = vessel.part_transform(index);
+            let corners = if hull.is_empty() {
+                vec![transform.position()]
+            } else {
+                hull.vertices
+                    .iter()
+                    .map(|&vertex| transform.transform_point(vertex))
+                    .collect()
+            };
+            corners.into_iter().map(|corner| {
+                let corner = body.to_geodetic(corner);
+                (
+                    corner.altitude - body.terrain_height(corner.latitude, corner.longitude),
+                    corner.altitude,
+                )
+            })
+        })
+        .fold(
+            (Number::MAX, Number::MAX),
+            |(ground, sea), (corner_ground, corner_sea)| {
+                (ground.min(corner_ground), sea.min(corner_sea))
+            },
+        );
+    let under_water =
+        body.ocean && body.terrain_height(geodetic.latitude, geodetic.longitude) < Number::ZERO;
+    let altitudes = body.situation_altitudes;
+    if under_water && above_sea <= SURFACE_HEIGHT {
+        Situation::Splashed
+    } else if above_ground <= SURFACE_HEIGHT {
+        Situation::Landed
+    } else if body
+        .atmosphere
+        .is_some_and(|atmosphere| geodetic.altitude < atmosphere.height)
+    {
+        if geodetic.altitude < altitudes.flying_high {
+            Situation::FlyingLow
+        } else {
+            Situation::FlyingHigh
+        }
+    } else if geodetic.altitude < altitudes.space_high {
+        Situation::SpaceLow
+    } else {
+        Situation::SpaceHigh
+    }
+}
@@ -0,0 +1,161 @@
+use crate::{
+    engine,
+    game_data::GameData,
+    physics,
+    vector3::Vector3,
+    vessel::{Vessel, VesselPart},
+    Number,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct DecouplerConfig {
+    /// Newton seconds pushing the separated pieces apart
+    pub ejection_impulse: f64,
+    /// Decouplers stay on their parent and let go of the parts below them, separators let go
+    /// of their parent as well
+    #[serde(default)]
+    pub separator: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DecouplerDefinition {
+    pub ejection_impulse: Number,
+    pub separator: bool,
+}
+
+impl DecouplerDefinition {
+    pub fn new(config: &DecouplerConfig) -> Self {
+        Self {
+            ejection_impulse: Number::from_num(config.ejection_impulse),
+            separator: config.separator,
+        }
+    }
+}
+
+/// Gives every engine and decoupler a stage, so that engines ignite bottom up and each
+/// decoupler fires together with the engines above it
+pub fn assign_stages(vessel: &mut Vessel, data: &GameData) {
+    let mut decouplers_above = vec![0; vessel.parts.len()];
+    for index in 0..vessel.parts.len() {
+        if let Some(parent) = vessel.parts[index].parent {
+            decouplers_above[index] = decouplers_above[parent]
+                + usize::from(data.parts[vessel.parts[parent].part].decoupler.is_some());
+        }
+    }
+
+    let staged = |part: &VesselPart| {
+        let definition = &data.parts[part.part];
+        definition.engine.is_some() || definition.decoupler.is_some()
+    };
+    let deepest = (vessel.parts.iter().zip(&decouplers_above))
+        .filter(|(part, _)| staged(part))
+        .map(|(_, &above)| above)
+        .max()
+        .unwrap_or(0);
+    for (part, above) in vessel.parts.iter_mut().zip(decouplers_above) {
+        part.stage = staged(part).then(|| deepest - above);
+    }
+    vessel.current_stage = 0;
+}
+
+/// Number of stages left to activate
+pub fn remaining_stages(vessel: &Vessel) -> usize {
+    vessel
+        .parts
+        .iter()
+        .filter_map(|part| part.stage)
+        .map(|stage| stage + 1)
+        .max()
+        .unwrap_or(0)
+        .saturating_sub(vessel.current_stage)
+}
+
+/// Whether it's time to activate the next stage: the engines the next stage would drop have run
+/// dry, or every activated engine has when nothing would be dropped, or none are running yet
+pub fn burnt_out(vessel: &Vessel, data: &GameData) -> bool {
+    let dropped = dropped_parts(vessel, data, vessel.current_stage);
+    let engines = (0..vessel.parts.len())
+        .filter(|&index| {
+            vessel.parts[index].activated && data.parts[vessel.parts[index].part].engine.is_some()
+        })
+        .collect::<Vec<_>>();
+    let watched_dropped = engines.iter().any(|&index| dropped[index]);
+    engines
+        .into_iter()
+        .filter(|&index| !watched_dropped || dropped[index])
+        .all(|index| !engine::has_propellant(vessel, data, index))
+}
+
+/// Marks every part that activating `stage` would separate from the root part
+pub fn dropped_parts(vessel: &Vessel, data: &GameData, stage: usize) -> Vec<bool> {
+    let mut dropped = vec![false; vessel.parts.len()];
+    for (index, _) in cuts(vessel, data, stage) {
+        dropped[index] = true;
+    }
+    for index in 0..vessel.parts.len() {
+        if let Some(parent) = vessel.parts[index].parent {
+            dropped[index] |= dropped[parent];
+        }
+    }
+    dropped
+}
+
+/// Activates every part in the current stage, returning the vessels split off by decouplers
+pub fn activate_next_stage(vessel: &mut Vessel, data: &GameData) -> Vec<Vessel> {
+    let stage = vessel.current_stage;
+    vessel.current_stage += 1;
+
+    for part in &mut vessel.parts {
+        if part.stage == Some(stage) {
+            part.activated = true;
+        }
+    }
+
+    // Detaching a part only moves parts after it, so cutting from the back keeps the earlier
+    // indices valid
+    let mut cuts = cuts(vessel, data, stage);
+    cuts.sort_by_key(|&(index, _)| std::cmp::Reverse(index));
+    let mut separated = Vec::with_capacity(cuts.len());
+    for (index, decoupler) in cuts {
+        let parent = vessel.parts[index].parent.unwrap();
+        let parent_position = vessel.part_transform(parent).position();
+        let child_position = vessel.part_transform(index).position();
+
+        let mut piece = vessel.detach(index, data);
+
+        let offset = child_position - parent_position;
+        let direction = if offset.sqr_length() > Number::ZERO {
+            offset / offset.length()
+        } else {
+            vessel.transform.transform_direction(-Vector3::Y)
+        };
+        let impulse = direction * decoupler.ejection_impulse;
+        let piece_mass_properties = piece.mass_properties(data);
+        physics::apply_impulse(&mut piece, &piece_mass_properties, impulse, child_position);
+        let mass_properties = vessel.mass_properties(data);
+        physics::apply_impulse(vessel, &mass_properties, -impulse, child_position);
+
+        separated.push(piece);
+    }
+    separated
+}
+
+/// The parts that get cut from their parent when `stage` is activated, with the decoupler
+/// doing the cutting
+fn cuts(vessel: &Vessel, data: &GameData, stage: usize) -> Vec<(usize, DecouplerDefinition)> {
+    let mut cuts = Vec::new();
+    for (index, part) in vessel.parts.iter().enumerate() {
+        if part.stage != Some(stage) {
+            continue;
+        }
+        if let Some(decoupler) = data.parts[part.part].decoupler {
+            cuts.extend(vessel.children(index).map(|child| (child, decoupler)));
+            if decoupler.separator && part.parent.is_some() {
+                cuts.push((index, decoupler));
+            }
+        }
+    }
+    cuts
+}
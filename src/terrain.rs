@@ -0,0 +1,282 @@
+use crate::{
+    atan2,
+    geodetic::{Ellipsoid, Geodetic},
+    vector3::Vector3,
+    Number,
+};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// How the ground of a body rises and falls around its sea level
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum TerrainConfig {
+    /// A grayscale image covering the whole body, longitude -180° to 180° from left to right
+    /// and latitude 90° to -90° from top to bottom
+    Heightmap {
+        image: String,
+        /// Meters above sea level where the image is black
+        #[serde(default)]
+        min_height: f64,
+        /// Meters above sea level where the image is white
+        max_height: f64,
+    },
+    /// Layers of random hills, each half the size of the one before
+    Noise {
+        #[serde(default)]
+        seed: u32,
+        /// Meters the highest hills reach above sea level, and the lowest valleys below it
+        amplitude: f64,
+        /// Meters across the largest hills
+        wavelength: f64,
+        #[serde(default = "default_octaves")]
+        octaves: u32,
+        /// How much lower each layer of hills is than the one before
+        #[serde(default = "default_persistence")]
+        persistence: f64,
+    },
+}
+
+fn default_octaves() -> u32 {
+    6
+}
+
+fn default_persistence() -> f64 {
+    0.5
+}
+
+#[derive(Debug)]
+pub enum Terrain {
+    Heightmap(Heightmap),
+    Noise(Noise),
+}
+
+impl Terrain {
+    /// `radius` is the body's, with paths relative to `directory`
+    pub fn new(config: &TerrainConfig, radius: Number, directory: &Path) -> Self {
+        match *config {
+            TerrainConfig::Heightmap {
+                ref image,
+                min_height,
+                max_height,
+            } => {
+                let path = directory.join(image);
+                let image = image::open(&path)
+                    .unwrap_or_else(|error| panic!("{}: {error}", path.display()))
+                    .to_luma16();
+                let min_height = Number::from_num(min_height);
+                let range = Number::from_num(max_height) - min_height;
+                let heights = image
+                    .pixels()
+                    .map(|pixel| min_height + range * Number::from_num(pixel.0[0]) / 65535)
+                    .collect();
+                Terrain::Heightmap(Heightmap::new(
+                    image.width() as usize,
+                    image.height() as usize,
+                    heights,
+                ))
+            }
+            TerrainConfig::Noise {
+                seed,
+                amplitude,
+                wavelength,
+                octaves,
+                persistence,
+            } => Terrain::Noise(Noise {
+                seed,
+                amplitude: Number::from_num(amplitude),
+                wavelength: Number::from_num(wavelength),
+                octaves,
+                persistence: Number::from_num(persistence),
+                radius,
+            }),
+        }
+    }
+
+    /// Meters above sea level of the ground at a latitude and longitude in radians
+    pub fn height_at(&self, latitude: Number, longitude: Number) -> Number {
+        match self {
+            Terrain::Heightmap(heightmap) => heightmap.height_at(latitude, longitude),
+            Terrain::Noise(noise) => noise.height_at(latitude, longitude),
+        }
+    }
+
+    /// Radians between the finest details, which is as close as it's worth sampling
+    pub fn spacing(&self) -> Number {
+        match self {
+            Terrain::Heightmap(heightmap) => (Number::PI / Number::from_num(heightmap.height))
+                .min(Number::TAU / Number::from_num(heightmap.width)),
+            Terrain::Noise(noise) => (noise.wavelength / noise.radius) >> noise.octaves.min(31),
+        }
+    }
+
+    /// Unit length normal of the ground at a latitude and longitude, in the body-fixed frame of
+    /// a body shaped like `ellipsoid`
+    pub fn normal_at(&self, ellipsoid: &Ellipsoid, latitude: Number, longitude: Number) -> Vector3 {
+        let up = ellipsoid.up(latitude, longitude);
+        // Two directions across the surface, which unlike north and east also exist at the poles
+        let reference = if up.z.abs() < Number::lit("0.9") {
+            Vector3::Z
+        } else {
+            Vector3::X
+        };
+        let across = up.cross(reference);
+        let across = across / across.length();
+        let other = up.cross(across);
+        // Nudging the normal direction nudges the latitude and longitude the same way
+        let step = self.spacing() / 2;
+        let point = |offset: Vector3| {
+            let direction = up + offset * step;
+            let latitude = atan2(
+                direction.z,
+                (direction.x * direction.x + direction.y * direction.y).sqrt(),
+            );
+            let longitude = atan2(direction.y, direction.x);
+            let height = self.height_at(latitude, longitude);
+            ellipsoid.to_cartesian(Geodetic::new(latitude, longitude, height))
+        };
+        let normal = (point(across) - point(-across)).cross(point(other) - point(-other));
+        let normal = normal / normal.length();
+        if normal.dot(up) < Number::ZERO {
+            -normal
+        } else {
+            normal
+        }
+    }
+}
+
+/// Heights on an equirectangular grid, sampled between pixel centers
+#[derive(Debug)]
+pub struct Heightmap {
+    width: usize,
+    height: usize,
+    /// Meters above sea level, row by row from the north pole
+    heights: Vec<Number>,
+}
+
+impl Heightmap {
+    pub fn new(width: usize, height: usize, heights: Vec<Number>) -> Self {
+        assert_eq!(heights.len(), width * height);
+        assert!(width > 0 && height > 0);
+        Self {
+            width,
+            height,
+            heights,
+        }
+    }
+
+    pub fn height_at(&self, latitude: Number, longitude: Number) -> Number {
+        // Pixel coordinates, with pixel centers on whole numbers
+        let x = (longitude + Number::PI).rem_euclid(Number::TAU) / Number::TAU
+            * Number::from_num(self.width)
+            - Number::lit("0.5");
+        let y = ((Number::FRAC_PI_2 - latitude) / Number::PI * Number::from_num(self.height)
+            - Number::lit("0.5"))
+        .clamp(Number::ZERO, Number::from_num(self.height - 1));
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let column = |x: Number| (x.to_num::<i64>().rem_euclid(self.width as i64)) as usize;
+        let row = |y: Number| (y.to_num::<usize>()).min(self.height - 1);
+        let sample = |x: Number, y: Number| self.heights[row(y) * self.width + column(x)];
+        let top = lerp(sample(x0, y0), sample(x0 + Number::ONE, y0), fx);
+        let bottom = lerp(
+            sample(x0, y0 + Number::ONE),
+            sample(x0 + Number::ONE, y0 + Number::ONE),
+            fx,
+        );
+        lerp(top, bottom, fy)
+    }
+}
+
+/// Gradient noise evaluated across the body's sea level, so it wraps around seamlessly
+#[derive(Debug)]
+pub struct Noise {
+    pub seed: u32,
+    /// Meters the highest hills reach above sea level, and the lowest valleys below it
+    pub amplitude: Number,
+    /// Meters across the largest hills
+    pub wavelength: Number,
+    pub octaves: u32,
+    /// How much lower each layer of hills is than the one before
+    pub persistence: Number,
+    /// Meters, of the body
+    pub radius: Number,
+}
+
+impl Noise {
+    pub fn height_at(&self, latitude: Number, longitude: Number) -> Number {
+        let point = Ellipsoid::sphere(self.radius / self.wavelength).to_cartesian(Geodetic::new(
+            latitude,
+            longitude,
+            Number::ZERO,
+        ));
+        let mut total = Number::ZERO;
+        let mut weights = Number::ZERO;
+        let mut weight = Number::ONE;
+        let mut frequency = Number::ONE;
+        for octave in 0..self.octaves {
+            total += gradient_noise(self.seed.wrapping_add(octave), point * frequency) * weight;
+            weights += weight;
+            weight *= self.persistence;
+            frequency *= 2;
+        }
+        if weights > Number::ZERO {
+            self.amplitude * total / weights
+        } else {
+            Number::ZERO
+        }
+    }
+}
+
+fn lerp(a: Number, b: Number, t: Number) -> Number {
+    a + (b - a) * t
+}
+
+/// Smooth noise between about -1 and 1 that changes over a distance of about 1
+fn gradient_noise(seed: u32, point: Vector3) -> Number {
+    let cell = [point.x.floor(), point.y.floor(), point.z.floor()];
+    let offset = point - Vector3::new(cell[0], cell[1], cell[2]);
+    // Eases in and out of each cell so the noise has no creases
+    let fade = |t: Number| t * t * t * (t * (t * 6 - Number::from_num(15)) + Number::from_num(10));
+    let mut corners = [Number::ZERO; 8];
+    for (index, value) in corners.iter_mut().enumerate() {
+        let corner = [index & 1, (index >> 1) & 1, (index >> 2) & 1].map(|bit| bit as i64);
+        let hash = hash(
+            seed,
+            [0, 1, 2].map(|axis| cell[axis].to_num::<i64>() + corner[axis]),
+        );
+        let corner = Vector3::new(
+            Number::from_num(corner[0]),
+            Number::from_num(corner[1]),
+            Number::from_num(corner[2]),
+        );
+        *value = gradient(hash).dot(offset - corner);
+    }
+    let [fx, fy, fz] = [offset.x, offset.y, offset.z].map(fade);
+    let x = [0, 2, 4, 6].map(|index| lerp(corners[index], corners[index + 1], fx));
+    let y = [lerp(x[0], x[1], fy), lerp(x[2], x[3], fy)];
+    lerp(y[0], y[1], fz)
+}
+
+/// One of the twelve directions towards the edges of a cube
+fn gradient(hash: u32) -> Vector3 {
+    let [a, b] = [Number::ONE, -Number::ONE];
+    let signs = [(a, a), (b, a), (a, b), (b, b)][(hash & 3) as usize];
+    let zero = Number::ZERO;
+    match (hash >> 2) % 3 {
+        0 => Vector3::new(signs.0, signs.1, zero),
+        1 => Vector3::new(signs.0, zero, signs.1),
+        _ => Vector3::new(zero, signs.0, signs.1),
+    }
+}
+
+/// Scrambles a lattice point into random looking bits
+fn hash(seed: u32, cell: [i64; 3]) -> u32 {
+    let mut hash = seed as u64 ^ 0x9e37_79b9_7f4a_7c15;
+    for coordinate in cell {
+        hash ^= coordinate as u64;
+        hash = hash.wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        hash ^= hash >> 31;
+    }
+    (hash >> 32) as u32
+}
@@ -0,0 +1,270 @@
+use crate::{
+    engine::EngineOutput,
+    game_data::GameData,
+    physics::Environment,
+    registry::Registry,
+    resource::{ResourceDefinition, ResourceId},
+    vector3::Vector3,
+    vessel::Vessel,
+    Number,
+};
+use serde::{Deserialize, Serialize};
+
+/// Kelvin new parts start at
+pub const ROOM_TEMPERATURE: Number = Number::lit("288");
+
+/// Watts per square meter a black body gives off at 1000 kelvin, the Stefan-Boltzmann constant
+/// scaled so that fourth powers of temperatures stay small
+const RADIATION_AT_1000_KELVIN: Number = Number::lit("56704");
+
+/// Joules per kilogram per kelvin of air, which sets how hot air gets stopping against a part
+const AIR_SPECIFIC_HEAT: Number = Number::lit("1005");
+
+/// Watts per square meter per kelvin of convection for every square root of a kilogram of air
+/// swept past each square meter every second
+const CONVECTION: Number = Number::lit("1000");
+
+/// How a part takes in, holds and gives off heat
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields, default)]
+pub struct ThermalConfig {
+    /// Kelvin the part is destroyed above
+    pub max_temperature: f64,
+    /// Joules per kilogram per kelvin
+    pub specific_heat: f64,
+    /// Fraction of sunlight absorbed, and of black body radiation given off
+    pub emissivity: f64,
+    /// Watts per kelvin of difference flowing through the joint to the part's parent
+    pub conductance: f64,
+    /// Square meters of skin, the surface area of the hull when not given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub area: Option<f64>,
+}
+
+impl Default for ThermalConfig {
+    fn default() -> Self {
+        Self {
+            max_temperature: 2000.0,
+            specific_heat: 800.0,
+            emissivity: 0.4,
+            conductance: 500.0,
+            area: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ThermalDefinition {
+    /// Kelvin
+    pub max_temperature: Number,
+    /// Joules per kilogram per kelvin
+    pub specific_heat: Number,
+    pub emissivity: Number,
+    /// Watts per kelvin
+    pub conductance: Number,
+    /// Square meters
+    pub area: Number,
+}
+
+impl ThermalDefinition {
+    pub fn new(config: &ThermalConfig, hull_area: Number) -> Self {
+        Self {
+            max_temperature: Number::from_num(config.max_temperature),
+            specific_heat: Number::from_num(config.specific_heat),
+            emissivity: Number::from_num(config.emissivity),
+            conductance: Number::from_num(config.conductance),
+            area: config.area.map_or(hull_area, Number::from_num),
+        }
+    }
+}
+
+/// Burns away a resource held in the part to carry off heat above a temperature
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct AblatorConfig {
+    pub resource: String,
+    /// Kelvin the resource starts burning away at
+    pub temperature: f64,
+    /// Joules carried off by every unit burned
+    pub heat: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AblatorDefinition {
+    pub resource: ResourceId,
+    /// Kelvin
+    pub temperature: Number,
+    /// Joules per unit
+    pub heat: Number,
+}
+
+impl AblatorDefinition {
+    pub fn new(config: &AblatorConfig, resources: &Registry<ResourceDefinition>) -> Self {
+        Self {
+            resource: resources
+                .id(&config.resource)
+                .unwrap_or_else(|| panic!("unknown ablator {}", config.resource)),
+            temperature: Number::from_num(config.temperature),
+            heat: Number::from_num(config.heat),
+        }
+    }
+}
+
+/// Heats and cools every part of `vessel` for `dt` seconds, after it moved with `engines`
+/// running
+///
+/// Parts in the wake of another part of the vessel are kept out of the airflow, which is what
+/// lets a heat shield protect what's behind it.
+pub fn heat(
+    vessel: &mut Vessel,
+    data: &GameData,
+    engines: &[EngineOutput],
+    environment: &Environment,
+    dt: Number,
+) {
+    let heat_capacities = (0..vessel.parts.len())
+        .map(|index| {
+            let thermal = &data.parts[vessel.parts[index].part].thermal;
+            vessel.part_mass(index, data) * thermal.specific_heat
+        })
+        .collect::<Vec<_>>();
+    let air_velocity = vessel.velocity - environment.air_velocity;
+    let sheltered = sheltered(vessel, data, air_velocity);
+
+    // Air stopping against the part heats up to the recovery temperature, and pulls the part
+    // towards it faster the more air is swept past
+    let speed = air_velocity.length();
+    let recovery_temperature = environment.air_temperature + speed * speed / AIR_SPECIFIC_HEAT / 2;
+    let convection = CONVECTION * (environment.density * speed).sqrt();
+
+    let mut incoming = vec![Number::ZERO; vessel.parts.len()];
+    for output in engines {
+        let Some(engine) = &data.parts[vessel.parts[output.part].part].engine else {
+            continue;
+        };
+        // Engines without any thrust to speak of make no heat either
+        if engine.max_thrust > Number::ZERO {
+            incoming[output.part] += engine.heat * output.thrust / engine.max_thrust;
+        }
+    }
+    for (index, part) in vessel.parts.iter_mut().enumerate() {
+        let thermal = &data.parts[part.part].thermal;
+        let capacity = heat_capacities[index];
+        if capacity <= Number::ZERO {
+            continue;
+        }
+        // A convex part shows a quarter of its surface to any one direction on average
+        let cross_section = thermal.area / 4;
+        let sunlight = thermal.emissivity * environment.solar_flux * cross_section;
+        let temperature = part.temperature / 1000;
+        let radiation = thermal.emissivity
+            * RADIATION_AT_1000_KELVIN
+            * temperature
+            * temperature
+            * temperature
+            * temperature
+            * thermal.area;
+        part.temperature = (part.temperature
+            + (incoming[index] + sunlight - radiation) * dt / capacity)
+            .max(Number::ZERO);
+
+        if !sheltered[index] {
+            // Stepped implicitly, as thin air on light parts would overshoot otherwise
+            let steps = convection * cross_section * dt / capacity;
+            part.temperature =
+                (part.temperature + recovery_temperature * steps) / (Number::ONE + steps);
+        }
+    }
+
+    // Heat flows through each joint towards the colder side, never further than evening the two
+    // out
+    for index in 1..vessel.parts.len() {
+        let Some(parent) = vessel.parts[index].parent else {
+            continue;
+        };
+        let (capacity, parent_capacity) = (heat_capacities[index], heat_capacities[parent]);
+        if capacity <= Number::ZERO || parent_capacity <= Number::ZERO {
+            continue;
+        }
+        let conductance = data.parts[vessel.parts[index].part].thermal.conductance;
+        let difference = vessel.parts[parent].temperature - vessel.parts[index].temperature;
+        let even = difference * capacity / (capacity + parent_capacity) * parent_capacity;
+        let flow = difference * conductance * dt;
+        let flow = if difference > Number::ZERO {
+            flow.min(even)
+        } else {
+            flow.max(even)
+        };
+        vessel.parts[index].temperature += flow / capacity;
+        vessel.parts[parent].temperature -= flow / parent_capacity;
+    }
+
+    for (index, capacity) in heat_capacities.into_iter().enumerate() {
+        let Some(ablator) = &data.parts[vessel.parts[index].part].ablator else {
+            continue;
+        };
+        let excess = vessel.parts[index].temperature - ablator.temperature;
+        if excess <= Number::ZERO {
+            continue;
+        }
+        let part = &mut vessel.parts[index];
+        let Some(stored) = part.resource_mut(ablator.resource) else {
+            continue;
+        };
+        let burned = (excess * capacity / ablator.heat).min(stored.amount);
+        stored.amount -= burned;
+        part.temperature -= burned * ablator.heat / capacity;
+    }
+}
+
+/// Destroys the parts of `vessel` that got hotter than they can take, returning the pieces that
+/// fall apart as new vessels
+pub fn destroy_overheated(vessel: &mut Vessel, data: &GameData) -> Vec<Vessel> {
+    let destroyed = vessel
+        .parts
+        .iter()
+        .map(|part| part.temperature > data.parts[part.part].thermal.max_temperature)
+        .collect::<Vec<_>>();
+    if destroyed.contains(&true) {
+        vessel.destroy_parts(&destroyed, data)
+    } else {
+        Vec::new()
+    }
+}
+
+/// Which parts of `vessel`, moving through the air at `air_velocity`, are behind another part as
+/// seen from the airflow
+fn sheltered(vessel: &Vessel, data: &GameData, air_velocity: Vector3) -> Vec<bool> {
+    let speed = air_velocity.length();
+    if speed == Number::ZERO {
+        return vec![false; vessel.parts.len()];
+    }
+    let forward = air_velocity / speed;
+    let across = |offset: Vector3| (offset - forward * offset.dot(forward)).length();
+
+    // World space centers, and how far each hull reaches out across the airflow
+    let shapes = vessel
+        .parts
+        .iter()
+        .enumerate()
+        .map(|(index, part)| {
+            let hull = &data.parts[part.part].hull;
+            let transform = vessel.part_transform(index);
+            let center = transform.transform_point(hull.center);
+            let reach = hull
+                .vertices
+                .iter()
+                .map(|&vertex| across(transform.transform_point(vertex) - center))
+                .fold(Number::ZERO, Number::max);
+            (center, reach)
+        })
+        .collect::<Vec<_>>();
+    shapes
+        .iter()
+        .map(|&(center, _)| {
+            shapes.iter().any(|&(shield, reach)| {
+                (shield - center).dot(forward) > Number::ZERO && across(center - shield) < reach
+            })
+        })
+        .collect()
+}
@@ -1,8 +1,9 @@
 use crate::Number;
 use az::Cast;
+use serde::{Deserialize, Serialize};
 use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Vector3 {
     pub x: Number,
     pub y: Number,
@@ -26,6 +27,15 @@ impl Vector3 {
         self.x * other.x + self.y * other.y + self.z * other.z
     }
 
+    #[inline]
+    pub fn cross(self, other: Vector3) -> Vector3 {
+        Self {
+            x: self.y * other.z - self.z * other.y,
+            y: self.z * other.x - self.x * other.z,
+            z: self.x * other.y - self.y * other.x,
+        }
+    }
+
     #[inline]
     pub fn sqr_length(self) -> Number {
         self.dot(self)
@@ -37,6 +47,16 @@ impl Vector3 {
     }
 }
 
+impl From<cgmath::Vector3<f32>> for Vector3 {
+    fn from(value: cgmath::Vector3<f32>) -> Self {
+        Self::new(
+            Number::from_num(value.x),
+            Number::from_num(value.y),
+            Number::from_num(value.z),
+        )
+    }
+}
+
 impl From<Vector3> for cgmath::Vector3<f32> {
     fn from(value: Vector3) -> Self {
         Self::new(value.x.cast(), value.y.cast(), value.z.cast())
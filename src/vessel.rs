@@ -0,0 +1,420 @@
+use crate::{
+    control::{self, ControlInput},
+    electric,
+    engine::{self, EngineOutput},
+    game_data::GameData,
+    joint::Flex,
+    matrix3::Matrix3,
+    motor::Transform,
+    part::{PartId, PartInstance},
+    physics::{self, Environment, Forces},
+    resource::{PartResource, ResourceId},
+    thermal,
+    vector3::Vector3,
+    Number,
+};
+
+/// A part attached to a vessel
+#[derive(Debug, Clone)]
+pub struct VesselPart {
+    pub part: PartId,
+    /// From part space to vessel space
+    pub transform: Transform,
+    /// The part this one is attached to, always before it in [`Vessel::parts`], `None` for the
+    /// root part
+    pub parent: Option<usize>,
+    pub resources: Vec<PartResource>,
+    pub crossfeed: bool,
+    /// When the part gets activated, see [`crate::staging`]
+    pub stage: Option<usize>,
+    /// Engines only run once activated
+    pub activated: bool,
+    /// Deployable solar panels only make power once deployed
+    pub deployed: bool,
+    /// Kelvin
+    pub temperature: Number,
+    /// How far the part has flexed on its joint, `None` while the vessel is rigid, not saved
+    pub flex: Option<Flex>,
+}
+
+impl VesselPart {
+    pub fn new(part: PartId, transform: Transform, parent: Option<usize>, data: &GameData) -> Self {
+        Self {
+            part,
+            transform,
+            parent,
+            resources: data.parts[part].resources.clone(),
+            crossfeed: data.parts[part].config.crossfeed,
+            stage: None,
+            activated: false,
+            deployed: false,
+            temperature: thermal::ROOM_TEMPERATURE,
+            flex: None,
+        }
+    }
+
+    pub fn resource(&self, resource: ResourceId) -> Option<&PartResource> {
+        self.resources
+            .iter()
+            .find(|stored| stored.resource == resource)
+    }
+
+    pub fn resource_mut(&mut self, resource: ResourceId) -> Option<&mut PartResource> {
+        self.resources
+            .iter_mut()
+            .find(|stored| stored.resource == resource)
+    }
+}
+
+/// A tree of parts moving as one rigid body
+#[derive(Debug, Clone)]
+pub struct Vessel {
+    /// From vessel space to world space
+    pub transform: Transform,
+    /// Velocity of the center of mass in world space
+    pub velocity: Vector3,
+    /// In world space
+    pub angular_velocity: Vector3,
+    pub parts: Vec<VesselPart>,
+    /// Between 0 and 1
+    pub throttle: Number,
+    /// Steering for the reaction wheels, RCS and engine gimbals, not saved
+    pub control: ControlInput,
+    /// The next stage to activate
+    pub current_stage: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MassProperties {
+    pub mass: Number,
+    /// In vessel space
+    pub center_of_mass: Vector3,
+    /// Around `center_of_mass`, in vessel space
+    pub inertia: Matrix3,
+}
+
+impl MassProperties {
+    /// The inertia tensor rotated into the space `transform` maps to
+    pub fn transformed_inertia(&self, transform: Transform) -> Matrix3 {
+        let rotation = transform.rotation_matrix();
+        rotation * self.inertia * rotation.transpose()
+    }
+}
+
+impl Vessel {
+    pub fn new(root: PartId, transform: Transform, data: &GameData) -> Self {
+        Self {
+            transform,
+            velocity: Vector3::ZERO,
+            angular_velocity: Vector3::ZERO,
+            parts: vec![VesselPart::new(root, Transform::IDENTITY, None, data)],
+            throttle: Number::ZERO,
+            control: ControlInput::NEUTRAL,
+            current_stage: 0,
+        }
+    }
+
+    /// Attaches `part` to the part at index `parent`, with `transform` in vessel space
+    pub fn attach(
+        &mut self,
+        parent: usize,
+        part: PartId,
+        transform: Transform,
+        data: &GameData,
+    ) -> usize {
+        assert!(parent < self.parts.len());
+        let index = self.parts.len();
+        self.parts
+            .push(VesselPart::new(part, transform, Some(parent), data));
+        index
+    }
+
+    /// The parent and children of the part at `index`
+    pub fn neighbours(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        self.parts[index]
+            .parent
+            .into_iter()
+            .chain(self.children(index))
+    }
+
+    pub fn children(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        self.parts
+            .iter()
+            .enumerate()
+            .filter(move |(_, part)| part.parent == Some(index))
+            .map(|(child, _)| child)
+    }
+
+    /// World space transform of the part at `index`, flexed on its joint
+    pub fn part_transform(&self, index: usize) -> Transform {
+        let part = &self.parts[index];
+        let flex = part
+            .flex
+            .map_or(Transform::IDENTITY, |flex| flex.transform());
+        part.transform.apply(flex).apply(self.transform)
+    }
+
+    /// Every part placed in world space, flexed on its joint
+    pub fn instances(&self) -> impl Iterator<Item = PartInstance> + '_ {
+        self.parts
+            .iter()
+            .enumerate()
+            .map(|(index, part)| PartInstance {
+                part: part.part,
+                transform: self.part_transform(index),
+            })
+    }
+
+    /// Simulates every part as a rigid body of its own, held on by springy joints that can
+    /// break, see [`crate::joint::flex`], or goes back to one rigid body
+    pub fn set_flexible(&mut self, flexible: bool, data: &GameData) {
+        for part in &mut self.parts {
+            part.flex = flexible.then(|| {
+                let definition = &data.parts[part.part];
+                Flex::new(part.transform.transform_point(definition.center_of_mass))
+            });
+        }
+    }
+
+    pub fn is_flexible(&self) -> bool {
+        self.parts.iter().all(|part| part.flex.is_some())
+    }
+
+    /// Dry mass plus the mass of every resource stored in the part
+    pub fn part_mass(&self, index: usize, data: &GameData) -> Number {
+        let part = &self.parts[index];
+        part.resources
+            .iter()
+            .fold(data.parts[part.part].mass, |mass, stored| {
+                mass + stored.amount * data.resources[stored.resource].density
+            })
+    }
+
+    pub fn mass(&self, data: &GameData) -> Number {
+        (0..self.parts.len())
+            .map(|index| self.part_mass(index, data))
+            .sum()
+    }
+
+    /// Total amount of `resource` left in the whole vessel
+    pub fn resource_amount(&self, resource: ResourceId) -> Number {
+        self.parts
+            .iter()
+            .filter_map(|part| part.resource(resource))
+            .map(|stored| stored.amount)
+            .sum()
+    }
+
+    pub fn mass_properties(&self, data: &GameData) -> MassProperties {
+        let mut mass = Number::ZERO;
+        let mut moment = Vector3::ZERO;
+        for (index, part) in self.parts.iter().enumerate() {
+            let part_mass = self.part_mass(index, data);
+            let center = part
+                .transform
+                .transform_point(data.parts[part.part].center_of_mass);
+            mass += part_mass;
+            moment += center * part_mass;
+        }
+        let center_of_mass = if mass > Number::ZERO {
+            moment / mass
+        } else {
+            Vector3::ZERO
+        };
+
+        let mut inertia = Matrix3::ZERO;
+        for (index, part) in self.parts.iter().enumerate() {
+            let definition = &data.parts[part.part];
+            let part_mass = self.part_mass(index, data);
+            let scale = if definition.mass > Number::ZERO {
+                part_mass / definition.mass
+            } else {
+                Number::ZERO
+            };
+            let rotation = part.transform.rotation_matrix();
+            let offset = part.transform.transform_point(definition.center_of_mass) - center_of_mass;
+            // Parallel axis theorem to move the part's inertia onto the vessel center of mass
+            inertia += rotation * (definition.inertia * scale) * rotation.transpose()
+                + (Matrix3::IDENTITY * offset.sqr_length() - Matrix3::outer(offset, offset))
+                    * part_mass;
+        }
+
+        MassProperties {
+            mass,
+            center_of_mass,
+            inertia,
+        }
+    }
+
+    /// Splits the part at `index` and everything attached below it off into a new vessel,
+    /// keeping the velocity each piece had while they were still connected
+    pub fn detach(&mut self, index: usize, data: &GameData) -> Vessel {
+        assert!(index != 0, "cannot detach the root part");
+
+        let center_of_mass = self.mass_properties(data).center_of_mass;
+
+        let mut detached = vec![false; self.parts.len()];
+        for i in index..self.parts.len() {
+            detached[i] = i == index || self.parts[i].parent.is_some_and(|parent| detached[parent]);
+        }
+
+        let mut remaining_indices = Vec::with_capacity(self.parts.len());
+        let mut detached_indices = Vec::with_capacity(self.parts.len());
+        let (mut remaining_count, mut detached_count) = (0, 0);
+        for &is_detached in &detached {
+            if is_detached {
+                remaining_indices.push(usize::MAX);
+                detached_indices.push(detached_count);
+                detached_count += 1;
+            } else {
+                remaining_indices.push(remaining_count);
+                detached_indices.push(usize::MAX);
+                remaining_count += 1;
+            }
+        }
+
+        let mut remaining = Vec::with_capacity(remaining_count);
+        let mut split = Vec::with_capacity(detached_count);
+        for (i, mut part) in std::mem::take(&mut self.parts).into_iter().enumerate() {
+            if detached[i] {
+                part.parent = part
+                    .parent
+                    .filter(|&parent| detached[parent])
+                    .map(|parent| detached_indices[parent]);
+                split.push(part);
+            } else {
+                part.parent = part.parent.map(|parent| remaining_indices[parent]);
+                remaining.push(part);
+            }
+        }
+        self.parts = remaining;
+
+        let mut vessel = self.split_off(split);
+        for piece in [&mut *self, &mut vessel] {
+            piece.keep_spin_velocity(center_of_mass, data);
+        }
+        vessel
+    }
+
+    /// Removes every part marked in `destroyed`, returning the pieces that fall apart as new
+    /// vessels; the piece holding the root stays in `self`, which is left without any parts
+    /// when the root itself is destroyed
+    pub fn destroy_parts(&mut self, destroyed: &[bool], data: &GameData) -> Vec<Vessel> {
+        let center_of_mass = self.mass_properties(data).center_of_mass;
+
+        // Parents come before their children, so each surviving part either joins its
+        // parent's piece or starts a new one when the parent is gone
+        let mut pieces = Vec::<Vec<VesselPart>>::new();
+        let mut piece_of = vec![usize::MAX; self.parts.len()];
+        let mut new_index = vec![usize::MAX; self.parts.len()];
+        for (i, mut part) in std::mem::take(&mut self.parts).into_iter().enumerate() {
+            if destroyed[i] {
+                continue;
+            }
+            let parent = part.parent.filter(|&parent| !destroyed[parent]);
+            let piece = match parent {
+                Some(parent) => piece_of[parent],
+                None => {
+                    pieces.push(Vec::new());
+                    pieces.len() - 1
+                }
+            };
+            part.parent = parent.map(|parent| new_index[parent]);
+            piece_of[i] = piece;
+            new_index[i] = pieces[piece].len();
+            pieces[piece].push(part);
+        }
+
+        let mut pieces = pieces.into_iter();
+        let root = if destroyed[0] { None } else { pieces.next() };
+        let split = pieces
+            .map(|parts| {
+                let mut vessel = self.split_off(parts);
+                vessel.keep_spin_velocity(center_of_mass, data);
+                vessel
+            })
+            .collect();
+        if let Some(parts) = root {
+            self.parts = parts;
+            self.keep_spin_velocity(center_of_mass, data);
+        }
+        split
+    }
+
+    /// A new vessel made of `parts`, moving along with this one with nobody at the controls
+    fn split_off(&self, parts: Vec<VesselPart>) -> Vessel {
+        Vessel {
+            transform: self.transform,
+            velocity: self.velocity,
+            angular_velocity: self.angular_velocity,
+            parts,
+            throttle: Number::ZERO,
+            control: ControlInput::NEUTRAL,
+            current_stage: self.current_stage,
+        }
+    }
+
+    /// Adds the velocity a piece had from spinning around `center_of_mass`, in vessel space,
+    /// while it was still part of a bigger vessel
+    fn keep_spin_velocity(&mut self, center_of_mass: Vector3, data: &GameData) {
+        let offset = self.mass_properties(data).center_of_mass - center_of_mass;
+        self.velocity += self
+            .angular_velocity
+            .cross(self.transform.transform_direction(offset));
+    }
+
+    /// Runs every part for `dt` seconds and moves the vessel under the resulting forces
+    pub fn update(
+        &mut self,
+        data: &GameData,
+        environment: &Environment,
+        dt: Number,
+    ) -> Vec<EngineOutput> {
+        electric::run_electric(self, data, environment, dt);
+        if !electric::has_control(self, data) {
+            self.control = ControlInput::NEUTRAL;
+            self.throttle = Number::ZERO;
+        }
+        let mut engines = engine::run_engines(self, data, environment.pressure, dt);
+
+        let mass_properties = self.mass_properties(data);
+        control::gimbal_engines(self, data, &mut engines, mass_properties.center_of_mass);
+        let center_of_mass = self
+            .transform
+            .transform_point(mass_properties.center_of_mass);
+        let mut forces = Forces::ZERO;
+        for output in &engines {
+            let transform = self.parts[output.part].transform.apply(self.transform);
+            forces.add_at(
+                transform.transform_direction(output.direction) * output.thrust,
+                transform.position(),
+                center_of_mass,
+            );
+        }
+
+        // Drag acts through the center of mass against the movement through the air, so it slows
+        // the vessel down to the air's speed without turning it
+        let drag_area = self
+            .parts
+            .iter()
+            .map(|part| data.parts[part.part].drag)
+            .sum::<Number>();
+        let air_velocity = self.velocity - environment.air_velocity;
+        let speed = air_velocity.length();
+        forces.force -= air_velocity * (environment.density * speed * drag_area / 2);
+
+        let mut attitude = control::run_rcs(
+            self,
+            data,
+            environment.pressure,
+            mass_properties.center_of_mass,
+            dt,
+        );
+        attitude.torque += control::run_reaction_wheels(self, data, dt);
+        forces.force += self.transform.transform_direction(attitude.force);
+        forces.torque += self.transform.transform_direction(attitude.torque);
+
+        physics::integrate(self, &mass_properties, &forces, environment.gravity, dt);
+        engines
+    }
+}
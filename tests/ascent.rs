@@ -0,0 +1,216 @@
+mod common;
+
+use serde_json::json;
+use space::{
+    ascent::{AscentAutopilot, AscentPhase, AscentSettings},
+    body::{Atmosphere, Body},
+    game_data::GameData,
+    motor::Transform,
+    orbit::Orbit,
+    staging,
+    vector3::Vector3,
+    vessel::Vessel,
+    Number,
+};
+
+const DT: f64 = 0.1;
+
+/// A small planet with thick air at the bottom, about 5 m/s² of gravity at sea level
+fn planet() -> Body {
+    Body {
+        atmosphere: Some(Atmosphere {
+            pressure: Number::ONE,
+            density: Number::from_num(1.2),
+            scale_height: Number::from_num(5000),
+            height: Number::from_num(40_000),
+            temperature: Number::from_num(288),
+        }),
+        ..Body::new(Number::from_num(300_000), Number::from_num(4.5e11))
+    }
+}
+
+fn setup() -> GameData {
+    let mut data = GameData::new();
+    common::resource(
+        &mut data,
+        json!({ "name": "LiquidFuel", "title": "", "density": 5 }),
+    );
+    common::part(
+        &mut data,
+        json!({
+            "name": "pod",
+            "mass": 500,
+            "inertia": [300, 200, 300],
+            "reaction_wheel": { "torque": [2000, 2000, 2000] },
+        }),
+    );
+    for (name, capacity) in [("small-tank", 200), ("big-tank", 400)] {
+        common::part(
+            &mut data,
+            json!({
+                "name": name,
+                "mass": capacity / 2,
+                "inertia": [capacity * 3, capacity, capacity * 3],
+                "resources": [{ "name": "LiquidFuel", "capacity": capacity }],
+            }),
+        );
+    }
+    for (name, thrust) in [("small-engine", 20_000), ("big-engine", 40_000)] {
+        common::part(
+            &mut data,
+            json!({
+                "name": name,
+                "mass": thrust / 200,
+                "inertia": [50, 50, 50],
+                "engine": {
+                    "max_thrust": thrust,
+                    "isp": [[0, 320], [1, 280]],
+                    "propellants": [{ "name": "LiquidFuel", "ratio": 1 }],
+                    "max_gimbal": 3,
+                },
+            }),
+        );
+    }
+    common::part(
+        &mut data,
+        json!({
+            "name": "decoupler",
+            "mass": 50,
+            "inertia": [20, 20, 20],
+            "crossfeed": false,
+            "decoupler": { "ejection_impulse": 200 },
+        }),
+    );
+    data
+}
+
+/// Two stage rocket standing on the planet's equator, pointing straight up
+fn rocket(data: &GameData, body: &Body) -> Vessel {
+    let transform = Transform::rotation(Vector3::Z, -Number::FRAC_PI_2).apply(
+        Transform::translation(Vector3::X * (body.radius + Number::from_num(10))),
+    );
+    let mut vessel = Vessel::new(data.parts.id("pod").unwrap(), transform, data);
+    let mut parent = 0;
+    for (name, height) in [
+        ("small-tank", -2.0),
+        ("small-engine", -4.0),
+        ("decoupler", -5.0),
+        ("big-tank", -6.5),
+        ("big-engine", -8.5),
+    ] {
+        parent = vessel.attach(
+            parent,
+            data.parts.id(name).unwrap(),
+            Transform::translation(Vector3::Y * Number::from_num(height)),
+            data,
+        );
+    }
+    staging::assign_stages(&mut vessel, data);
+    vessel
+}
+
+/// Flies the ascent autopilot to the end, returning the final orbit
+fn fly(body: &Body, apoapsis: f64, inclination: f64) -> Orbit {
+    let data = setup();
+    let mut vessel = rocket(&data, body);
+    let mut autopilot = AscentAutopilot::new(AscentSettings::new(
+        Number::from_num(apoapsis),
+        Number::from_num(inclination.to_radians()),
+        body,
+    ));
+
+    let dt = Number::from_num(DT);
+    for _ in 0..(900.0 / DT) as usize {
+        autopilot.update(&mut vessel, &data, body, dt);
+        if autopilot.phase() == AscentPhase::Done {
+            break;
+        }
+        let environment = body.environment(vessel.transform.position());
+        vessel.update(&data, &environment, dt);
+        assert!(
+            body.altitude(vessel.transform.position()) > Number::ZERO,
+            "crashed during {:?}",
+            autopilot.phase()
+        );
+    }
+    assert_eq!(autopilot.phase(), AscentPhase::Done);
+    assert_eq!(
+        staging::remaining_stages(&vessel),
+        0,
+        "never dropped the first stage"
+    );
+    body.orbit(&vessel)
+}
+
+fn assert_orbit(orbit: &Orbit, body: &Body, apoapsis: f64, inclination: f64) {
+    let atmosphere = body.atmosphere.unwrap().height.to_num::<f64>();
+    let radius = body.radius.to_num::<f64>();
+    let periapsis = orbit.periapsis().to_num::<f64>() - radius;
+    let reached = orbit.apoapsis().unwrap().to_num::<f64>() - radius;
+    assert!(
+        periapsis > atmosphere,
+        "periapsis {periapsis} inside the atmosphere"
+    );
+    assert!(
+        (reached - apoapsis).abs() < apoapsis * 0.05,
+        "apoapsis {reached} instead of {apoapsis}"
+    );
+    assert!(orbit.eccentricity < Number::from_num(0.02));
+    let reached = orbit.inclination.to_num::<f64>().to_degrees();
+    assert!(
+        (reached - inclination).abs() < 1.0,
+        "inclination {reached} instead of {inclination}"
+    );
+}
+
+#[test]
+fn reaches_an_equatorial_orbit() {
+    let orbit = fly(&planet(), 60_000.0, 0.0);
+    assert_orbit(&orbit, &planet(), 60_000.0, 0.0);
+}
+
+#[test]
+fn reaches_an_equatorial_orbit_around_a_tilted_planet() {
+    // Launching from where the tilted equator crosses the world's XY plane
+    let body = Body {
+        rotation: Transform::rotation(Vector3::X, Number::from_num(40f64.to_radians())),
+        ..planet()
+    };
+    let orbit = fly(&body, 60_000.0, 0.0);
+    assert_orbit(&orbit, &body, 60_000.0, 0.0);
+}
+
+#[test]
+fn reaches_an_inclined_orbit() {
+    let orbit = fly(&planet(), 80_000.0, 30.0);
+    assert_orbit(&orbit, &planet(), 80_000.0, 30.0);
+}
+
+#[test]
+fn circularizes_along_the_launch_heading_when_falling_straight_down() {
+    let data = setup();
+    let body = planet();
+    let mut vessel = rocket(&data, &body);
+    // Above the atmosphere with nothing sideways to keep going round with
+    vessel.transform = vessel.transform.apply(Transform::translation(
+        Vector3::X * Number::from_num(60_000),
+    ));
+    vessel.velocity = -Vector3::X * Number::from_num(10);
+    let mut autopilot = AscentAutopilot::new(AscentSettings::new(
+        Number::from_num(50_000),
+        Number::ZERO,
+        &body,
+    ));
+
+    let dt = Number::from_num(DT);
+    for _ in 0..20 {
+        autopilot.update(&mut vessel, &data, &body, dt);
+        let environment = body.environment(vessel.transform.position());
+        vessel.update(&data, &environment, dt);
+    }
+
+    // Turned from straight up towards the east, the way it would have launched
+    assert_eq!(autopilot.phase(), AscentPhase::Circularize);
+    let forward = vessel.transform.transform_direction(Vector3::Y);
+    assert!(forward.y > Number::ZERO, "pointing {forward:?}");
+}
@@ -0,0 +1,289 @@
+mod common;
+
+use common::{close, game_data_dir, number};
+use serde_json::json;
+use space::{
+    body::{self, Body},
+    game_data::GameData,
+    motor::Transform,
+    orbit::Orbit,
+    vector3::Vector3,
+    vessel::Vessel,
+    Number,
+};
+use std::path::Path;
+
+/// A moon on a circular orbit around a planet, which itself goes around a star, listed
+/// children first next to a resource
+fn small_system() -> GameData {
+    let directory = game_data_dir(
+        "small-system",
+        &[
+            (
+                "a/moon.json",
+                r#"{
+                    "type": "body",
+                    "name": "moon",
+                    "title": "Moon",
+                    "parent": "planet",
+                    "radius": 100000,
+                    "mu": 1e10,
+                    "orbit": { "semi_major_axis": 5000000, "inclination": 90 }
+                }"#,
+            ),
+            (
+                "b/planet.json",
+                r#"{
+                    "type": "body",
+                    "name": "planet",
+                    "title": "Planet",
+                    "parent": "star",
+                    "radius": 500000,
+                    "mu": 4e12,
+                    "rotation_period": 20000,
+                    "axial_tilt": 20,
+                    "orbit": { "semi_major_axis": 1e10, "mean_anomaly": 90 },
+                    "atmosphere": {
+                        "pressure": 1,
+                        "density": 1.2,
+                        "scale_height": 5000,
+                        "height": 60000
+                    },
+                    "texture_color_map": "planet.png"
+                }"#,
+            ),
+            (
+                "c/star.json",
+                r#"{
+                    "type": "body",
+                    "name": "star",
+                    "title": "Star",
+                    "radius": 2e8,
+                    "mu": 1e18
+                }"#,
+            ),
+            (
+                "fuel.json",
+                r#"{ "type": "resource", "name": "Fuel", "title": "Fuel", "density": 5 }"#,
+            ),
+        ],
+    );
+    GameData::load(&directory)
+}
+
+#[test]
+fn loads_bodies_next_to_other_configs() {
+    let data = small_system();
+
+    assert_eq!(data.resources.len(), 1);
+    assert_eq!(data.bodies.len(), 3);
+    let star = data.bodies.id("star").unwrap();
+    let planet = data.bodies.id("planet").unwrap();
+    let moon = data.bodies.id("moon").unwrap();
+    assert_eq!(data.bodies[star].parent, None);
+    assert_eq!(data.bodies[planet].parent, Some(star));
+    assert_eq!(data.bodies[moon].parent, Some(planet));
+
+    let planet = &data.bodies[planet];
+    assert_eq!(planet.rotation_period, Some(number(20000.0)));
+    assert!((planet.axial_tilt - number(20f64.to_radians())).abs() < number(1e-9));
+    assert_eq!(planet.atmosphere.unwrap().height, number(60000.0));
+    assert!(planet
+        .texture_color_map
+        .as_ref()
+        .unwrap()
+        .ends_with(Path::new("b/planet.png")));
+    // The moon orbits using the planet's gravity
+    assert_eq!(data.bodies[moon].orbit.unwrap().mu, number(4e12));
+}
+
+#[test]
+fn bodies_move_on_rails_around_their_parents() {
+    let data = small_system();
+    let [star, planet, moon] =
+        ["star", "planet", "moon"].map(|name| data.bodies.id(name).unwrap().index());
+
+    let bodies = body::propagate(&data.bodies, Number::ZERO);
+    assert_eq!(bodies[star].position, Vector3::ZERO);
+    // A quarter of the way around from +X
+    assert!(close(
+        bodies[planet].position,
+        Vector3::Y * number(1e10),
+        1.0
+    ));
+    let planet_speed = (1e18f64 / 1e10).sqrt();
+    assert!(close(
+        bodies[planet].velocity,
+        Vector3::X * number(-planet_speed),
+        0.001
+    ));
+    // Straight over the planet's poles, starting at its ascending node
+    let moon_offset = bodies[moon].position - bodies[planet].position;
+    assert!(close(moon_offset, Vector3::X * number(5e6), 1.0));
+    let moon_speed = (4e12f64 / 5e6).sqrt();
+    let moon_velocity = bodies[moon].velocity - bodies[planet].velocity;
+    assert!(close(moon_velocity, Vector3::Z * number(moon_speed), 0.001));
+
+    // Half a moon orbit later it's on the other side of the planet, which has barely moved
+    let period = std::f64::consts::TAU * (5e6f64.powi(3) / 4e12).sqrt();
+    let later = body::propagate(&data.bodies, number(period / 2.0));
+    let moon_offset = later[moon].position - later[planet].position;
+    assert!(close(moon_offset, Vector3::X * number(-5e6), 10.0));
+    assert_eq!(later[planet].radius, number(500000.0));
+}
+
+#[test]
+fn eccentric_orbits_follow_keplers_equation() {
+    let mu = number(4e14);
+    let start = Orbit {
+        mu,
+        semi_major_axis: number(1e7),
+        eccentricity: number(0.6),
+        inclination: number(0.3),
+        longitude_of_ascending_node: number(1.0),
+        argument_of_periapsis: number(2.0),
+        true_anomaly: Number::ZERO,
+    };
+    let period = start.period().unwrap();
+
+    // Periapsis to apoapsis takes half the period
+    let (position, _) = start.after(period / 2).state();
+    assert!((position.length() - start.apoapsis().unwrap()).abs() < number(1.0));
+
+    // The state round trips through the elements at any point of the orbit
+    let (position, velocity) = start.after(period / 7).state();
+    let orbit = Orbit::from_state(position, velocity, mu);
+    assert!((orbit.semi_major_axis - start.semi_major_axis).abs() < number(1.0));
+    assert!((orbit.eccentricity - start.eccentricity).abs() < number(1e-6));
+    assert!((orbit.argument_of_periapsis - start.argument_of_periapsis).abs() < number(1e-6));
+    assert!((orbit.mean_anomaly() - Number::TAU / 7).abs() < number(1e-6));
+
+    // A whole number of periods later it's back where it started
+    let (position, _) = start.after(period * 3).state();
+    assert!(close(position, start.state().0, 1.0));
+}
+
+#[test]
+fn loads_the_bundled_solar_system() {
+    let data = GameData::load(Path::new("GameData"));
+    let kerbin = data.bodies.id("kerbin").unwrap();
+    assert_eq!(data.bodies[kerbin].parent, data.bodies.id("kerbol"));
+    assert_eq!(
+        data.bodies[data.bodies.id("mun").unwrap()].parent,
+        Some(kerbin)
+    );
+
+    let bodies: Vec<Body> = body::propagate(&data.bodies, number(1000.0));
+    let mun = &bodies[data.bodies.id("mun").unwrap().index()];
+    let distance = (mun.position - bodies[kerbin.index()].position).length();
+    assert!((distance - number(1.2e7)).abs() < number(1.0));
+}
+
+#[test]
+fn bodies_turn_about_their_tilted_axis() {
+    let data = small_system();
+    let planet = data.bodies.id("planet").unwrap().index();
+    let tilt = 20f64.to_radians();
+    let north = Vector3::new(Number::ZERO, number(-tilt.sin()), number(tilt.cos()));
+
+    let start = &body::propagate(&data.bodies, Number::ZERO)[planet];
+    // The north pole leans over around +X, and stays put as the planet turns
+    let pole = start.to_world_position(Vector3::Z * start.radius) - start.position;
+    assert!(close(pole, north * start.radius, 0.01));
+    let rate = std::f64::consts::TAU / 20000.0;
+    assert!(close(start.angular_velocity, north * number(rate), 1e-9));
+
+    // A quarter turn later the prime meridian has turned to where 90° east was
+    let later = &body::propagate(&data.bodies, number(5000.0))[planet];
+    let meridian = later.surface_position(Number::ZERO, Number::ZERO, Number::ZERO);
+    let east = start.surface_position(Number::ZERO, Number::FRAC_PI_2, Number::ZERO);
+    assert!(close(
+        meridian - later.position,
+        east - start.position,
+        0.01
+    ));
+}
+
+#[test]
+fn orbits_are_measured_from_the_tilted_equator() {
+    let data = small_system();
+    let planet = data.bodies.id("planet").unwrap().index();
+    let planet = Body {
+        position: Vector3::ZERO,
+        velocity: Vector3::ZERO,
+        ..body::propagate(&data.bodies, number(5000.0))[planet].clone()
+    };
+    let mut parts = GameData::new();
+    let pod = common::part(&mut parts, json!({ "name": "pod", "mass": 1000 }));
+
+    // Going round over the equator, a quarter of the way around from where it was at the epoch
+    let radius = planet.radius + number(100_000.0);
+    let up = planet.to_world_position(Vector3::Y * radius) / radius;
+    let mut vessel = Vessel::new(pod, Transform::translation(up * radius), &parts);
+    vessel.velocity = planet.spin_axis().cross(up) * (planet.mu / radius).sqrt();
+
+    let orbit = planet.orbit(&vessel);
+    assert!(orbit.eccentricity < number(1e-6));
+    assert!(
+        orbit.inclination.abs() < number(1e-6),
+        "{}",
+        orbit.inclination
+    );
+}
+
+#[test]
+fn converts_between_world_space_and_the_body_fixed_frame() {
+    let data = small_system();
+    let planet = &body::propagate(&data.bodies, number(1234.0))[1];
+    let rate = std::f64::consts::TAU / 20000.0;
+
+    // A point standing still on the equator moves along with the turning surface
+    let fixed = Vector3::X * planet.radius;
+    let world = planet.to_world_position(fixed);
+    assert!(close(planet.to_body_fixed_position(world), fixed, 0.001));
+    let velocity = planet.to_world_velocity(fixed, Vector3::ZERO);
+    let turning = velocity - planet.velocity;
+    assert!((turning.length() - number(rate * 500000.0)).abs() < number(0.001));
+    assert!(turning.dot(world - planet.position).abs() < number(1.0));
+    assert!(close(
+        planet.to_body_fixed_velocity(world, velocity),
+        Vector3::ZERO,
+        0.001
+    ));
+    assert!(close(
+        planet.surface_velocity(world, velocity),
+        Vector3::ZERO,
+        0.001
+    ));
+
+    // Moving velocities round trip too
+    let moving = Vector3::new(number(10.0), number(-20.0), number(30.0));
+    let velocity = planet.to_world_velocity(fixed, moving);
+    assert!(close(
+        planet.to_body_fixed_velocity(world, velocity),
+        moving,
+        0.001
+    ));
+
+    // So do transforms, keeping their orientation relative to the surface. Transforms lose
+    // meters of precision this far out from the star, so these are checked around a planet
+    // sitting at the origin
+    let planet = Body {
+        position: Vector3::ZERO,
+        ..planet.clone()
+    };
+    let local = Transform::rotation(Vector3::Y, number(0.7)).apply(Transform::translation(fixed));
+    let transform = planet.to_world_transform(local);
+    assert!(close(
+        transform.position(),
+        planet.to_world_position(fixed),
+        0.01
+    ));
+    let back = planet.to_body_fixed_transform(transform);
+    assert!(close(back.position(), fixed, 0.01));
+    assert!(close(
+        back.transform_direction(Vector3::X),
+        local.transform_direction(Vector3::X),
+        1e-6
+    ));
+}
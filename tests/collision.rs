@@ -0,0 +1,141 @@
+mod common;
+
+use serde_json::json;
+use space::{
+    collision::{self, Contact},
+    convex_hull::ConvexHull,
+    game_data::GameData,
+    motor::Transform,
+    vector3::Vector3,
+    vessel::Vessel,
+    Number,
+};
+
+fn cube() -> ConvexHull {
+    ConvexHull::from_points(&common::cube_corners())
+}
+
+fn at(x: f64, y: f64, z: f64) -> Transform {
+    Transform::translation(Vector3::new(
+        Number::from_num(x),
+        Number::from_num(y),
+        Number::from_num(z),
+    ))
+}
+
+fn assert_near(value: Number, expected: f64) {
+    let value = value.to_num::<f64>();
+    assert!(
+        (value - expected).abs() < 1e-3,
+        "{value} instead of {expected}"
+    );
+}
+
+fn assert_contact(contact: Contact, depth: f64, normal: [f64; 3]) {
+    assert_near(contact.depth, depth);
+    assert_near(contact.normal.x, normal[0]);
+    assert_near(contact.normal.y, normal[1]);
+    assert_near(contact.normal.z, normal[2]);
+}
+
+#[test]
+fn hull_keeps_only_the_corners() {
+    let mut points = common::cube_corners();
+    points.extend([[0.0, 0.0, 0.0], [0.5, -0.5, 0.25], [1.0, 0.0, 0.0]]);
+    points.extend(common::cube_corners());
+    let hull = ConvexHull::from_points(&points);
+
+    assert_eq!(hull.vertices.len(), 8);
+    assert_eq!(hull.faces.len(), 12);
+    for &[a, b, c] in &hull.faces {
+        let [a, b, c] = [a, b, c].map(|index| hull.vertices[index]);
+        let normal = (b - a).cross(c - a);
+        // Every corner is behind every face
+        for &vertex in &hull.vertices {
+            assert!(normal.dot(vertex - a) <= Number::ZERO);
+        }
+    }
+    assert_near(hull.radius, 3f64.sqrt());
+}
+
+#[test]
+fn flat_points_still_make_a_hull() {
+    let hull = ConvexHull::from_points(&[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]);
+    assert_eq!(hull.vertices.len(), 3);
+    assert!(hull.faces.is_empty());
+}
+
+#[test]
+fn overlapping_cubes_report_how_deep() {
+    let contact = collision::collide(&cube(), at(0.0, 0.0, 0.0), &cube(), at(1.5, 0.2, 0.0));
+    assert_contact(contact.unwrap(), 0.5, [1.0, 0.0, 0.0]);
+
+    // Swapping the shapes flips the normal
+    let contact = collision::collide(&cube(), at(1.5, 0.2, 0.0), &cube(), at(0.0, 0.0, 0.0));
+    assert_contact(contact.unwrap(), 0.5, [-1.0, 0.0, 0.0]);
+}
+
+#[test]
+fn separated_cubes_do_not_collide() {
+    assert!(collision::collide(&cube(), at(0.0, 0.0, 0.0), &cube(), at(2.5, 0.0, 0.0)).is_none());
+    assert!(collision::collide(&cube(), at(0.0, 0.0, 0.0), &cube(), at(2.1, 2.1, 0.0)).is_none());
+}
+
+#[test]
+fn rotated_cube_touches_with_its_edge() {
+    let rotated = Transform::rotation(Vector3::Z, Number::FRAC_PI_4).apply(at(2.2, 0.0, 0.0));
+    let contact = collision::collide(&cube(), at(0.0, 0.0, 0.0), &cube(), rotated).unwrap();
+    assert_contact(contact, 1.0 - (2.2 - 2f64.sqrt()), [1.0, 0.0, 0.0]);
+    assert_near(contact.point.y, 0.0);
+}
+
+#[test]
+fn collisions_work_far_from_the_origin() {
+    let contact = collision::collide(
+        &cube(),
+        at(300_000.0, 0.0, 0.0),
+        &cube(),
+        at(300_000.0, 0.0, 1.75),
+    );
+    let contact = contact.unwrap();
+    assert_contact(contact, 0.25, [0.0, 0.0, 1.0]);
+    // Anywhere in the overlap, halfway between the two faces
+    assert!((contact.point.x.to_num::<f64>() - 300_000.0).abs() <= 1.0);
+    assert_near(contact.point.z, 0.875);
+}
+
+#[test]
+fn only_parts_of_different_vessels_touch() {
+    let mut data = GameData::new();
+    let block = common::part_with_model(
+        &mut data,
+        json!({ "name": "block", "mass": 100 }),
+        common::cube_model(),
+    );
+    assert_eq!(data.parts[block].hull.vertices.len(), 8);
+
+    // Two parts stacked on top of each other, overlapping where they're attached
+    let stack = |data: &GameData, transform: Transform| {
+        let mut vessel = Vessel::new(block, transform, data);
+        vessel.attach(0, block, at(0.0, 1.9, 0.0), data);
+        vessel
+    };
+    let vessels = [
+        stack(&data, at(0.0, 0.0, 0.0)),
+        stack(&data, at(1.8, 1.0, 0.0)),
+        stack(&data, at(50.0, 0.0, 0.0)),
+    ];
+
+    let contacts = collision::find_contacts(&vessels, &data);
+    let mut touching = contacts
+        .iter()
+        .map(|contact| {
+            assert_eq!(contact.vessels, [0, 1]);
+            assert_contact(contact.contact, 0.2, [1.0, 0.0, 0.0]);
+            contact.parts
+        })
+        .collect::<Vec<_>>();
+    touching.sort();
+    // The top of the second vessel is above the bottom of the first
+    assert_eq!(touching, [[0, 0], [1, 0], [1, 1]]);
+}
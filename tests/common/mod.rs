@@ -0,0 +1,129 @@
+#![allow(dead_code)]
+
+use serde_json::{json, Value};
+use space::{
+    convex_hull::ConvexHull,
+    game_data::GameData,
+    motor::Transform,
+    part::{Model, PartDefinition, PartId},
+    resource::{ResourceDefinition, ResourceId},
+    vector3::Vector3,
+    vessel::Vessel,
+    Number, Vertex,
+};
+use std::path::PathBuf;
+
+pub fn number(value: f64) -> Number {
+    Number::from_num(value)
+}
+
+pub fn radians(degrees: f64) -> Number {
+    number(degrees.to_radians())
+}
+
+pub fn close(a: Vector3, b: Vector3, tolerance: f64) -> bool {
+    (a - b).length() < number(tolerance)
+}
+
+/// A fresh directory with each of `files` written into it
+pub fn game_data_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("space-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    for (path, contents) in files {
+        let path = directory.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+    directory
+}
+
+/// Registers a resource from the same json a `GameData` file would contain
+pub fn resource(data: &mut GameData, config: Value) -> ResourceId {
+    let config: space::resource::ResourceConfig = serde_json::from_value(config).unwrap();
+    let name = config.name.clone();
+    data.resources
+        .insert(&name, ResourceDefinition::new(config))
+}
+
+/// Registers electric charge, which flows between every part of a vessel and weighs nothing
+pub fn electric_charge(data: &mut GameData) -> ResourceId {
+    resource(
+        data,
+        json!({ "name": "ElectricCharge", "title": "", "density": 0, "flow_mode": "all_vessel" }),
+    )
+}
+
+/// Registers a part without a model, so it needs an explicit `inertia` to rotate
+pub fn part(data: &mut GameData, config: Value) -> PartId {
+    part_with_model(data, config, Model::default())
+}
+
+/// Registers a part with geometry built in the test rather than loaded from a file
+pub fn part_with_model(data: &mut GameData, mut config: Value, model: Model) -> PartId {
+    for (key, value) in [
+        ("model", ""),
+        ("title", ""),
+        ("texture_color_map", ""),
+        ("discription", ""),
+    ] {
+        config
+            .as_object_mut()
+            .unwrap()
+            .entry(key)
+            .or_insert(value.into());
+    }
+    let config: space::part::PartConfig = serde_json::from_value(config).unwrap();
+    let name = config.name.clone();
+    let definition = PartDefinition::new(config, model, PathBuf::new(), &data.resources);
+    data.parts.insert(&name, definition)
+}
+
+/// Corners of a cube with sides of 2 meters around the origin
+pub fn cube_corners() -> Vec<[f64; 3]> {
+    let mut corners = Vec::new();
+    for x in [-1.0, 1.0] {
+        for y in [-1.0, 1.0] {
+            for z in [-1.0, 1.0] {
+                corners.push([x, y, z]);
+            }
+        }
+    }
+    corners
+}
+
+/// The cube from [`cube_corners`] as consistently wound triangles, the way a model would hold it
+pub fn cube_model() -> Model {
+    let hull = ConvexHull::from_points(&cube_corners());
+    let vertex = |index: usize| {
+        let corner = hull.vertices[index];
+        Vertex {
+            position: cgmath::Vector3::new(corner.x.to_num(), corner.y.to_num(), corner.z.to_num()),
+            normal: cgmath::Vector3::new(0.0, 0.0, 0.0),
+            texture_coords: cgmath::Vector2::new(0.0, 0.0),
+        }
+    };
+    Model {
+        vertices: hull.faces.iter().flatten().copied().map(vertex).collect(),
+        thrusters: Vec::new(),
+    }
+}
+
+/// `cubes` 2 meter, 1 tonne cubes stacked downwards from the root, with the default joints
+pub fn stack(data: &mut GameData, cubes: usize) -> Vessel {
+    let cube = part_with_model(
+        data,
+        json!({ "name": "cube", "mass": 1000.0 }),
+        cube_model(),
+    );
+    let mut vessel = Vessel::new(cube, Transform::IDENTITY, data);
+    for index in 1..cubes {
+        vessel.attach(
+            index - 1,
+            cube,
+            Transform::translation(Vector3::Y * number(-2.0 * index as f64)),
+            data,
+        );
+    }
+    vessel
+}
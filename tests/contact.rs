@@ -0,0 +1,383 @@
+mod common;
+
+use common::number;
+use serde_json::json;
+use space::{
+    body::Body,
+    contact::{self, Crash, Ground},
+    game_data::GameData,
+    motor::Transform,
+    part::PartId,
+    physics::Environment,
+    terrain::{Noise, Terrain},
+    vector3::Vector3,
+    vessel::Vessel,
+    Number,
+};
+use std::sync::Arc;
+
+const DT: f64 = 0.02;
+
+fn floor() -> Ground {
+    Ground::Plane {
+        point: Vector3::ZERO,
+        normal: Vector3::Y,
+    }
+}
+
+fn earth_gravity() -> Environment {
+    Environment {
+        gravity: Vector3::Y * number(-9.81),
+        ..Environment::VACUUM
+    }
+}
+
+/// A 2 meter, 1 tonne cube
+fn cube(data: &mut GameData, name: &str, config: serde_json::Value) -> PartId {
+    let mut config = config;
+    config["name"] = name.into();
+    config["mass"] = 1000.0.into();
+    common::part_with_model(data, config, common::cube_model())
+}
+
+/// Runs every vessel and their contacts for `seconds`, returning every crash
+fn run(
+    vessels: &mut Vec<Vessel>,
+    data: &GameData,
+    ground: &Ground,
+    environment: impl Fn(&Vessel) -> Environment,
+    seconds: f64,
+) -> Vec<Crash> {
+    let mut crashes = Vec::new();
+    for _ in 0..(seconds / DT) as usize {
+        for vessel in vessels.iter_mut() {
+            let environment = environment(vessel);
+            vessel.update(data, &environment, number(DT));
+        }
+        crashes.extend(contact::resolve_contacts(vessels, data, Some(ground)));
+    }
+    crashes
+}
+
+/// Largest speed and the range of heights above `ground` the vessel moves through over
+/// `seconds`, which stay tiny for a vessel at rest
+fn jitter(
+    vessel: &mut Vec<Vessel>,
+    data: &GameData,
+    ground: &Ground,
+    environment: impl Fn(&Vessel) -> Environment,
+    seconds: f64,
+) -> (f64, f64, f64) {
+    let mut max_speed = 0.0f64;
+    let mut min_height = f64::MAX;
+    let mut max_height = f64::MIN;
+    for _ in 0..(seconds / DT) as usize {
+        run(vessel, data, ground, &environment, DT);
+        let vessel = &vessel[0];
+        let speed = vessel.velocity.length() + vessel.angular_velocity.length();
+        let (height, _) = ground.probe(vessel.transform.position());
+        max_speed = max_speed.max(speed.to_num());
+        min_height = min_height.min(height.to_num());
+        max_height = max_height.max(height.to_num());
+    }
+    (max_speed, min_height, max_height)
+}
+
+#[test]
+fn settles_on_a_plane() {
+    let mut data = GameData::new();
+    let cube = cube(&mut data, "cube", json!({}));
+    let tilted = Transform::rotation(Vector3::Z, number(0.3))
+        .apply(Transform::rotation(Vector3::X, number(0.2)))
+        .apply(Transform::translation(Vector3::Y * number(4.0)));
+    let mut vessels = vec![Vessel::new(cube, tilted, &data)];
+    let ground = floor();
+
+    let crashes = run(&mut vessels, &data, &ground, |_| earth_gravity(), 8.0);
+    assert!(crashes.is_empty());
+    let (speed, min_height, max_height) =
+        jitter(&mut vessels, &data, &ground, |_| earth_gravity(), 2.0);
+
+    assert!(speed < 0.05, "still moving at {speed}");
+    assert!(
+        max_height - min_height < 0.001,
+        "{min_height}..{max_height}"
+    );
+    assert!((min_height - 1.0).abs() < 0.02, "resting at {min_height}");
+    let up = vessels[0].transform.transform_direction(Vector3::Y);
+    let flat = [Vector3::X, Vector3::Y, Vector3::Z]
+        .map(|axis| {
+            vessels[0]
+                .transform
+                .transform_direction(axis)
+                .dot(Vector3::Y)
+                .abs()
+        })
+        .into_iter()
+        .max()
+        .unwrap();
+    assert!(flat > number(0.999), "tipped over, up is {up:?}");
+}
+
+#[test]
+fn settles_on_a_sphere() {
+    let mut data = GameData::new();
+    let cube = cube(&mut data, "cube", json!({}));
+    let body = Body::new(number(100_000.0), number(9.81e10));
+    let start = body.surface_position(number(0.3), number(1.0), number(3.0));
+    let mut vessels = vec![Vessel::new(
+        cube,
+        Transform::rotation(Vector3::X, number(0.5)).apply(Transform::translation(start)),
+        &data,
+    )];
+    let ground = body.ground();
+    let environment = |vessel: &Vessel| body.environment(vessel.transform.position());
+
+    let crashes = run(&mut vessels, &data, &ground, environment, 8.0);
+    assert!(crashes.is_empty());
+    let (speed, min_height, max_height) = jitter(&mut vessels, &data, &ground, environment, 2.0);
+
+    assert!(speed < 0.05, "still moving at {speed}");
+    assert!(
+        max_height - min_height < 0.001,
+        "{min_height}..{max_height}"
+    );
+    assert!((min_height - 1.0).abs() < 0.02, "resting at {min_height}");
+}
+
+#[test]
+fn settles_on_hilly_terrain() {
+    let mut data = GameData::new();
+    let cube = cube(&mut data, "cube", json!({}));
+    let body = Body {
+        terrain: Some(Arc::new(Terrain::Noise(Noise {
+            seed: 3,
+            amplitude: number(500.0),
+            wavelength: number(20_000.0),
+            octaves: 3,
+            persistence: number(0.5),
+            radius: number(100_000.0),
+        }))),
+        ..Body::new(number(100_000.0), number(9.81e10))
+    };
+    let (latitude, longitude) = (number(0.3), number(1.0));
+    let ground_height = body.terrain_height(latitude, longitude);
+    assert!(
+        ground_height.abs() > number(10.0),
+        "flat at {ground_height}"
+    );
+    let start = body.surface_position(latitude, longitude, ground_height + number(3.0));
+    let mut vessels = vec![Vessel::new(
+        cube,
+        Transform::rotation(Vector3::X, number(0.5)).apply(Transform::translation(start)),
+        &data,
+    )];
+    let ground = body.ground();
+    let environment = |vessel: &Vessel| body.environment(vessel.transform.position());
+
+    let crashes = run(&mut vessels, &data, &ground, environment, 8.0);
+    assert!(crashes.is_empty());
+    let (speed, min_height, max_height) = jitter(&mut vessels, &data, &ground, environment, 2.0);
+
+    // Resting on the hillside rather than at sea level
+    assert!(speed < 0.05, "still moving at {speed}");
+    assert!(
+        max_height - min_height < 0.001,
+        "{min_height}..{max_height}"
+    );
+    assert!((min_height - 1.0).abs() < 0.05, "resting at {min_height}");
+    let position = vessels[0].transform.position();
+    let altitude = body.altitude(position);
+    assert!(
+        (altitude - ground_height).abs() < number(5.0),
+        "{altitude} above sea level"
+    );
+}
+
+#[test]
+fn turns_along_with_a_spinning_sphere() {
+    let mut data = GameData::new();
+    let cube = cube(&mut data, "cube", json!({}));
+    // The equator moves at 314 m/s, almost fast enough to throw the cube off
+    let rate = std::f64::consts::TAU / 2000.0;
+    let body = Body {
+        angular_velocity: Vector3::Z * number(rate),
+        ..Body::new(number(100_000.0), number(9.81e10))
+    };
+    let start = body.surface_position(Number::ZERO, Number::ZERO, number(1.2));
+    let mut vessel = Vessel::new(
+        cube,
+        Transform::rotation(Vector3::Z, -Number::FRAC_PI_2).apply(Transform::translation(start)),
+        &data,
+    );
+    // Sliding sideways relative to the ground underneath
+    vessel.velocity = body.rotation_velocity(start) + Vector3::Y * number(2.0);
+    let mut vessels = vec![vessel];
+    let ground = body.ground();
+    let environment = |vessel: &Vessel| body.environment(vessel.transform.position());
+
+    let crashes = run(&mut vessels, &data, &ground, environment, 8.0);
+
+    assert!(crashes.is_empty());
+    let vessel = &vessels[0];
+    let position = vessel.transform.position();
+    let relative = body.surface_velocity(position, vessel.velocity);
+    assert!(relative.length() < number(0.05), "sliding at {relative:?}");
+    let (height, _) = ground.probe(position);
+    assert!(
+        (height - number(1.0)).abs() < number(0.02),
+        "resting at {height}"
+    );
+    assert!(position.y > number(2000.0), "left behind at {position:?}");
+}
+
+#[test]
+fn fast_impact_destroys_the_part() {
+    let mut data = GameData::new();
+    let fragile = cube(&mut data, "fragile", json!({ "crash_tolerance": 8.0 }));
+    let tough = cube(&mut data, "tough", json!({ "crash_tolerance": 50.0 }));
+    let mut vessel = Vessel::new(
+        tough,
+        Transform::translation(Vector3::Y * number(3.5)),
+        &data,
+    );
+    vessel.attach(
+        0,
+        fragile,
+        Transform::translation(Vector3::Y * number(-2.0)),
+        &data,
+    );
+    vessel.velocity = Vector3::Y * number(-20.0);
+    let mut vessels = vec![vessel];
+
+    let crashes = run(&mut vessels, &data, &floor(), |_| earth_gravity(), 3.0);
+
+    assert_eq!(crashes.len(), 1);
+    assert_eq!(crashes[0].part, fragile);
+    assert!(crashes[0].speed > number(19.0));
+    assert_eq!(vessels.len(), 1);
+    assert_eq!(vessels[0].parts.len(), 1);
+    assert_eq!(vessels[0].parts[0].part, tough);
+}
+
+#[test]
+fn destroying_the_middle_splits_the_vessel() {
+    let mut data = GameData::new();
+    let cube = cube(&mut data, "cube", json!({}));
+    let mut vessel = Vessel::new(cube, Transform::IDENTITY, &data);
+    let middle = vessel.attach(
+        0,
+        cube,
+        Transform::translation(Vector3::Y * number(-2.0)),
+        &data,
+    );
+    vessel.attach(
+        middle,
+        cube,
+        Transform::translation(Vector3::Y * number(-4.0)),
+        &data,
+    );
+    vessel.angular_velocity = Vector3::Z;
+
+    let mut destroyed = vec![false; 3];
+    destroyed[middle] = true;
+    let pieces = vessel.destroy_parts(&destroyed, &data);
+
+    assert_eq!(vessel.parts.len(), 1);
+    assert_eq!(pieces.len(), 1);
+    assert_eq!(pieces[0].parts.len(), 1);
+    assert!(pieces[0].parts[0].parent.is_none());
+    // Both pieces keep moving the way they were while spinning around the middle
+    assert!((vessel.velocity.x + number(2.0)).abs() < number(0.001));
+    assert!((pieces[0].velocity.x - number(2.0)).abs() < number(0.001));
+}
+
+#[test]
+fn slow_impact_bounces() {
+    let mut data = GameData::new();
+    let cube = cube(&mut data, "cube", json!({ "restitution": 0.5 }));
+    let mut vessel = Vessel::new(
+        cube,
+        Transform::translation(Vector3::Y * number(1.5)),
+        &data,
+    );
+    vessel.velocity = Vector3::Y * number(-4.0);
+    let mut vessels = vec![vessel];
+
+    run(&mut vessels, &data, &floor(), |_| Environment::VACUUM, 0.5);
+
+    let velocity = vessels[0].velocity;
+    assert!(
+        (velocity.y - number(2.0)).abs() < number(0.05),
+        "{velocity:?}"
+    );
+    assert!(velocity.x.abs() < number(0.001) && velocity.z.abs() < number(0.001));
+}
+
+#[test]
+fn friction_stops_sliding() {
+    let mut data = GameData::new();
+    let cube = cube(&mut data, "cube", json!({ "friction": 0.6 }));
+    let mut vessel = Vessel::new(
+        cube,
+        Transform::translation(Vector3::Y * number(0.999)),
+        &data,
+    );
+    vessel.velocity = Vector3::X * number(3.0);
+    let mut vessels = vec![vessel];
+
+    run(&mut vessels, &data, &floor(), |_| earth_gravity(), 2.0);
+
+    // Friction decelerates at 0.6 g, stopping after v² / 2a
+    let stopping_distance = 9.0 / (2.0 * 0.6 * 9.81);
+    let position = vessels[0].transform.position();
+    assert!(vessels[0].velocity.length() < number(0.01));
+    assert!(
+        (position.x.to_num::<f64>() - stopping_distance).abs() < 0.15,
+        "slid {}",
+        position.x
+    );
+}
+
+#[test]
+fn vessels_push_each_other_apart() {
+    let mut data = GameData::new();
+    let cube = cube(&mut data, "cube", json!({ "restitution": 0.25 }));
+    let mut vessels = [-1.5, 1.5]
+        .map(|x| {
+            let mut vessel =
+                Vessel::new(cube, Transform::translation(Vector3::X * number(x)), &data);
+            vessel.velocity = Vector3::X * number(-x.signum() * 2.0);
+            vessel
+        })
+        .to_vec();
+    // Far below, so only the vessels touch
+    let ground = Ground::Plane {
+        point: Vector3::Y * number(-100.0),
+        normal: Vector3::Y,
+    };
+
+    run(&mut vessels, &data, &ground, |_| Environment::VACUUM, 0.5);
+
+    let total = vessels[0].velocity + vessels[1].velocity;
+    assert!(
+        total.length() < number(0.001),
+        "momentum changed by {total:?}"
+    );
+    assert!(vessels[1].velocity.x > vessels[0].velocity.x);
+    // They started with 4 kJ, and a bounce this soft loses most of it even counting the spin
+    // the single contact point adds
+    let energy = vessels
+        .iter()
+        .map(|vessel| {
+            let mass_properties = vessel.mass_properties(&data);
+            let inertia = mass_properties.transformed_inertia(vessel.transform);
+            mass_properties.mass * vessel.velocity.sqr_length()
+                + vessel
+                    .angular_velocity
+                    .dot(inertia * vessel.angular_velocity)
+        })
+        .sum::<Number>()
+        / 2;
+    assert!(energy < number(2000.0), "{energy}");
+}
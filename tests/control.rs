@@ -0,0 +1,262 @@
+mod common;
+
+use common::{close, game_data_dir, number};
+use serde_json::json;
+use space::{
+    control::{self, ControlInput},
+    engine::{EngineOutput, STANDARD_GRAVITY},
+    game_data::GameData,
+    motor::Transform,
+    part,
+    vector3::Vector3,
+    vessel::Vessel,
+    Number,
+};
+
+const DT: f64 = 0.1;
+
+/// A reaction wheel using 2 units of charge a second at full effort, an RCS block with four
+/// thrusters that pitch it or push it along Z, and an engine gimballing 3 degrees
+fn setup() -> GameData {
+    let mut data = GameData::new();
+    common::electric_charge(&mut data);
+    common::resource(
+        &mut data,
+        json!({ "name": "MonoPropellant", "title": "", "density": 4 }),
+    );
+    common::part(
+        &mut data,
+        json!({
+            "name": "wheel",
+            "mass": 100,
+            "inertia": [100, 100, 100],
+            "reaction_wheel": {
+                "torque": [100, 200, 300],
+                "resources": [{ "name": "ElectricCharge", "rate": 2 }],
+            },
+            "resources": [{ "name": "ElectricCharge", "capacity": 100, "amount": 100 }],
+        }),
+    );
+    common::part(
+        &mut data,
+        json!({
+            "name": "rcs",
+            "mass": 100,
+            "inertia": [100, 100, 100],
+            "rcs": {
+                "thrust": 1000,
+                "isp": [[0, 250]],
+                "propellants": [{ "name": "MonoPropellant", "ratio": 1 }],
+                "thrusters": [
+                    { "position": [0, 1, 0], "direction": [0, 0, 1] },
+                    { "position": [0, 1, 0], "direction": [0, 0, -1] },
+                    { "position": [0, -1, 0], "direction": [0, 0, 1] },
+                    { "position": [0, -1, 0], "direction": [0, 0, -1] },
+                ],
+            },
+            "resources": [{ "name": "MonoPropellant", "capacity": 100, "amount": 100 }],
+        }),
+    );
+    common::part(
+        &mut data,
+        json!({
+            "name": "engine",
+            "mass": 100,
+            "inertia": [100, 100, 100],
+            "engine": {
+                "max_thrust": 10000,
+                "isp": [[0, 300]],
+                "propellants": [{ "name": "MonoPropellant", "ratio": 1 }],
+                "max_gimbal": 3,
+            },
+        }),
+    );
+    data
+}
+
+fn steer(pitch: f64, roll: f64, yaw: f64) -> ControlInput {
+    ControlInput {
+        pitch: number(pitch),
+        roll: number(roll),
+        yaw: number(yaw),
+        ..ControlInput::NEUTRAL
+    }
+}
+
+fn held(vessel: &Vessel) -> Number {
+    vessel.parts[0].resources[0].amount
+}
+
+#[test]
+fn reaction_wheels_turn_around_each_requested_axis() {
+    let data = setup();
+    let wheel = data.parts.id("wheel").unwrap();
+    let dt = number(DT);
+
+    for (control, torque, effort) in [
+        (steer(1.0, 0.0, 0.0), Vector3::X * number(100.0), 1.0),
+        (steer(0.0, 1.0, 0.0), Vector3::Y * number(200.0), 1.0),
+        (steer(0.0, 0.0, -1.0), Vector3::Z * number(-300.0), 1.0),
+        (
+            steer(0.25, -0.5, 0.0),
+            Vector3::new(number(25.0), number(-100.0), Number::ZERO),
+            0.5,
+        ),
+    ] {
+        let mut vessel = Vessel::new(wheel, Transform::IDENTITY, &data);
+        vessel.control = control;
+        let made = control::run_reaction_wheels(&mut vessel, &data, dt);
+        assert!(close(made, torque, 1e-6), "{made:?}");
+        // Charge goes by the busiest axis
+        let used = number(100.0) - held(&vessel);
+        assert!(
+            (used - number(2.0 * effort * DT)).abs() < number(1e-6),
+            "{used}"
+        );
+    }
+
+    // A wheel turned a quarter around Y pitches with what was its Z axis
+    let mut vessel = Vessel::new(wheel, Transform::IDENTITY, &data);
+    vessel.parts[0].transform = Transform::rotation(Vector3::Y, Number::FRAC_PI_2);
+    vessel.control = steer(1.0, 0.0, 0.0);
+    let torque = control::run_reaction_wheels(&mut vessel, &data, dt);
+    assert!(
+        close(torque, Vector3::X * number(300.0), 1e-6),
+        "{torque:?}"
+    );
+
+    // And without charge it does nothing
+    let mut vessel = Vessel::new(wheel, Transform::IDENTITY, &data);
+    vessel.parts[0].resources[0].amount = Number::ZERO;
+    vessel.control = steer(1.0, 1.0, 1.0);
+    let torque = control::run_reaction_wheels(&mut vessel, &data, dt);
+    assert_eq!(torque, Vector3::ZERO);
+}
+
+#[test]
+fn rcs_fires_only_the_thrusters_that_help() {
+    let data = setup();
+    let rcs = data.parts.id("rcs").unwrap();
+    let dt = number(DT);
+    // Kilograms a second per thruster, at 4 kilograms a unit
+    let units_per_thruster = 1000.0 / (250.0 * STANDARD_GRAVITY) / 4.0 * DT;
+
+    let run = |control: ControlInput| {
+        let mut vessel = Vessel::new(rcs, Transform::IDENTITY, &data);
+        vessel.control = control;
+        let forces = control::run_rcs(&mut vessel, &data, Number::ZERO, Vector3::ZERO, dt);
+        (forces, number(100.0) - held(&vessel))
+    };
+
+    // Pitching fires the pair turning it the right way, whose pushes cancel out
+    let (forces, used) = run(steer(1.0, 0.0, 0.0));
+    assert!(close(forces.force, Vector3::ZERO, 1e-6));
+    assert!(
+        close(forces.torque, Vector3::X * number(2000.0), 1e-3),
+        "{:?}",
+        forces.torque
+    );
+    assert!(
+        (used - number(2.0 * units_per_thruster)).abs() < number(1e-6),
+        "{used}"
+    );
+    let (forces, _) = run(steer(-1.0, 0.0, 0.0));
+    assert!(close(forces.torque, Vector3::X * number(-2000.0), 1e-3));
+
+    // Translating fires the pair pushing that way, whose turns cancel out
+    let (forces, used) = run(ControlInput {
+        translate: Vector3::Z,
+        ..ControlInput::NEUTRAL
+    });
+    assert!(
+        close(forces.force, Vector3::Z * number(2000.0), 1e-3),
+        "{:?}",
+        forces.force
+    );
+    assert!(close(forces.torque, Vector3::ZERO, 1e-6));
+    assert!(
+        (used - number(2.0 * units_per_thruster)).abs() < number(1e-6),
+        "{used}"
+    );
+
+    // None of them can yaw or roll it, so nothing fires
+    let (forces, used) = run(steer(0.0, 1.0, 1.0));
+    assert_eq!(forces.force, Vector3::ZERO);
+    assert_eq!(forces.torque, Vector3::ZERO);
+    assert_eq!(used, Number::ZERO);
+}
+
+#[test]
+fn engines_gimbal_towards_the_requested_rotation() {
+    let data = setup();
+    let mut vessel = Vessel::new(data.parts.id("rcs").unwrap(), Transform::IDENTITY, &data);
+    vessel.attach(
+        0,
+        data.parts.id("engine").unwrap(),
+        Transform::translation(Vector3::Y * number(-2.0)),
+        &data,
+    );
+    let max_torque = number(2.0) * number(3f64.to_radians().sin());
+
+    for (control, axis) in [
+        (steer(1.0, 0.0, 0.0), Vector3::X),
+        (steer(-1.0, 0.0, 0.0), -Vector3::X),
+        (steer(0.0, 0.0, 1.0), Vector3::Z),
+        (steer(0.0, 0.0, -1.0), -Vector3::Z),
+    ] {
+        vessel.control = control;
+        let mut outputs = [EngineOutput {
+            part: 1,
+            thrust: Number::ONE,
+            direction: Vector3::Y,
+            flameout: false,
+        }];
+        control::gimbal_engines(&vessel, &data, &mut outputs, Vector3::ZERO);
+        // Two meters below the center of mass, tilted all the way over
+        let torque = (Vector3::Y * number(-2.0)).cross(outputs[0].direction);
+        assert!(close(torque, axis * max_torque, 1e-6), "{torque:?}");
+    }
+
+    // Rolling is out of an engine's reach
+    vessel.control = steer(0.0, 1.0, 0.0);
+    let mut outputs = [EngineOutput {
+        part: 1,
+        thrust: Number::ONE,
+        direction: Vector3::Y,
+        flameout: false,
+    }];
+    control::gimbal_engines(&vessel, &data, &mut outputs, Vector3::ZERO);
+    assert_eq!(outputs[0].direction, Vector3::Y);
+}
+
+#[test]
+#[should_panic(expected = "thruster in")]
+fn model_thrusters_need_faces_to_point_them() {
+    let directory = game_data_dir(
+        "flat-thruster",
+        &[(
+            "part.obj",
+            "v 0 0 0\nv 1 0 0\nv 2 0 0\no thruster\nf 1 2 3\n",
+        )],
+    );
+    part::load_model(&directory.join("part.obj"));
+}
+
+#[test]
+#[should_panic(expected = "broken-rcs needs a positive vacuum isp for its rcs")]
+fn rcs_needs_a_vacuum_isp() {
+    let mut data = setup();
+    common::part(
+        &mut data,
+        json!({
+            "name": "broken-rcs",
+            "mass": 100,
+            "rcs": {
+                "thrust": 1000,
+                "isp": [[0, 0], [1, 100]],
+                "propellants": [{ "name": "MonoPropellant", "ratio": 1 }],
+                "thrusters": [{ "position": [0, 1, 0], "direction": [0, 0, 1] }],
+            },
+        }),
+    );
+}
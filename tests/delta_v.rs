@@ -0,0 +1,175 @@
+mod common;
+
+use serde_json::json;
+use space::{
+    delta_v, engine::STANDARD_GRAVITY, game_data::GameData, motor::Transform, staging,
+    vessel::Vessel, Number,
+};
+
+fn setup() -> GameData {
+    let mut data = GameData::new();
+    common::resource(
+        &mut data,
+        json!({ "name": "LiquidFuel", "title": "", "density": 5 }),
+    );
+    common::part(
+        &mut data,
+        json!({
+            "name": "tank",
+            "mass": 100,
+            "resources": [{ "name": "LiquidFuel", "capacity": 100 }],
+        }),
+    );
+    common::part(
+        &mut data,
+        json!({
+            "name": "engine",
+            "mass": 100,
+            "engine": {
+                "max_thrust": 10000,
+                "isp": [[0, 300], [1, 250]],
+                "propellants": [{ "name": "LiquidFuel", "ratio": 1 }],
+            },
+        }),
+    );
+    common::part(
+        &mut data,
+        json!({
+            "name": "decoupler",
+            "mass": 50,
+            "crossfeed": false,
+            "decoupler": { "ejection_impulse": 100 },
+        }),
+    );
+    data
+}
+
+fn attach(vessel: &mut Vessel, parent: usize, part: &str, data: &GameData) -> usize {
+    vessel.attach(
+        parent,
+        data.parts.id(part).unwrap(),
+        Transform::IDENTITY,
+        data,
+    )
+}
+
+fn assert_close(actual: Number, expected: f64, tolerance: f64) {
+    let actual = actual.to_num::<f64>();
+    assert!(
+        (actual - expected).abs() <= tolerance,
+        "expected {expected}, got {actual}"
+    );
+}
+
+#[test]
+fn single_stage_matches_rocket_equation() {
+    let data = setup();
+    let mut vessel = Vessel::new(data.parts.id("tank").unwrap(), Transform::IDENTITY, &data);
+    attach(&mut vessel, 0, "engine", &data);
+    staging::assign_stages(&mut vessel, &data);
+
+    let gravity = Number::from_num(STANDARD_GRAVITY);
+    let stages = delta_v::stage_stats(&vessel, &data, gravity, Number::ZERO);
+    assert_eq!(stages.len(), 1);
+    let stage = stages[0];
+    assert_eq!(stage.start_mass, Number::from_num(700));
+    assert_close(stage.end_mass, 200.0, 1e-6);
+    assert_close(stage.isp, 300.0, 1e-3);
+    assert_close(stage.delta_v, 300.0 * STANDARD_GRAVITY * 3.5f64.ln(), 1e-3);
+    assert_close(
+        stage.burn_time,
+        500.0 * 300.0 * STANDARD_GRAVITY / 10000.0,
+        1e-3,
+    );
+    assert_close(stage.twr, 10000.0 / (700.0 * STANDARD_GRAVITY), 1e-6);
+    assert_close(stage.max_twr, 10000.0 / (200.0 * STANDARD_GRAVITY), 1e-6);
+
+    // Working out the numbers leaves the vessel itself untouched
+    assert_eq!(vessel.mass(&data), Number::from_num(700));
+}
+
+#[test]
+fn pressure_lowers_isp_and_thrust() {
+    let data = setup();
+    let mut vessel = Vessel::new(data.parts.id("tank").unwrap(), Transform::IDENTITY, &data);
+    attach(&mut vessel, 0, "engine", &data);
+    staging::assign_stages(&mut vessel, &data);
+
+    let gravity = Number::from_num(STANDARD_GRAVITY);
+    let stage = delta_v::stage_stats(&vessel, &data, gravity, Number::ONE)[0];
+    assert_close(stage.isp, 250.0, 1e-3);
+    assert_close(stage.delta_v, 250.0 * STANDARD_GRAVITY * 3.5f64.ln(), 1e-3);
+    assert_close(stage.thrust, 10000.0 * 250.0 / 300.0, 1e-3);
+    // The same fuel flow still takes just as long to burn
+    assert_close(
+        stage.burn_time,
+        500.0 * 300.0 * STANDARD_GRAVITY / 10000.0,
+        1e-3,
+    );
+}
+
+#[test]
+fn dropped_stages_stop_counting_towards_mass() {
+    let data = setup();
+    let mut vessel = Vessel::new(data.parts.id("tank").unwrap(), Transform::IDENTITY, &data);
+    let upper_engine = attach(&mut vessel, 0, "engine", &data);
+    let decoupler = attach(&mut vessel, upper_engine, "decoupler", &data);
+    let lower_tank = attach(&mut vessel, decoupler, "tank", &data);
+    attach(&mut vessel, lower_tank, "engine", &data);
+    staging::assign_stages(&mut vessel, &data);
+
+    let gravity = Number::from_num(9.81);
+    let stages = delta_v::stage_stats(&vessel, &data, gravity, Number::ZERO);
+    assert_eq!(stages.len(), 2);
+
+    // The lower engine can't reach the upper tank through the decoupler
+    assert_eq!(stages[0].start_mass, Number::from_num(1450));
+    assert_close(stages[0].end_mass, 950.0, 1e-6);
+    assert_close(
+        stages[0].delta_v,
+        300.0 * STANDARD_GRAVITY * (1450.0f64 / 950.0).ln(),
+        1e-3,
+    );
+
+    // The decoupler stays on the upper stage
+    assert_eq!(stages[1].start_mass, Number::from_num(750));
+    assert_close(stages[1].end_mass, 250.0, 1e-6);
+    assert_close(stages[1].twr, 10000.0 / (750.0 * 9.81), 1e-6);
+}
+
+#[test]
+fn engines_sharing_a_stage_burn_at_their_combined_isp() {
+    let mut data = setup();
+    common::part(
+        &mut data,
+        json!({
+            "name": "vacuum-engine",
+            "mass": 100,
+            "engine": {
+                "max_thrust": 10000,
+                "isp": [[0, 400]],
+                "propellants": [{ "name": "LiquidFuel", "ratio": 1 }],
+            },
+        }),
+    );
+    let mut vessel = Vessel::new(data.parts.id("tank").unwrap(), Transform::IDENTITY, &data);
+    attach(&mut vessel, 0, "engine", &data);
+    attach(&mut vessel, 0, "vacuum-engine", &data);
+    staging::assign_stages(&mut vessel, &data);
+
+    let gravity = Number::from_num(STANDARD_GRAVITY);
+    let stages = delta_v::stage_stats(&vessel, &data, gravity, Number::ZERO);
+    assert_eq!(stages.len(), 1);
+    let stage = stages[0];
+    // Weighted by how much propellant each engine goes through
+    let mass_flow = 10000.0 / (300.0 * STANDARD_GRAVITY) + 10000.0 / (400.0 * STANDARD_GRAVITY);
+    let isp = 20000.0 / mass_flow / STANDARD_GRAVITY;
+    assert_close(stage.isp, isp, 1e-3);
+    assert_close(stage.thrust, 20000.0, 1e-3);
+    assert_close(stage.burn_time, 500.0 / mass_flow, 1e-3);
+    assert_close(
+        stage.delta_v,
+        isp * STANDARD_GRAVITY * (800.0f64 / 300.0).ln(),
+        1e-3,
+    );
+}
@@ -0,0 +1,361 @@
+mod common;
+
+use common::number;
+use serde_json::json;
+use space::{
+    body, control::ControlInput, electric, flow, game_data::GameData, light::Sunlight,
+    motor::Transform, physics::Environment, vector3::Vector3, vessel::Vessel, Number,
+};
+use std::path::Path;
+
+fn close(a: Number, b: Number) -> bool {
+    (a - b).abs() < number(1e-3)
+}
+
+/// A battery, a probe core using 1 unit of charge per second, and panels making 2 per second
+/// when fully lit: fixed facing +Z, tracking around +Y, and a deployable fixed one
+fn setup() -> GameData {
+    let mut data = GameData::new();
+    common::electric_charge(&mut data);
+    let made = json!([{ "name": "ElectricCharge", "rate": 2 }]);
+    common::part(
+        &mut data,
+        json!({
+            "name": "battery",
+            "mass": 10,
+            "inertia": [1, 1, 1],
+            "resources": [{ "name": "ElectricCharge", "capacity": 10, "amount": 2 }],
+        }),
+    );
+    common::part(
+        &mut data,
+        json!({
+            "name": "probe",
+            "mass": 10,
+            "inertia": [1, 1, 1],
+            "probe_core": { "resources": [{ "name": "ElectricCharge", "rate": 1 }] },
+        }),
+    );
+    common::part(
+        &mut data,
+        json!({ "name": "fixed-panel", "mass": 1, "solar_panel": { "resources": made } }),
+    );
+    common::part(
+        &mut data,
+        json!({
+            "name": "tracking-panel",
+            "mass": 1,
+            "solar_panel": { "resources": made, "pivot": [0, 1, 0] },
+        }),
+    );
+    common::part(
+        &mut data,
+        json!({
+            "name": "deployable-panel",
+            "mass": 1,
+            "solar_panel": { "resources": made, "deployable": true },
+        }),
+    );
+    data
+}
+
+fn vessel(data: &GameData, parts: &[&str]) -> Vessel {
+    let mut vessel = Vessel::new(data.parts.id(parts[0]).unwrap(), Transform::IDENTITY, data);
+    for part in &parts[1..] {
+        vessel.attach(0, data.parts.id(part).unwrap(), Transform::IDENTITY, data);
+    }
+    vessel
+}
+
+/// Sunlight at the reference flux times `brightness` from `direction`
+fn sunlit(direction: Vector3, brightness: f64) -> Environment {
+    Environment {
+        sun_direction: direction / direction.length(),
+        solar_flux: electric::REFERENCE_FLUX * number(brightness),
+        ..Environment::VACUUM
+    }
+}
+
+fn output(vessel: &Vessel, data: &GameData, environment: &Environment) -> Number {
+    electric::solar_output(vessel, data, environment)
+        .iter()
+        .map(|&(_, rate)| rate)
+        .fold(Number::ZERO, |total, rate| total + rate)
+}
+
+fn charge_held(vessel: &Vessel, data: &GameData) -> Number {
+    let charge = data.resources.id("ElectricCharge").unwrap();
+    vessel
+        .parts
+        .iter()
+        .flat_map(|part| &part.resources)
+        .filter(|held| held.resource == charge)
+        .fold(Number::ZERO, |total, held| total + held.amount)
+}
+
+#[test]
+fn fixed_panels_follow_the_sun_angle_and_brightness() {
+    let data = setup();
+    let charge = data.resources.id("ElectricCharge").unwrap();
+    let vessel = vessel(&data, &["battery", "fixed-panel"]);
+
+    let made = electric::solar_output(&vessel, &data, &sunlit(Vector3::Z, 1.0));
+    assert_eq!(made.len(), 1);
+    assert_eq!(made[0].0, charge);
+    assert!(close(made[0].1, number(2.0)));
+    // 60 degrees off the panel's face, and half as bright
+    let slanted = Vector3::new(number(3f64.sqrt()), Number::ZERO, Number::ONE);
+    assert!(close(
+        output(&vessel, &data, &sunlit(slanted, 1.0)),
+        number(1.0)
+    ));
+    assert!(close(
+        output(&vessel, &data, &sunlit(Vector3::Z, 0.5)),
+        number(1.0)
+    ));
+    // Edge on and from behind
+    assert!(close(
+        output(&vessel, &data, &sunlit(Vector3::X, 1.0)),
+        Number::ZERO
+    ));
+    assert!(close(
+        output(&vessel, &data, &sunlit(-Vector3::Z, 1.0)),
+        Number::ZERO
+    ));
+    assert_eq!(output(&vessel, &data, &Environment::VACUUM), Number::ZERO);
+
+    // Turning the vessel turns the panel away
+    let mut turned = vessel.clone();
+    turned.transform = Transform::rotation(Vector3::X, number(std::f64::consts::FRAC_PI_2));
+    assert!(close(
+        output(&turned, &data, &sunlit(Vector3::Z, 1.0)),
+        Number::ZERO
+    ));
+}
+
+#[test]
+fn tracking_panels_turn_towards_the_sun() {
+    let data = setup();
+    let vessel = vessel(&data, &["battery", "tracking-panel"]);
+
+    for direction in [Vector3::X, Vector3::Z, -Vector3::X] {
+        assert!(close(
+            output(&vessel, &data, &sunlit(direction, 1.0)),
+            number(2.0)
+        ));
+    }
+    // Only the sun along the pivot is out of reach
+    let diagonal = Vector3::new(Number::ONE, Number::ONE, Number::ZERO);
+    assert!(close(
+        output(&vessel, &data, &sunlit(diagonal, 1.0)),
+        number(2.0 * std::f64::consts::FRAC_1_SQRT_2)
+    ));
+    assert!(close(
+        output(&vessel, &data, &sunlit(Vector3::Y, 1.0)),
+        Number::ZERO
+    ));
+}
+
+#[test]
+fn deployable_panels_make_nothing_while_stowed() {
+    let data = setup();
+    let mut vessel = vessel(&data, &["battery", "deployable-panel", "fixed-panel"]);
+    let environment = sunlit(Vector3::Z, 1.0);
+
+    assert!(close(output(&vessel, &data, &environment), number(2.0)));
+    electric::deploy_solar_panels(&mut vessel, &data, true);
+    assert!(vessel.parts[1].deployed);
+    // Only deployable panels have anything to deploy
+    assert!(!vessel.parts[2].deployed);
+    assert!(close(output(&vessel, &data, &environment), number(4.0)));
+    electric::deploy_solar_panels(&mut vessel, &data, false);
+    assert!(close(output(&vessel, &data, &environment), number(2.0)));
+}
+
+#[test]
+fn panels_go_dark_in_a_bodys_shadow() {
+    let mut data = GameData::load(Path::new("GameData"));
+    common::part(
+        &mut data,
+        json!({
+            "name": "tracking-panel",
+            "mass": 1,
+            "solar_panel": {
+                "resources": [{ "name": "ElectricCharge", "rate": 2 }],
+                "pivot": [0, 1, 0],
+            },
+        }),
+    );
+    let bodies = body::propagate(&data.bodies, number(1000.0));
+    let sunlight = Sunlight::new(&data.bodies, &bodies);
+    let kerbin = &bodies[data.bodies.id("kerbin").unwrap().index()];
+    let towards_sun = sunlight.direction(kerbin.position).unwrap();
+    let mut vessel = Vessel::new(
+        data.parts.id("tracking-panel").unwrap(),
+        Transform::IDENTITY,
+        &data,
+    );
+
+    let orbit = kerbin.radius + number(100_000.0);
+    let mut at = |offset: Vector3| {
+        vessel.transform = Transform::translation(kerbin.position + offset);
+        let environment = Environment::VACUUM.with_sunlight(&sunlight, vessel.transform.position());
+        output(&vessel, &data, &environment)
+    };
+    let day = at(towards_sun * orbit);
+    // Kerbin is about as far from Kerbol as the panel is rated for
+    assert!((day - number(2.0)).abs() < number(0.01), "{day}");
+    assert_eq!(at(-towards_sun * orbit), Number::ZERO);
+}
+
+#[test]
+fn probes_lose_control_without_power() {
+    let data = setup();
+    let steer = ControlInput {
+        pitch: Number::ONE,
+        ..ControlInput::NEUTRAL
+    };
+    let mut vessel = vessel(&data, &["battery", "probe"]);
+    assert!(electric::has_control(&vessel, &data));
+
+    vessel.control = steer;
+    vessel.update(&data, &Environment::VACUUM, Number::ONE);
+    assert!(close(charge_held(&vessel, &data), Number::ONE));
+    assert_eq!(vessel.control, steer);
+    vessel.update(&data, &Environment::VACUUM, Number::ONE);
+    assert!(close(charge_held(&vessel, &data), Number::ZERO));
+    assert!(!electric::has_control(&vessel, &data));
+    assert_eq!(vessel.control, ControlInput::NEUTRAL);
+
+    // Sunlight brings it back, with the panel making more than the core uses
+    let mut vessel = self::vessel(&data, &["battery", "probe", "fixed-panel"]);
+    for _ in 0..10 {
+        vessel.control = steer;
+        vessel.update(&data, &sunlit(Vector3::Z, 1.0), Number::ONE);
+    }
+    assert_eq!(vessel.control, steer);
+    let held = charge_held(&vessel, &data);
+    assert!(close(held, number(10.0)), "{held}");
+
+    // Crewed vessels stay under control either way
+    let mut vessel = self::vessel(&data, &["battery"]);
+    vessel.parts[0].resources[0].amount = Number::ZERO;
+    assert!(electric::has_control(&vessel, &data));
+}
+
+#[test]
+fn unpowered_probes_stop_burning() {
+    let mut data = setup();
+    common::resource(
+        &mut data,
+        json!({ "name": "LiquidFuel", "title": "", "density": 5 }),
+    );
+    common::part(
+        &mut data,
+        json!({
+            "name": "engine",
+            "mass": 100,
+            "inertia": [10, 10, 10],
+            "engine": {
+                "max_thrust": 1000,
+                "isp": [[0, 300]],
+                "propellants": [{ "name": "LiquidFuel", "ratio": 1 }],
+            },
+            "resources": [{ "name": "LiquidFuel", "capacity": 100 }],
+        }),
+    );
+    let mut vessel = vessel(&data, &["battery", "probe", "engine"]);
+    vessel.parts[2].activated = true;
+    vessel.throttle = Number::ONE;
+    let fuel = |vessel: &Vessel| vessel.parts[2].resources[0].amount;
+
+    // Enough charge for the first second, which burns
+    let before = fuel(&vessel);
+    let engines = vessel.update(&data, &Environment::VACUUM, Number::ONE);
+    assert!(engines[0].thrust > Number::ZERO);
+    assert!(fuel(&vessel) < before);
+    assert_eq!(vessel.throttle, Number::ONE);
+
+    // The next uses up the battery, which cuts the engine mid-burn, and it stays off even when
+    // asked to burn again
+    for _ in 0..2 {
+        let before = fuel(&vessel);
+        let engines = vessel.update(&data, &Environment::VACUUM, Number::ONE);
+        assert_eq!(engines[0].thrust, Number::ZERO);
+        assert_eq!(fuel(&vessel), before);
+        assert_eq!(vessel.throttle, Number::ZERO);
+        vessel.throttle = Number::ONE;
+    }
+}
+
+#[test]
+fn warp_averages_sunlight_over_the_orbit() {
+    let data = setup();
+    let lit = sunlit(Vector3::Z, 1.0);
+    let dark = Environment::VACUUM;
+
+    // Half the orbit in the dark makes as much as the core uses
+    let mut vessel = vessel(&data, &["battery", "probe", "fixed-panel"]);
+    electric::warp(
+        &mut vessel,
+        &data,
+        &[lit, dark, lit, dark],
+        number(10_000.0),
+    );
+    assert!(close(charge_held(&vessel, &data), number(2.0)));
+
+    // Mostly dark drains the battery, stopping when it's empty
+    electric::warp(&mut vessel, &data, &[lit, dark, dark, dark], number(2.0));
+    assert!(close(charge_held(&vessel, &data), number(1.0)));
+    electric::warp(&mut vessel, &data, &[dark], number(1_000_000.0));
+    assert_eq!(charge_held(&vessel, &data), Number::ZERO);
+    assert!(!electric::has_control(&vessel, &data));
+
+    // And fully lit fills it, stopping when it's full
+    electric::warp(&mut vessel, &data, &[lit], number(3.0));
+    assert!(close(charge_held(&vessel, &data), number(3.0)));
+    electric::warp(&mut vessel, &data, &[lit], number(1_000_000.0));
+    assert!(close(charge_held(&vessel, &data), number(10.0)));
+}
+
+#[test]
+fn batteries_charge_and_drain_together() {
+    let mut data = setup();
+    let charge = data.resources.id("ElectricCharge").unwrap();
+    common::part(
+        &mut data,
+        json!({
+            "name": "big-battery",
+            "mass": 10,
+            "resources": [{ "name": "ElectricCharge", "capacity": 30, "amount": 10 }],
+        }),
+    );
+    let mut vessel = vessel(&data, &["battery", "big-battery"]);
+    vessel.parts[0].resources[0].amount = Number::ZERO;
+    let amounts = |vessel: &Vessel| {
+        (
+            vessel.parts[0].resources[0].amount,
+            vessel.parts[1].resources[0].amount,
+        )
+    };
+
+    // Filled by the room each has, 10 and 20
+    assert_eq!(flow::fill(&mut vessel, charge, number(15.0)), number(15.0));
+    let (small, big) = amounts(&vessel);
+    assert!(close(small, number(5.0)) && close(big, number(20.0)));
+    // Drained by what each holds
+    assert_eq!(flow::drain(&mut vessel, charge, number(15.0)), number(15.0));
+    let (small, big) = amounts(&vessel);
+    assert!(close(small, number(2.0)) && close(big, number(8.0)));
+    // Never past full or empty
+    assert!(close(
+        flow::fill(&mut vessel, charge, number(100.0)),
+        number(30.0)
+    ));
+    assert_eq!(amounts(&vessel), (number(10.0), number(30.0)));
+    assert!(close(
+        flow::drain(&mut vessel, charge, number(100.0)),
+        number(40.0)
+    ));
+    assert_eq!(amounts(&vessel), (Number::ZERO, Number::ZERO));
+}
@@ -0,0 +1,26 @@
+mod common;
+
+use serde_json::json;
+use space::game_data::GameData;
+
+#[test]
+#[should_panic(expected = "engine needs a positive vacuum isp for its engine")]
+fn engines_need_a_vacuum_isp() {
+    let mut data = GameData::new();
+    common::resource(
+        &mut data,
+        json!({ "name": "LiquidFuel", "title": "", "density": 5 }),
+    );
+    common::part(
+        &mut data,
+        json!({
+            "name": "engine",
+            "mass": 100,
+            "engine": {
+                "max_thrust": 10000,
+                "isp": [[0, 0], [1, 250]],
+                "propellants": [{ "name": "LiquidFuel", "ratio": 1 }],
+            },
+        }),
+    );
+}
@@ -0,0 +1,148 @@
+mod common;
+
+use common::number;
+use serde_json::json;
+use space::{
+    float_curve::{FloatCurve, Interpolation, Keyframe},
+    Number,
+};
+
+fn curve(keys: &[(f64, f64)], interpolation: Interpolation) -> FloatCurve {
+    FloatCurve::new(
+        keys.iter()
+            .map(|&(time, value)| Keyframe::new(number(time), number(value)))
+            .collect(),
+        interpolation,
+    )
+}
+
+fn close(a: Number, b: f64) -> bool {
+    (a - number(b)).abs() < number(1e-6)
+}
+
+#[test]
+fn hermite_curves_round_off_what_linear_ones_join_straight() {
+    let keys = [(0.0, 0.0), (1.0, 1.0), (2.0, 0.0)];
+    let linear = curve(&keys, Interpolation::Linear);
+    let hermite = curve(&keys, Interpolation::Hermite);
+
+    // Both go through every key
+    for (time, value) in keys {
+        assert!(close(linear.evaluate(number(time)), value));
+        assert!(close(hermite.evaluate(number(time)), value));
+    }
+    assert!(close(linear.evaluate(number(0.5)), 0.5));
+    assert!(close(linear.evaluate(number(1.5)), 0.5));
+    // Leaving the first key along the slope to the second, and flat over the peak between
+    // its neighbours
+    assert!(close(hermite.evaluate(number(0.5)), 0.625));
+    assert!(close(hermite.evaluate(number(1.5)), 0.625));
+}
+
+#[test]
+fn explicit_tangents_override_the_automatic_ones() {
+    let key = |time: f64, value: f64, tangent: f64| Keyframe {
+        in_tangent: Some(number(tangent)),
+        out_tangent: Some(number(tangent)),
+        ..Keyframe::new(number(time), number(value))
+    };
+    let automatic = curve(&[(0.0, 0.0), (1.0, 1.0)], Interpolation::Hermite);
+    let flat = FloatCurve::new(
+        vec![key(0.0, 0.0, 0.0), key(1.0, 1.0, 0.0)],
+        Interpolation::Hermite,
+    );
+
+    // Two keys on their own make a straight line, flat tangents an S
+    assert!(close(automatic.evaluate(number(0.25)), 0.25));
+    assert!(close(flat.evaluate(number(0.25)), 0.15625));
+    assert!(close(flat.evaluate(number(0.5)), 0.5));
+    assert!(close(flat.evaluate(number(0.75)), 0.84375));
+
+    // Only the tangent leaving the start and the one arriving at the end count
+    let steep_out = FloatCurve::new(
+        vec![
+            Keyframe {
+                out_tangent: Some(number(3.0)),
+                ..key(0.0, 0.0, 0.0)
+            },
+            key(1.0, 1.0, 0.0),
+        ],
+        Interpolation::Hermite,
+    );
+    assert!(close(steep_out.evaluate(number(0.5)), 0.875));
+}
+
+#[test]
+fn holds_the_end_values_outside_the_keys() {
+    for interpolation in [Interpolation::Linear, Interpolation::Hermite] {
+        let curve = curve(&[(1.0, 320.0), (2.0, 280.0), (3.0, 100.0)], interpolation);
+        assert_eq!(curve.evaluate(number(-5.0)), number(320.0));
+        assert_eq!(curve.evaluate(number(1.0)), number(320.0));
+        assert_eq!(curve.evaluate(number(3.0)), number(100.0));
+        assert_eq!(curve.evaluate(number(1e6)), number(100.0));
+    }
+}
+
+#[test]
+fn single_keys_are_constant() {
+    for curve in [
+        FloatCurve::constant(number(7.0)),
+        curve(&[(5.0, 7.0)], Interpolation::Hermite),
+        serde_json::from_value(json!([[5, 7]])).unwrap(),
+    ] {
+        for time in [-1.0, 0.0, 5.0, 100.0] {
+            assert_eq!(curve.evaluate(number(time)), number(7.0));
+        }
+    }
+}
+
+#[test]
+fn keys_are_sorted_by_time() {
+    let shuffled = curve(&[(2.0, 280.0), (0.0, 320.0)], Interpolation::Linear);
+    assert_eq!(shuffled.keys()[0].time, Number::ZERO);
+    assert!(close(shuffled.evaluate(number(1.0)), 300.0));
+}
+
+#[test]
+fn reads_both_json_forms() {
+    let list: FloatCurve = serde_json::from_value(json!([[0, 320], [1, 280]])).unwrap();
+    assert_eq!(list.interpolation(), Interpolation::Hermite);
+    assert_eq!(
+        list,
+        curve(&[(0.0, 320.0), (1.0, 280.0)], Interpolation::Hermite)
+    );
+
+    let object: FloatCurve = serde_json::from_value(json!({
+        "interpolation": "linear",
+        "keys": [[0, 320], [1, 280]],
+    }))
+    .unwrap();
+    assert_eq!(object.interpolation(), Interpolation::Linear);
+    assert!(close(object.evaluate(number(0.5)), 300.0));
+    // Hermite unless it says otherwise
+    let object: FloatCurve = serde_json::from_value(json!({ "keys": [[0, 320]] })).unwrap();
+    assert_eq!(object.interpolation(), Interpolation::Hermite);
+
+    // Tangents come after the value
+    let tangents: FloatCurve =
+        serde_json::from_value(json!([[0, 0, 0, 2], [1, 1, -1, 0]])).unwrap();
+    let keys = tangents.keys();
+    assert_eq!(keys[0].out_tangent, Some(number(2.0)));
+    assert_eq!(keys[1].in_tangent, Some(number(-1.0)));
+
+    for invalid in [json!([]), json!([[0, 1, 2]]), json!({ "keys": [] })] {
+        assert!(serde_json::from_value::<FloatCurve>(invalid).is_err());
+    }
+}
+
+#[test]
+fn writes_the_form_it_reads() {
+    for value in [
+        json!([[0.0, 320.0], [1.0, 280.0]]),
+        json!([[0.0, 0.0, 0.0, 2.0], [1.0, 1.0, -1.0, 0.0]]),
+        json!({ "interpolation": "linear", "keys": [[0.0, 320.0], [1.0, 280.0]] }),
+    ] {
+        let curve: FloatCurve = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&curve).unwrap(), value);
+    }
+}
@@ -0,0 +1,179 @@
+mod common;
+
+use serde_json::json;
+use space::{
+    flow::{self, Consumer},
+    game_data::GameData,
+    motor::Transform,
+    resource::ResourceId,
+    vessel::Vessel,
+    Number,
+};
+
+struct Setup {
+    data: GameData,
+    fuel: ResourceId,
+    charge: ResourceId,
+}
+
+fn setup() -> Setup {
+    let mut data = GameData::new();
+    let fuel = common::resource(
+        &mut data,
+        json!({ "name": "LiquidFuel", "title": "", "density": 5 }),
+    );
+    let charge = common::electric_charge(&mut data);
+    for (name, capacity) in [("small-tank", 40), ("big-tank", 100)] {
+        common::part(
+            &mut data,
+            json!({
+                "name": name,
+                "mass": 10,
+                "resources": [{ "name": "LiquidFuel", "capacity": capacity }],
+            }),
+        );
+    }
+    common::part(&mut data, json!({ "name": "engine", "mass": 10 }));
+    common::part(
+        &mut data,
+        json!({ "name": "decoupler", "mass": 10, "crossfeed": false }),
+    );
+    common::part(
+        &mut data,
+        json!({
+            "name": "battery",
+            "mass": 10,
+            "resources": [{ "name": "ElectricCharge", "capacity": 10 }],
+        }),
+    );
+    Setup { data, fuel, charge }
+}
+
+fn attach(vessel: &mut Vessel, parent: usize, part: &str, data: &GameData) -> usize {
+    vessel.attach(
+        parent,
+        data.parts.id(part).unwrap(),
+        Transform::IDENTITY,
+        data,
+    )
+}
+
+fn consumer(part: usize, resource: ResourceId, demand: i32) -> Consumer {
+    Consumer {
+        part,
+        resource,
+        demand: Number::from_num(demand),
+    }
+}
+
+#[test]
+fn stack_drains_farthest_tank_first() {
+    let Setup { data, fuel, .. } = setup();
+    let mut vessel = Vessel::new(
+        data.parts.id("big-tank").unwrap(),
+        Transform::IDENTITY,
+        &data,
+    );
+    let lower_tank = attach(&mut vessel, 0, "small-tank", &data);
+    let engine = attach(&mut vessel, lower_tank, "engine", &data);
+
+    let consumers = [consumer(engine, fuel, 30)];
+    let solution = flow::solve(&vessel, &data, &consumers);
+    assert_eq!(solution.supplied[0], Number::from_num(30));
+    assert_eq!(solution.drawn(0, fuel), Number::from_num(30));
+    assert_eq!(solution.drawn(lower_tank, fuel), Number::ZERO);
+
+    let consumers = [consumer(engine, fuel, 120)];
+    let solution = flow::solve(&vessel, &data, &consumers);
+    assert_eq!(solution.drawn(0, fuel), Number::from_num(100));
+    assert_eq!(solution.drawn(lower_tank, fuel), Number::from_num(20));
+}
+
+#[test]
+fn asymmetric_stacks_drain_independently() {
+    let Setup { data, fuel, .. } = setup();
+    // Two engines hang off a central big tank, but one of them sits below a decoupler that
+    // blocks crossfeed and can only use the small tank on its own side
+    let mut vessel = Vessel::new(
+        data.parts.id("big-tank").unwrap(),
+        Transform::IDENTITY,
+        &data,
+    );
+    let central_engine = attach(&mut vessel, 0, "engine", &data);
+    let decoupler = attach(&mut vessel, 0, "decoupler", &data);
+    let side_tank = attach(&mut vessel, decoupler, "small-tank", &data);
+    let side_engine = attach(&mut vessel, side_tank, "engine", &data);
+
+    let consumers = [
+        consumer(central_engine, fuel, 10),
+        consumer(side_engine, fuel, 10),
+    ];
+    for _ in 0..4 {
+        flow::solve(&vessel, &data, &consumers).apply(&mut vessel);
+    }
+
+    assert_eq!(vessel.parts[0].resources[0].amount, Number::from_num(60));
+    assert_eq!(vessel.parts[side_tank].resources[0].amount, Number::ZERO);
+
+    // The side stack is now dry and the central tank must not feed it through the decoupler
+    let solution = flow::solve(&vessel, &data, &consumers);
+    assert_eq!(solution.supplied[0], Number::from_num(10));
+    assert_eq!(solution.supplied[1], Number::ZERO);
+    assert!(!solution.is_starved(0, &consumers));
+    assert!(solution.is_starved(1, &consumers));
+}
+
+#[test]
+fn parallel_tanks_drain_in_proportion() {
+    let Setup { data, fuel, .. } = setup();
+    let mut vessel = Vessel::new(data.parts.id("engine").unwrap(), Transform::IDENTITY, &data);
+    let small_tank = attach(&mut vessel, 0, "small-tank", &data);
+    let big_tank = attach(&mut vessel, 0, "big-tank", &data);
+
+    let consumers = [consumer(0, fuel, 70)];
+    let solution = flow::solve(&vessel, &data, &consumers);
+    assert_eq!(solution.supplied[0], Number::from_num(70));
+    assert_eq!(solution.drawn(small_tank, fuel), Number::from_num(20));
+    assert_eq!(solution.drawn(big_tank, fuel), Number::from_num(50));
+}
+
+#[test]
+fn starved_engine_gets_what_is_left() {
+    let Setup { data, fuel, .. } = setup();
+    let mut vessel = Vessel::new(
+        data.parts.id("small-tank").unwrap(),
+        Transform::IDENTITY,
+        &data,
+    );
+    let first_engine = attach(&mut vessel, 0, "engine", &data);
+    let second_engine = attach(&mut vessel, 0, "engine", &data);
+
+    let consumers = [
+        consumer(first_engine, fuel, 25),
+        consumer(second_engine, fuel, 25),
+    ];
+    let solution = flow::solve(&vessel, &data, &consumers);
+    assert_eq!(solution.supplied[0], Number::from_num(25));
+    assert_eq!(solution.supplied[1], Number::from_num(15));
+    assert!(solution.is_starved(1, &consumers));
+
+    solution.apply(&mut vessel);
+    let solution = flow::solve(&vessel, &data, &consumers);
+    assert_eq!(solution.supplied, [Number::ZERO, Number::ZERO]);
+    assert!(solution.draws.is_empty());
+}
+
+#[test]
+fn flow_modes_respect_crossfeed_only_where_they_should() {
+    let Setup { data, fuel, charge } = setup();
+    let mut vessel = Vessel::new(data.parts.id("engine").unwrap(), Transform::IDENTITY, &data);
+    let decoupler = attach(&mut vessel, 0, "decoupler", &data);
+    let battery = attach(&mut vessel, decoupler, "battery", &data);
+    attach(&mut vessel, decoupler, "small-tank", &data);
+
+    // Electric charge flows through the whole vessel, fuel stops at the decoupler
+    let consumers = [consumer(0, charge, 4), consumer(0, fuel, 4)];
+    let solution = flow::solve(&vessel, &data, &consumers);
+    assert_eq!(solution.drawn(battery, charge), Number::from_num(4));
+    assert_eq!(solution.supplied[1], Number::ZERO);
+}
@@ -0,0 +1,184 @@
+mod common;
+
+use common::{close, number};
+use space::{
+    body::Body,
+    geodetic::{self, Ellipsoid, Geodetic},
+    motor::Transform,
+    vector3::Vector3,
+    Number,
+};
+use std::f64::consts::{FRAC_PI_2, PI};
+
+fn degrees(latitude: f64, longitude: f64, altitude: f64) -> Geodetic {
+    Geodetic::new(
+        number(latitude.to_radians()),
+        number(longitude.to_radians()),
+        number(altitude),
+    )
+}
+
+fn assert_round_trips(ellipsoid: Ellipsoid, geodetic: Geodetic) {
+    let back = ellipsoid.to_geodetic(ellipsoid.to_cartesian(geodetic));
+    assert!(
+        (back.latitude - geodetic.latitude).abs() < number(1e-8)
+            && (back.altitude - geodetic.altitude).abs() < number(0.001),
+        "{geodetic:?} came back as {back:?}"
+    );
+    // Longitude is meaningless at the poles
+    if geodetic.latitude.abs() < number(1.5) {
+        assert!((back.longitude - geodetic.longitude).abs() < number(1e-8));
+    }
+}
+
+#[test]
+fn spheres_round_trip_through_cartesian_coordinates() {
+    let sphere = Ellipsoid::sphere(number(600_000.0));
+
+    let equator = sphere.to_cartesian(degrees(0.0, 90.0, 1000.0));
+    assert!(close(equator, Vector3::Y * number(601_000.0), 0.001));
+    let pole = sphere.to_cartesian(degrees(-90.0, 0.0, 0.0));
+    assert!(close(pole, Vector3::Z * number(-600_000.0), 0.001));
+
+    for geodetic in [
+        degrees(0.0, 0.0, 0.0),
+        degrees(45.0, -120.0, 70_000.0),
+        degrees(-10.0, 170.0, -500.0),
+        degrees(90.0, 0.0, 1.0e6),
+    ] {
+        assert_round_trips(sphere, geodetic);
+    }
+}
+
+#[test]
+fn oblate_bodies_are_shorter_through_the_poles() {
+    let ellipsoid = Ellipsoid {
+        equatorial_radius: number(600_000.0),
+        flattening: number(0.1),
+    };
+    assert!((ellipsoid.polar_radius() - number(540_000.0)).abs() < number(0.001));
+
+    let pole = ellipsoid.to_cartesian(degrees(90.0, 0.0, 100.0));
+    assert!(close(pole, Vector3::Z * number(540_100.0), 0.001));
+    let equator = ellipsoid.to_cartesian(degrees(0.0, 0.0, 100.0));
+    assert!(close(equator, Vector3::X * number(600_100.0), 0.001));
+
+    // Altitude is along the normal, which doesn't point at the center away from the equator
+    let geodetic = degrees(45.0, 30.0, 5000.0);
+    let above = ellipsoid.to_cartesian(geodetic);
+    let below = ellipsoid.to_cartesian(Geodetic {
+        altitude: Number::ZERO,
+        ..geodetic
+    });
+    let normal = ellipsoid.up(geodetic.latitude, geodetic.longitude);
+    assert!(close(above - below, normal * number(5000.0), 0.001));
+    assert!((above.length() - below.length() - number(5000.0)).abs() > number(0.01));
+
+    for geodetic in [
+        degrees(0.0, 10.0, 0.0),
+        degrees(45.0, 30.0, 5000.0),
+        degrees(-80.0, -60.0, 200_000.0),
+        degrees(89.99, 0.0, 10.0),
+        degrees(-90.0, 0.0, 0.0),
+    ] {
+        assert_round_trips(ellipsoid, geodetic);
+    }
+}
+
+#[test]
+fn great_circles_and_bearings() {
+    let radius = number(600_000.0);
+    let origin = degrees(0.0, 0.0, 0.0);
+
+    // A quarter of the way around the equator, or up to the pole
+    let quarter = number(FRAC_PI_2 * 600_000.0);
+    let east = degrees(0.0, 90.0, 0.0);
+    let pole = degrees(90.0, 0.0, 0.0);
+    assert!((geodetic::great_circle_distance(radius, origin, east) - quarter).abs() < number(0.01));
+    assert!((geodetic::great_circle_distance(radius, origin, pole) - quarter).abs() < number(0.01));
+    assert!((geodetic::bearing(origin, east) - number(FRAC_PI_2)).abs() < number(1e-8));
+    assert!(geodetic::bearing(origin, pole).abs() < number(1e-8));
+    assert!((geodetic::bearing(origin, degrees(-1.0, 0.0, 0.0)) - number(PI)).abs() < number(1e-8));
+    assert!((geodetic::bearing(east, origin) - number(1.5 * PI)).abs() < number(1e-8));
+
+    // Close points stay precise, and altitude doesn't count
+    let near = degrees(0.001, 0.0, 5000.0);
+    let expected = 0.001f64.to_radians() * 600_000.0;
+    assert!(
+        (geodetic::great_circle_distance(radius, origin, near) - number(expected)).abs()
+            < number(0.001)
+    );
+
+    // Going from the equator towards somewhere north east sets off north east
+    let bearing = geodetic::bearing(origin, degrees(10.0, 10.0, 0.0));
+    assert!(bearing > number(0.7) && bearing < number(0.8), "{bearing}");
+}
+
+#[test]
+fn north_east_up_frames() {
+    let ellipsoid = Ellipsoid {
+        equatorial_radius: number(600_000.0),
+        flattening: number(0.05),
+    };
+    let geodetic = degrees(30.0, 60.0, 250.0);
+    let frame = ellipsoid.north_east_up(geodetic);
+
+    assert!(close(
+        frame.position(),
+        ellipsoid.to_cartesian(geodetic),
+        0.01
+    ));
+    let up = ellipsoid.up(geodetic.latitude, geodetic.longitude);
+    assert!(close(frame.transform_direction(Vector3::Y), up, 1e-6));
+    // North and east are where nudging the latitude or longitude goes
+    let direction = |offset: Geodetic| {
+        let offset = ellipsoid.to_cartesian(offset) - ellipsoid.to_cartesian(geodetic);
+        offset / offset.length()
+    };
+    let north = direction(Geodetic {
+        latitude: geodetic.latitude + number(1e-4),
+        ..geodetic
+    });
+    let east = direction(Geodetic {
+        longitude: geodetic.longitude + number(1e-4),
+        ..geodetic
+    });
+    assert!(close(frame.transform_direction(Vector3::X), north, 1e-3));
+    assert!(close(frame.transform_direction(Vector3::Z), east, 1e-3));
+
+    // Facing east turns the forward +X axis onto east
+    let facing_east = geodetic::heading(number(FRAC_PI_2)).apply(frame);
+    assert!(close(
+        facing_east.transform_direction(Vector3::X),
+        east,
+        1e-3
+    ));
+    assert!(close(facing_east.transform_direction(Vector3::Y), up, 1e-6));
+}
+
+#[test]
+fn bodies_convert_world_space_positions() {
+    let body = Body {
+        position: Vector3::X * number(1e7),
+        rotation: Transform::rotation(Vector3::Z, number(1.0))
+            .apply(Transform::rotation(Vector3::X, number(0.4))),
+        flattening: number(0.02),
+        ..Body::new(number(600_000.0), number(3.5e12))
+    };
+    let geodetic = degrees(-20.0, 135.0, 12_000.0);
+
+    let position = body.from_geodetic(geodetic);
+    let back = body.to_geodetic(position);
+    assert!((back.latitude - geodetic.latitude).abs() < number(1e-8));
+    assert!((back.longitude - geodetic.longitude).abs() < number(1e-8));
+    assert!((body.altitude(position) - number(12_000.0)).abs() < number(0.01));
+    // Transforms lose a little precision out at 10,000 km
+    let frame = body.north_east_up(geodetic);
+    assert!(close(frame.position(), position, 0.1));
+    assert!(close(
+        frame.transform_direction(Vector3::Y),
+        body.rotation
+            .transform_direction(body.ellipsoid().up(geodetic.latitude, geodetic.longitude)),
+        1e-6
+    ));
+}
@@ -0,0 +1,213 @@
+mod common;
+
+use common::number;
+use serde_json::json;
+use space::{
+    collision,
+    contact::{self, Ground},
+    engine::EngineOutput,
+    game_data::GameData,
+    joint,
+    motor::Transform,
+    physics::Environment,
+    vector3::Vector3,
+    vessel::Vessel,
+};
+
+const DT: f64 = 0.02;
+
+/// Three cubes from [`common::stack`], flexing on their joints
+fn flexible_stack(data: &mut GameData) -> Vessel {
+    let mut vessel = common::stack(data, 3);
+    vessel.set_flexible(true, data);
+    vessel
+}
+
+/// Newtons pushing the bottom cube upwards
+fn thrust(newtons: f64) -> EngineOutput {
+    EngineOutput {
+        part: 2,
+        thrust: number(newtons),
+        direction: Vector3::Y,
+        flameout: false,
+    }
+}
+
+/// Flexes the vessel for `seconds`, returning every piece that broke off
+fn run(
+    vessel: &mut Vessel,
+    data: &GameData,
+    engines: &[EngineOutput],
+    seconds: f64,
+) -> Vec<Vessel> {
+    let mut pieces = Vec::new();
+    for _ in 0..(seconds / DT) as usize {
+        // Once something breaks off the engine is no longer where it was
+        let engines = if pieces.is_empty() { engines } else { &[] };
+        pieces.extend(joint::flex(
+            vessel,
+            data,
+            engines,
+            &Environment::VACUUM,
+            number(DT),
+        ));
+    }
+    pieces
+}
+
+/// How far the joint holding `child` onto its parent is stretched along Y
+fn stretch(vessel: &Vessel, child: usize) -> f64 {
+    let parent = vessel.parts[child].parent.unwrap();
+    let offset = |index: usize| vessel.parts[index].flex.unwrap().offset.y.to_num::<f64>();
+    offset(child) - offset(parent)
+}
+
+#[test]
+fn rigid_vessels_do_not_flex() {
+    let mut data = GameData::new();
+    let mut vessel = flexible_stack(&mut data);
+    vessel.set_flexible(false, &data);
+
+    let pieces = run(&mut vessel, &data, &[thrust(1e7)], 1.0);
+
+    assert!(pieces.is_empty());
+    assert_eq!(vessel.parts.len(), 3);
+    assert!(vessel.parts.iter().all(|part| part.flex.is_none()));
+}
+
+#[test]
+fn unloaded_joints_stay_put() {
+    let mut data = GameData::new();
+    let mut vessel = flexible_stack(&mut data);
+
+    let pieces = run(&mut vessel, &data, &[], 1.0);
+
+    assert!(pieces.is_empty());
+    for part in &vessel.parts {
+        let flex = part.flex.unwrap();
+        assert_eq!(flex.offset, Vector3::ZERO);
+        assert_eq!(flex.rotation, Vector3::ZERO);
+    }
+}
+
+#[test]
+fn thrust_squashes_the_joints() {
+    let mut data = GameData::new();
+    let mut vessel = flexible_stack(&mut data);
+
+    let pieces = run(&mut vessel, &data, &[thrust(30_000.0)], 3.0);
+
+    // Each joint pushes along everything above it, at the 10 m/s² the thrust gives the stack
+    assert!(pieces.is_empty());
+    let stiffness = 1e6;
+    assert!((stretch(&vessel, 2) - 20_000.0 / stiffness).abs() < 0.001);
+    assert!((stretch(&vessel, 1) - 10_000.0 / stiffness).abs() < 0.001);
+    // The vessel as a whole doesn't move from flexing
+    let position = vessel.transform.position();
+    assert!(position.length() < number(0.001), "{position:?}");
+}
+
+#[test]
+fn overloaded_joint_breaks() {
+    let mut data = GameData::new();
+    let mut vessel = flexible_stack(&mut data);
+
+    let pieces = run(&mut vessel, &data, &[thrust(1e6)], 0.1);
+
+    // The bottom joint pushes two thirds of the thrust and breaks, the top one only a third
+    assert_eq!(pieces.len(), 1);
+    assert_eq!(vessel.parts.len(), 2);
+    assert_eq!(pieces[0].parts.len(), 1);
+    assert!(pieces[0].velocity.y > vessel.velocity.y);
+}
+
+#[test]
+fn spinning_too_fast_tears_the_vessel_apart() {
+    let mut data = GameData::new();
+    let cube = common::part_with_model(
+        &mut data,
+        json!({ "name": "cube", "mass": 1000.0 }),
+        common::cube_model(),
+    );
+    let mut vessel = Vessel::new(cube, Transform::IDENTITY, &data);
+    vessel.attach(
+        0,
+        cube,
+        Transform::translation(Vector3::Y * number(-2.0)),
+        &data,
+    );
+    vessel.set_flexible(true, &data);
+    // Pulls each cube outwards with 900 kN, well over the 500 kN the joint holds
+    vessel.angular_velocity = Vector3::Z * number(30.0);
+
+    let pieces = run(&mut vessel, &data, &[], 0.1);
+
+    assert_eq!(pieces.len(), 1);
+    assert_eq!(vessel.parts.len(), 1);
+    // Both fly off at their own velocity, at least as fast as they were going around
+    let relative = pieces[0].velocity - vessel.velocity;
+    assert!(relative.length() > number(55.0), "{relative:?}");
+}
+
+#[test]
+fn only_moving_through_the_air_drags_on_the_joints() {
+    let mut data = GameData::new();
+    let cube = common::part_with_model(
+        &mut data,
+        json!({ "name": "cube", "mass": 1000.0 }),
+        common::cube_model(),
+    );
+    let draggy = common::part_with_model(
+        &mut data,
+        json!({ "name": "draggy-cube", "mass": 1000.0, "drag": 10.0 }),
+        common::cube_model(),
+    );
+    let flown = |air_velocity: Vector3| {
+        let mut vessel = Vessel::new(cube, Transform::IDENTITY, &data);
+        vessel.attach(
+            0,
+            draggy,
+            Transform::translation(Vector3::Y * number(-2.0)),
+            &data,
+        );
+        vessel.set_flexible(true, &data);
+        vessel.velocity = Vector3::Y * number(300.0);
+        let environment = Environment {
+            density: number(1.2),
+            air_velocity,
+            ..Environment::VACUUM
+        };
+        for _ in 0..50 {
+            joint::flex(&mut vessel, &data, &[], &environment, number(DT));
+        }
+        stretch(&vessel, 1)
+    };
+
+    // Held back by the air, the bottom cube pulls on the joint
+    assert!(flown(Vector3::ZERO) < -0.01);
+    // Air moving along at the same speed, like around a turning planet, holds nothing back
+    assert_eq!(flown(Vector3::Y * number(300.0)), 0.0);
+}
+
+#[test]
+fn parts_collide_where_they_have_flexed_to() {
+    let mut data = GameData::new();
+    let mut vessel = flexible_stack(&mut data);
+    // The bottom cube reaches down to -5 until it sags half a meter on its joint
+    let floor = Ground::Plane {
+        point: Vector3::Y * number(-5.25),
+        normal: Vector3::Y,
+    };
+    assert!(contact::ground_contacts(&vessel, &data, &floor).is_empty());
+    let rigid = collision::vessel_bounds(&vessel, &data).unwrap();
+    vessel.parts[2].flex.as_mut().unwrap().offset = Vector3::Y * number(-0.5);
+
+    let bounds = collision::vessel_bounds(&vessel, &data).unwrap();
+    assert!(
+        (bounds.min.y - (rigid.min.y - number(0.5))).abs() < number(1e-6),
+        "{bounds:?}"
+    );
+    let contacts = contact::ground_contacts(&vessel, &data, &floor);
+    assert!(!contacts.is_empty());
+    assert!(contacts.iter().all(|&(part, _)| part == 2));
+}
@@ -0,0 +1,206 @@
+mod common;
+
+use serde_json::json;
+use space::{
+    body::Body,
+    game_data::GameData,
+    landing::{self, LandingAutopilot, LandingPhase, LandingSettings},
+    motor::Transform,
+    staging,
+    terrain::{Heightmap, Terrain},
+    vector3::Vector3,
+    vessel::Vessel,
+    Number,
+};
+use std::sync::Arc;
+
+const DT: f64 = 0.1;
+
+/// Meters from the lander's origin down to the bottom of its engine
+const HEIGHT: f64 = 5.0;
+
+/// An airless moon with 1.6 m/s² of gravity at the surface
+fn moon() -> Body {
+    Body::new(Number::from_num(200_000), Number::from_num(6.4e10))
+}
+
+fn setup() -> GameData {
+    let mut data = GameData::new();
+    common::resource(
+        &mut data,
+        json!({ "name": "LiquidFuel", "title": "", "density": 5 }),
+    );
+    common::part(
+        &mut data,
+        json!({
+            "name": "pod",
+            "mass": 500,
+            "inertia": [300, 200, 300],
+            "reaction_wheel": { "torque": [2000, 2000, 2000] },
+        }),
+    );
+    common::part(
+        &mut data,
+        json!({
+            "name": "tank",
+            "mass": 100,
+            "inertia": [400, 200, 400],
+            "resources": [{ "name": "LiquidFuel", "capacity": 200 }],
+        }),
+    );
+    common::part(
+        &mut data,
+        json!({
+            "name": "engine",
+            "mass": 100,
+            "inertia": [50, 50, 50],
+            "engine": {
+                "max_thrust": 15_000,
+                "isp": [[0, 300]],
+                "propellants": [{ "name": "LiquidFuel", "ratio": 1 }],
+            },
+        }),
+    );
+    data
+}
+
+/// Lander with its engine running, standing upright `altitude` meters above the ground over
+/// the point where the equator crosses +X
+fn lander(data: &GameData, body: &Body, altitude: f64) -> Vessel {
+    let transform = Transform::rotation(Vector3::Z, -Number::FRAC_PI_2).apply(
+        Transform::translation(Vector3::X * (body.radius + Number::from_num(altitude + HEIGHT))),
+    );
+    let mut vessel = Vessel::new(data.parts.id("pod").unwrap(), transform, data);
+    let tank = vessel.attach(
+        0,
+        data.parts.id("tank").unwrap(),
+        Transform::translation(Vector3::Y * Number::from_num(-2)),
+        data,
+    );
+    vessel.attach(
+        tank,
+        data.parts.id("engine").unwrap(),
+        Transform::translation(Vector3::Y * Number::from_num(-2)),
+        data,
+    );
+    staging::assign_stages(&mut vessel, data);
+    staging::activate_next_stage(&mut vessel, data);
+    vessel
+}
+
+/// Flies the landing autopilot until touchdown
+fn land(data: &GameData, body: &Body, vessel: &mut Vessel, settings: LandingSettings) {
+    let mut autopilot = LandingAutopilot::new(settings);
+    let dt = Number::from_num(DT);
+    for _ in 0..(600.0 / DT) as usize {
+        autopilot.update(vessel, data, body, dt);
+        if autopilot.phase() == LandingPhase::Landed {
+            return;
+        }
+        let environment = body.environment(vessel.transform.position());
+        vessel.update(data, &environment, dt);
+    }
+    panic!("still {:?} after 600 seconds", autopilot.phase());
+}
+
+fn touchdown_speed(vessel: &Vessel, body: &Body) -> f64 {
+    (vessel.velocity - body.velocity).length().to_num()
+}
+
+#[test]
+fn suicide_burn_altitude_accounts_for_gravity() {
+    let altitude = landing::suicide_burn_altitude(
+        Number::from_num(100),
+        Number::from_num(3000),
+        Number::from_num(1000),
+        Number::ONE,
+    );
+    assert_eq!(altitude, Some(Number::from_num(2500)));
+    assert_eq!(
+        landing::suicide_burn_altitude(
+            Number::from_num(100),
+            Number::from_num(1000),
+            Number::from_num(1000),
+            Number::ONE,
+        ),
+        None
+    );
+}
+
+#[test]
+fn lands_softly_from_a_fall() {
+    let data = setup();
+    let body = moon();
+    let mut vessel = lander(&data, &body, 5000.0);
+    vessel.velocity = Vector3::new(Number::from_num(-20), Number::from_num(80), Number::ZERO);
+    let start_mass = vessel.mass(&data).to_num::<f64>();
+
+    land(
+        &data,
+        &body,
+        &mut vessel,
+        LandingSettings::new(Number::from_num(HEIGHT)),
+    );
+    let speed = touchdown_speed(&vessel, &body);
+    assert!(speed < 2.0, "touched down at {speed} m/s");
+
+    // Killing about 190 m/s takes little more than that when braking late, hovering down would
+    // take far more
+    let delta_v = 300.0 * 9.80665 * (start_mass / vessel.mass(&data).to_num::<f64>()).ln();
+    assert!(delta_v < 250.0, "used {delta_v} m/s");
+}
+
+#[test]
+fn hovers_over_to_a_target() {
+    let data = setup();
+    let body = moon();
+    let mut vessel = lander(&data, &body, 100.0);
+    // About 200 meters north and 500 meters east
+    let latitude = Number::from_num(0.001);
+    let longitude = Number::from_num(0.0025);
+
+    land(
+        &data,
+        &body,
+        &mut vessel,
+        LandingSettings::new(Number::from_num(HEIGHT)).with_target(latitude, longitude),
+    );
+    let speed = touchdown_speed(&vessel, &body);
+    assert!(speed < 2.0, "touched down at {speed} m/s");
+
+    let target = body.surface_position(latitude, longitude, Number::ZERO);
+    let position = vessel.transform.position();
+    let up = position / position.length();
+    let offset = target - position;
+    let miss = (offset - up * offset.dot(up)).length().to_num::<f64>();
+    assert!(miss < 5.0, "landed {miss} m from the target");
+}
+
+#[test]
+fn lands_on_a_plateau() {
+    let data = setup();
+    // Flat ground 2 km above sea level everywhere
+    let body = Body {
+        terrain: Some(Arc::new(Terrain::Heightmap(Heightmap::new(
+            1,
+            1,
+            vec![Number::from_num(2000)],
+        )))),
+        ..moon()
+    };
+    let mut vessel = lander(&data, &body, 5000.0);
+
+    land(
+        &data,
+        &body,
+        &mut vessel,
+        LandingSettings::new(Number::from_num(HEIGHT)),
+    );
+    let speed = touchdown_speed(&vessel, &body);
+    assert!(speed < 2.0, "touched down at {speed} m/s");
+    let height = body.height_above_ground(vessel.transform.position());
+    assert!(
+        (height - Number::from_num(HEIGHT)).abs() < Number::ONE,
+        "stopped {height} m above the plateau"
+    );
+}
@@ -0,0 +1,245 @@
+mod common;
+
+use common::{close, game_data_dir, number};
+use serde_json::json;
+use space::{
+    body::{self, Body, BodyId},
+    contact,
+    game_data::GameData,
+    motor::Transform,
+    vector3::Vector3,
+    vessel::Vessel,
+    Number,
+};
+use std::path::Path;
+
+const DT: f64 = 0.02;
+
+/// A tilted planet turning fast enough for its surface to move at 200 m/s, with a launch site
+/// `altitude` meters up facing east, going around a star that pulls it along at 10 mm/s² when
+/// `star` is set
+fn planet_with_site(name: &str, altitude: f64, star: bool) -> GameData {
+    let site = json!({
+        "type": "launch_site",
+        "name": "pad",
+        "title": "Pad",
+        "body": "planet",
+        "latitude": 20,
+        "longitude": -60,
+        "heading": 90,
+        "altitude": altitude,
+    });
+    let mut planet = json!({
+        "type": "body",
+        "name": "planet",
+        "title": "Planet",
+        "radius": 100000,
+        "mu": 9.81e10,
+        "rotation_period": 3141.59,
+        "axial_tilt": 30,
+    });
+    let mut files = vec![("pad.json", site.to_string())];
+    if star {
+        planet["parent"] = json!("star");
+        planet["orbit"] = json!({ "semi_major_axis": 1e10, "mean_anomaly": 45 });
+        let star = json!({
+            "type": "body",
+            "name": "star",
+            "title": "Star",
+            "radius": 2e8,
+            "mu": 1e18,
+        });
+        files.push(("star.json", star.to_string()));
+    }
+    files.push(("planet.json", planet.to_string()));
+    let files = files
+        .iter()
+        .map(|(path, contents)| (*path, contents.as_str()))
+        .collect::<Vec<_>>();
+    GameData::load(&game_data_dir(name, &files))
+}
+
+/// Every body `time` seconds after the epoch, around `origin` sitting still at the origin
+fn bodies_around(data: &GameData, origin: BodyId, time: Number) -> Vec<Body> {
+    let mut bodies = body::propagate(&data.bodies, time);
+    body::center_on(&mut bodies, origin);
+    bodies
+}
+
+#[test]
+fn loads_the_bundled_launch_pad() {
+    let data = GameData::load(Path::new("GameData"));
+    let site = &data.launch_sites[data.launch_sites.id("launch-pad").unwrap()];
+    assert_eq!(site.body, data.bodies.id("kerbin").unwrap());
+    assert!((site.heading - Number::FRAC_PI_2).abs() < number(1e-9));
+}
+
+#[test]
+#[should_panic(expected = "unknown body")]
+fn launch_sites_must_be_on_a_known_body() {
+    let directory = game_data_dir(
+        "unknown-body-site",
+        &[(
+            "pad.json",
+            r#"{ "type": "launch_site", "name": "pad", "title": "Pad", "body": "nowhere",
+                 "latitude": 0, "longitude": 0 }"#,
+        )],
+    );
+    GameData::load(&directory);
+}
+
+#[test]
+fn spawns_standing_on_the_ground_turning_with_it() {
+    let mut data = planet_with_site("spawn-site", 2.0, false);
+    let mut vessel = common::stack(&mut data, 2);
+    let site = &data.launch_sites[data.launch_sites.id("pad").unwrap()];
+    let body = &body::propagate(&data.bodies, number(1000.0))[site.body.index()];
+
+    site.spawn(&mut vessel, body, &data);
+
+    // The bottom of the lower cube is 3 meters below the root, and sits 2 meters up
+    let root = body.to_geodetic(vessel.transform.position());
+    assert!((root.latitude - site.position.latitude).abs() < number(1e-6));
+    assert!((root.longitude - site.position.longitude).abs() < number(1e-6));
+    assert!((root.altitude - number(5.0)).abs() < number(0.01));
+    let up = body.north_east_up(site.position);
+    assert!(close(
+        vessel.transform.transform_direction(Vector3::Y),
+        up.transform_direction(Vector3::Y),
+        1e-6
+    ));
+    // Facing east
+    assert!(close(
+        vessel.transform.transform_direction(Vector3::X),
+        up.transform_direction(Vector3::Z),
+        1e-6
+    ));
+
+    let center = vessel
+        .transform
+        .transform_point(vessel.mass_properties(&data).center_of_mass);
+    assert!(close(
+        body.surface_velocity(center, vessel.velocity),
+        Vector3::ZERO,
+        0.001
+    ));
+    assert!(vessel.velocity.length() > number(150.0));
+    assert_eq!(vessel.angular_velocity, body.angular_velocity);
+}
+
+#[test]
+fn stays_put_on_the_ground() {
+    let mut data = planet_with_site("stay-put-site", 0.0, false);
+    let mut vessels = vec![common::stack(&mut data, 2)];
+    let site = &data.launch_sites[data.launch_sites.id("pad").unwrap()];
+    let start = number(1000.0);
+    let bodies = body::propagate(&data.bodies, start);
+    let body = &bodies[site.body.index()];
+    site.spawn(&mut vessels[0], body, &data);
+    let spawned = body.to_body_fixed_position(vessels[0].transform.position());
+
+    let steps = 150;
+    for step in 1..=steps {
+        let environment = body.environment(vessels[0].transform.position());
+        vessels[0].update(&data, &environment, number(DT));
+        let crashes = contact::resolve_contacts(&mut vessels, &data, Some(&body.ground()));
+        assert!(crashes.is_empty(), "crashed after {step} steps");
+    }
+
+    // Still on the same spot, which has carried it along with the turning planet
+    let bodies = body::propagate(&data.bodies, start + number(DT) * steps);
+    let body = &bodies[site.body.index()];
+    let position = vessels[0].transform.position();
+    let moved = body.to_body_fixed_position(position) - spawned;
+    assert!(moved.length() < number(0.05), "moved {moved:?}");
+    let relative = body.surface_velocity(position, vessels[0].velocity);
+    assert!(relative.length() < number(0.05), "sliding at {relative:?}");
+}
+
+#[test]
+fn the_air_carries_vessels_along_with_the_planet() {
+    let data = GameData::load(Path::new("GameData"));
+    let site = &data.launch_sites[data.launch_sites.id("launch-pad").unwrap()];
+    let body = &body::propagate(&data.bodies, Number::ZERO)[site.body.index()];
+    let mut vessel = Vessel::new(
+        data.parts.id("mk1-cockpit").unwrap(),
+        Transform::IDENTITY,
+        &data,
+    );
+    site.spawn(&mut vessel, body, &data);
+    let environment = body.environment(vessel.transform.position());
+    assert!(environment.density > Number::ONE);
+
+    // Thousands of meters per second along with Kerbin around Kerbol, and none through the air
+    // around it, so only gravity changes the velocity
+    assert!(vessel.velocity.length() > number(9000.0));
+    let before = vessel.velocity;
+    vessel.update(&data, &environment, number(DT));
+    let change = vessel.velocity - before - environment.gravity * number(DT);
+    assert!(change.length() < number(0.001), "dragged by {change:?}");
+}
+
+#[test]
+fn spawns_on_a_planet_going_around_a_star() {
+    let mut data = planet_with_site("star-spawn-site", 0.0, true);
+    let mut vessels = vec![common::stack(&mut data, 2)];
+    let site = &data.launch_sites[data.launch_sites.id("pad").unwrap()];
+    let start = number(1000.0);
+    let bodies = bodies_around(&data, site.body, start);
+    let body = &bodies[site.body.index()];
+    site.spawn(&mut vessels[0], body, &data);
+
+    let root = body.to_geodetic(vessels[0].transform.position());
+    assert!(
+        (root.altitude - number(3.0)).abs() < number(0.01),
+        "{root:?}"
+    );
+    let relative = body.surface_velocity(vessels[0].transform.position(), vessels[0].velocity);
+    assert!(relative.length() < number(0.01), "{relative:?}");
+
+    let steps = 150;
+    for step in 1..=steps {
+        let bodies = bodies_around(&data, site.body, start + number(DT) * step);
+        let body = &bodies[site.body.index()];
+        let environment = body.environment(vessels[0].transform.position());
+        vessels[0].update(&data, &environment, number(DT));
+        let crashes = contact::resolve_contacts(&mut vessels, &data, Some(&body.ground()));
+        assert!(crashes.is_empty(), "crashed after {step} steps");
+    }
+
+    let bodies = bodies_around(&data, site.body, start + number(DT) * steps);
+    let body = &bodies[site.body.index()];
+    let position = vessels[0].transform.position();
+    let relative = body.surface_velocity(position, vessels[0].velocity);
+    assert!(relative.length() < number(0.05), "sliding at {relative:?}");
+}
+
+#[test]
+fn orbits_stay_put_around_a_planet_going_around_a_star() {
+    let mut data = planet_with_site("orbit-site", 0.0, true);
+    let mut vessel = common::stack(&mut data, 1);
+    let planet = data.bodies.id("planet").unwrap();
+    let start = number(1000.0);
+    let body = &bodies_around(&data, planet, start)[planet.index()];
+
+    // A circular orbit 50 km up, around once every 1165 seconds
+    let radius = body.radius + number(50_000.0);
+    vessel.transform = Transform::translation(Vector3::X * radius);
+    vessel.velocity = Vector3::Y * (body.mu / radius).sqrt();
+    let before = body.orbit(&vessel);
+
+    let dt = number(0.1);
+    let steps = 11_650;
+    for step in 0..steps {
+        let bodies = bodies_around(&data, planet, start + dt * step);
+        let environment = bodies[planet.index()].environment(vessel.transform.position());
+        vessel.update(&data, &environment, dt);
+    }
+
+    // Falling around the star along with the planet, rather than being left kilometers behind
+    let after = bodies_around(&data, planet, start + dt * steps)[planet.index()].orbit(&vessel);
+    let drift = (after.semi_major_axis - before.semi_major_axis).abs();
+    assert!(drift < number(100.0), "drifted {drift} m");
+    let eccentricity = after.eccentricity;
+    assert!(eccentricity < number(0.001), "{eccentricity}");
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::number;
+use space::{body, game_data::GameData, light::Sunlight, vector3::Vector3, Number};
+use std::path::Path;
+
+#[test]
+fn shines_from_the_root_of_the_hierarchy() {
+    let data = GameData::load(Path::new("GameData"));
+    let bodies = body::propagate(&data.bodies, number(1000.0));
+    let sunlight = Sunlight::new(&data.bodies, &bodies);
+    let kerbol = &bodies[data.bodies.id("kerbol").unwrap().index()];
+    let kerbin = &bodies[data.bodies.id("kerbin").unwrap().index()];
+
+    let position = kerbin.position + Vector3::Z * number(700_000.0);
+    let direction = sunlight.direction(position).unwrap();
+    let towards = (kerbol.position - position) / (kerbol.position - position).length();
+    assert!((direction - towards).length() < number(1e-6));
+    // About as bright as above Earth, at Kerbin's distance
+    let flux = sunlight.flux(position);
+    assert!((flux - number(1360.0)).abs() < number(1.0), "{flux}");
+    assert_eq!(sunlight.solar_exposure(position), Number::ONE);
+}
+
+#[test]
+fn bodies_cast_shadows() {
+    let data = GameData::load(Path::new("GameData"));
+    let bodies = body::propagate(&data.bodies, number(1000.0));
+    let sunlight = Sunlight::new(&data.bodies, &bodies);
+    for name in ["kerbin", "mun"] {
+        let body = &bodies[data.bodies.id(name).unwrap().index()];
+        let towards_sun = sunlight.direction(body.position).unwrap();
+        // Sideways from the sun, far enough to clear the body
+        let sideways = towards_sun.cross(Vector3::Z);
+        let sideways = sideways / sideways.length();
+        let distance = body.radius * number(1.5);
+
+        let day = body.position + towards_sun * distance;
+        let night = body.position - towards_sun * distance;
+        let dusk = body.position + sideways * distance;
+        let in_umbra = night + sideways * body.radius * number(0.9);
+        assert_eq!(sunlight.solar_exposure(day), Number::ONE, "{name}");
+        assert_eq!(sunlight.solar_exposure(night), Number::ZERO, "{name}");
+        assert_eq!(sunlight.solar_exposure(dusk), Number::ONE, "{name}");
+        assert_eq!(sunlight.solar_exposure(in_umbra), Number::ZERO, "{name}");
+        // Standing on the ground at noon and at midnight
+        let up = towards_sun * (body.radius + number(2.0));
+        assert_eq!(
+            sunlight.solar_exposure(body.position + up),
+            Number::ONE,
+            "{name}"
+        );
+        assert_eq!(
+            sunlight.solar_exposure(body.position - up),
+            Number::ZERO,
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn dark_without_a_shining_root() {
+    let directory = common::game_data_dir(
+        "dark-planet",
+        &[(
+            "planet.json",
+            r#"{ "type": "body", "name": "planet", "title": "Planet", "radius": 100000, "mu": 1e11 }"#,
+        )],
+    );
+    let data = GameData::load(&directory);
+    let bodies = body::propagate(&data.bodies, Number::ZERO);
+    let sunlight = Sunlight::new(&data.bodies, &bodies);
+
+    let position = Vector3::X * number(200_000.0);
+    assert_eq!(sunlight.direction(position), None);
+    assert_eq!(sunlight.flux(position), Number::ZERO);
+    assert_eq!(sunlight.solar_exposure(position), Number::ZERO);
+}
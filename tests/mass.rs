@@ -0,0 +1,213 @@
+mod common;
+
+use common::{close, cube_model, number};
+use serde_json::json;
+use space::{
+    game_data::GameData, matrix3::Matrix3, motor::Transform, part, vector3::Vector3,
+    vessel::Vessel, Number, Vertex,
+};
+
+fn matrix_close(a: Matrix3, b: Matrix3, tolerance: f64) -> bool {
+    close(a.x, b.x, tolerance) && close(a.y, b.y, tolerance) && close(a.z, b.z, tolerance)
+}
+
+fn diagonal(x: f64, y: f64, z: f64) -> Matrix3 {
+    Matrix3::from_diagonal(Vector3::new(number(x), number(y), number(z)))
+}
+
+fn vertex(x: f32, y: f32, z: f32) -> Vertex {
+    Vertex {
+        position: cgmath::Vector3::new(x, y, z),
+        normal: cgmath::Vector3::new(0.0, 0.0, 0.0),
+        texture_coords: cgmath::Vector2::new(0.0, 0.0),
+    }
+}
+
+/// A 1 tonne pod and a 100 kilogram tank holding 500 kilograms of fuel two meters below it,
+/// both with 100 kg m² of inertia around every axis when dry
+fn setup() -> GameData {
+    let mut data = GameData::new();
+    common::resource(
+        &mut data,
+        json!({ "name": "LiquidFuel", "title": "", "density": 5 }),
+    );
+    common::part(
+        &mut data,
+        json!({ "name": "pod", "mass": 1000, "inertia": [100, 100, 100] }),
+    );
+    common::part(
+        &mut data,
+        json!({
+            "name": "tank",
+            "mass": 100,
+            "inertia": [100, 100, 100],
+            "resources": [{ "name": "LiquidFuel", "capacity": 100, "amount": 100 }],
+        }),
+    );
+    data
+}
+
+fn pod_and_tank(data: &GameData) -> Vessel {
+    let mut vessel = Vessel::new(data.parts.id("pod").unwrap(), Transform::IDENTITY, data);
+    vessel.attach(
+        0,
+        data.parts.id("tank").unwrap(),
+        Transform::translation(Vector3::Y * number(-2.0)),
+        data,
+    );
+    vessel
+}
+
+#[test]
+fn meshes_are_filled_uniformly() {
+    // A 2 meter cube of 12 kilograms has 12 / 12 * (2² + 2²) kg m² around each axis
+    let model = cube_model();
+    let (center, inertia) = part::mesh_mass_properties(&model.vertices, number(12.0));
+    assert!(close(center, Vector3::ZERO, 1e-6));
+    assert!(
+        matrix_close(inertia, diagonal(8.0, 8.0, 8.0), 1e-6),
+        "{inertia:?}"
+    );
+
+    // Moved away from the origin, around its own center
+    let moved = model
+        .vertices
+        .iter()
+        .map(|corner| {
+            vertex(
+                corner.position.x + 3.0,
+                corner.position.y,
+                corner.position.z,
+            )
+        })
+        .collect::<Vec<_>>();
+    let (center, inertia) = part::mesh_mass_properties(&moved, number(12.0));
+    assert!(close(center, Vector3::X * number(3.0), 1e-6));
+    assert!(
+        matrix_close(inertia, diagonal(8.0, 8.0, 8.0), 1e-6),
+        "{inertia:?}"
+    );
+}
+
+#[test]
+fn flat_meshes_still_have_inertia() {
+    // A 2 by 4 meter sheet around (3, 0, 0), which encloses no volume
+    let sheet = [
+        vertex(2.0, -2.0, 0.0),
+        vertex(4.0, -2.0, 0.0),
+        vertex(4.0, 2.0, 0.0),
+        vertex(2.0, -2.0, 0.0),
+        vertex(4.0, 2.0, 0.0),
+        vertex(2.0, 2.0, 0.0),
+    ];
+    let (center, inertia) = part::mesh_mass_properties(&sheet, number(12.0));
+    assert!(close(center, Vector3::X * number(3.0), 1e-6));
+    assert!(
+        matrix_close(inertia, diagonal(16.0, 4.0, 20.0), 1e-6),
+        "{inertia:?}"
+    );
+
+    // Nothing at all is a point at the origin
+    let (center, inertia) = part::mesh_mass_properties(&[], number(12.0));
+    assert_eq!(center, Vector3::ZERO);
+    assert_eq!(inertia, Matrix3::ZERO);
+}
+
+#[test]
+fn parts_combine_around_the_shared_center_of_mass() {
+    let mut data = GameData::new();
+    let vessel = common::stack(&mut data, 2);
+    let properties = vessel.mass_properties(&data);
+    assert_eq!(properties.mass, number(2000.0));
+    assert!(close(
+        properties.center_of_mass,
+        Vector3::Y * number(-1.0),
+        1e-6
+    ));
+    // Each cube is a meter off the shared center along Y, which adds 1000 kg m² around X and Z
+    let cube = 1000.0 / 12.0 * 8.0;
+    assert!(
+        matrix_close(
+            properties.inertia,
+            diagonal(2.0 * (cube + 1000.0), 2.0 * cube, 2.0 * (cube + 1000.0)),
+            1e-3
+        ),
+        "{:?}",
+        properties.inertia
+    );
+
+    // Turning a part turns its inertia with it
+    let mut data = GameData::new();
+    let lopsided = common::part(
+        &mut data,
+        json!({ "name": "lopsided", "mass": 10, "inertia": [100, 200, 300] }),
+    );
+    let mut vessel = Vessel::new(lopsided, Transform::IDENTITY, &data);
+    vessel.parts[0].transform = Transform::rotation(Vector3::Z, Number::FRAC_PI_2);
+    let properties = vessel.mass_properties(&data);
+    assert!(
+        matrix_close(properties.inertia, diagonal(200.0, 100.0, 300.0), 1e-6),
+        "{:?}",
+        properties.inertia
+    );
+}
+
+#[test]
+fn draining_fuel_moves_the_center_of_mass_and_lightens_the_tank() {
+    let data = setup();
+    let mut vessel = pod_and_tank(&data);
+
+    // Inertia around X of the pod at the origin and the tank two meters down, each scaled up
+    // by the fuel it holds
+    let expected = |tank_mass: f64| {
+        let center = -2.0 * tank_mass / (1000.0 + tank_mass);
+        let around_x = 100.0
+            + 1000.0 * center * center
+            + 100.0 * tank_mass / 100.0
+            + tank_mass * (2.0 + center) * (2.0 + center);
+        (center, around_x)
+    };
+
+    for (amount, tank_mass) in [(100.0, 600.0), (50.0, 350.0), (0.0, 100.0)] {
+        vessel.parts[1].resources[0].amount = number(amount);
+        let properties = vessel.mass_properties(&data);
+        let (center, around_x) = expected(tank_mass);
+        assert_eq!(properties.mass, number(1000.0 + tank_mass));
+        assert!(close(
+            properties.center_of_mass,
+            Vector3::Y * number(center),
+            1e-6
+        ));
+        assert!(
+            (properties.inertia.x.x - number(around_x)).abs() < number(1e-3),
+            "{} {around_x}",
+            properties.inertia.x.x
+        );
+        assert_eq!(properties.inertia.y.y, number(100.0 + tank_mass));
+    }
+}
+
+#[test]
+fn detached_pieces_keep_the_mass_of_their_own_parts() {
+    let mut data = GameData::new();
+    let mut vessel = common::stack(&mut data, 3);
+    let piece = vessel.detach(2, &data);
+
+    // Both halves stay where they were, so each is just its own cubes
+    let remaining = vessel.mass_properties(&data);
+    let mut upper_data = GameData::new();
+    let upper = common::stack(&mut upper_data, 2).mass_properties(&upper_data);
+    assert_eq!(remaining.mass, upper.mass);
+    assert!(close(remaining.center_of_mass, upper.center_of_mass, 1e-6));
+    assert!(matrix_close(remaining.inertia, upper.inertia, 1e-3));
+
+    let piece = piece.mass_properties(&data);
+    let cube = 1000.0 / 12.0 * 8.0;
+    assert_eq!(piece.mass, number(1000.0));
+    assert!(close(piece.center_of_mass, Vector3::Y * number(-4.0), 1e-6));
+    assert!(
+        matrix_close(piece.inertia, diagonal(cube, cube, cube), 1e-3),
+        "{:?}",
+        piece.inertia
+    );
+}
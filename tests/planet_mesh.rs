@@ -0,0 +1,229 @@
+mod common;
+
+use common::number;
+use space::{
+    body::Body,
+    planet_mesh::{Chunk, LodSettings, PlanetMesh},
+    terrain::{Noise, Terrain},
+    vector3::Vector3,
+    Number,
+};
+use std::sync::Arc;
+
+fn planet(terrain: Option<Terrain>) -> Body {
+    Body {
+        terrain: terrain.map(Arc::new),
+        ..Body::new(number(100_000.0), number(1e11))
+    }
+}
+
+fn hilly_planet() -> Body {
+    planet(Some(Terrain::Noise(Noise {
+        seed: 5,
+        amplitude: number(2000.0),
+        wavelength: number(30_000.0),
+        octaves: 4,
+        persistence: number(0.5),
+        radius: number(100_000.0),
+    })))
+}
+
+fn settings() -> LodSettings {
+    LodSettings {
+        resolution: 4,
+        max_level: 5,
+        split_distance: number(2.0),
+    }
+}
+
+/// Grid points along each edge of a chunk, in the order its edges are numbered
+fn edges(chunk: &Chunk) -> [Vec<Vector3>; 4] {
+    let count = (chunk.positions.len() as f64).sqrt() as usize;
+    let at = |i: usize, j: usize| chunk.positions[j * count + i];
+    let last = count - 1;
+    [
+        (0..count).map(|i| at(i, 0)).collect(),
+        (0..count).map(|j| at(last, j)).collect(),
+        (0..count).map(|i| at(i, last)).collect(),
+        (0..count).map(|j| at(0, j)).collect(),
+    ]
+}
+
+fn distance_to_segment(point: Vector3, start: Vector3, end: Vector3) -> Number {
+    let along = end - start;
+    let fraction = ((point - start).dot(along) / along.dot(along)).clamp(Number::ZERO, Number::ONE);
+    (point - (start + along * fraction)).length()
+}
+
+#[test]
+fn splits_close_to_the_camera_and_merges_far_away() {
+    let body = planet(None);
+    let mut mesh = PlanetMesh::new(settings());
+
+    let camera = Vector3::X * number(100_010.0);
+    assert!(mesh.update(&body, camera));
+    let finest = mesh.chunks().map(|chunk| chunk.id.level).max().unwrap();
+    assert_eq!(finest, 5);
+    // The finest chunks are the ones under the camera
+    let closest = mesh
+        .chunks()
+        .min_by_key(|chunk| (chunk.origin - camera).length())
+        .unwrap();
+    assert_eq!(closest.id.level, 5);
+    assert!(!mesh.update(&body, camera));
+
+    assert!(mesh.update(&body, Vector3::X * number(10_000_000.0)));
+    assert_eq!(mesh.chunks().count(), 6);
+    assert!(mesh.chunks().all(|chunk| chunk.id.level == 0));
+}
+
+#[test]
+fn leaves_cover_each_face_with_neighbours_at_most_a_level_apart() {
+    let body = planet(None);
+    let mut mesh = PlanetMesh::new(settings());
+    mesh.update(
+        &body,
+        Vector3::new(number(60_000.0), number(60_000.0), number(60_000.0)),
+    );
+
+    for face in 0..6 {
+        let area = mesh
+            .chunks()
+            .filter(|chunk| chunk.id.face == face)
+            .fold(Number::ZERO, |area, chunk| {
+                area + chunk.id.size() * chunk.id.size()
+            });
+        assert_eq!(area, number(4.0), "face {face}");
+    }
+    for chunk in mesh.chunks() {
+        for other in mesh.chunks().filter(|other| other.id.face == chunk.id.face) {
+            if other.id.level > chunk.id.level {
+                let shift = other.id.level - chunk.id.level;
+                assert!(
+                    (other.id.x >> shift, other.id.y >> shift) != (chunk.id.x, chunk.id.y),
+                    "{:?} overlaps {:?}",
+                    other.id,
+                    chunk.id
+                );
+            }
+        }
+        for level in chunk.coarser_neighbours.into_iter().flatten() {
+            assert_eq!(level + 1, chunk.id.level);
+        }
+    }
+}
+
+#[test]
+fn seams_between_chunks_have_no_cracks() {
+    let body = hilly_planet();
+    let mut mesh = PlanetMesh::new(LodSettings {
+        max_level: 3,
+        ..settings()
+    });
+    // Over a corner of the cube, so seams between faces change level too
+    mesh.update(
+        &body,
+        Vector3::new(number(70_000.0), number(70_000.0), number(70_000.0)),
+    );
+    let chunks = mesh.chunks().collect::<Vec<_>>();
+    assert!(chunks
+        .iter()
+        .any(|chunk| chunk.coarser_neighbours.iter().any(Option::is_some)));
+
+    let edges = chunks
+        .iter()
+        .map(|chunk| (chunk.id, edges(chunk)))
+        .collect::<Vec<_>>();
+    let tolerance = number(0.001);
+    for (id, chunk_edges) in &edges {
+        for &point in chunk_edges.iter().flatten() {
+            // Every point along an edge lies on the edge of some other chunk
+            let covered = edges
+                .iter()
+                .filter(|(other, _)| other != id)
+                .flat_map(|(_, other_edges)| other_edges)
+                .any(|other| {
+                    other.windows(2).any(|segment| {
+                        distance_to_segment(point, segment[0], segment[1]) < tolerance
+                    })
+                });
+            assert!(covered, "{point:?} on {id:?} is over a crack");
+        }
+    }
+}
+
+#[test]
+fn triangles_face_outwards() {
+    let body = planet(None);
+    let mut mesh = PlanetMesh::new(settings());
+    mesh.update(
+        &body,
+        Vector3::new(number(0.0), number(-70_000.0), number(72_000.0)),
+    );
+    for chunk in mesh.chunks() {
+        let origin = [chunk.origin.x, chunk.origin.y, chunk.origin.z].map(|x| x.to_num::<f64>());
+        for triangle in chunk.vertices.chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|index| {
+                let position = triangle[index].position;
+                [position.x, position.y, position.z].map(f64::from)
+            });
+            let ab = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+            let ac = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+            let normal = [
+                ab[1] * ac[2] - ab[2] * ac[1],
+                ab[2] * ac[0] - ab[0] * ac[2],
+                ab[0] * ac[1] - ab[1] * ac[0],
+            ];
+            let outwards = (0..3)
+                .map(|axis| normal[axis] * (a[axis] + origin[axis]))
+                .sum::<f64>();
+            assert!(outwards > 0.0, "{:?} winds inwards", chunk.id);
+        }
+    }
+}
+
+#[test]
+fn vertices_follow_the_terrain() {
+    let body = hilly_planet();
+    let mut mesh = PlanetMesh::new(settings());
+    mesh.update(&body, Vector3::Z * number(102_000.0));
+    for chunk in mesh.chunks() {
+        for &position in &chunk.positions {
+            let geodetic = body.to_geodetic(position);
+            let height = body.terrain_height(geodetic.latitude, geodetic.longitude);
+            // Stitched edges are pulled onto a coarser neighbour's, so only roughly there
+            let tolerance = if chunk.coarser_neighbours.iter().any(Option::is_some) {
+                number(500.0)
+            } else {
+                number(0.01)
+            };
+            assert!(
+                (geodetic.altitude - height).abs() < tolerance,
+                "{:?}: {} vs {height}",
+                chunk.id,
+                geodetic.altitude
+            );
+        }
+    }
+}
+
+#[test]
+fn textures_stay_whole_across_the_antimeridian_and_the_poles() {
+    let body = planet(None);
+    for camera in [
+        Vector3::X * number(-102_000.0),
+        Vector3::Z * number(102_000.0),
+    ] {
+        let mut mesh = PlanetMesh::new(settings());
+        mesh.update(&body, camera);
+        for chunk in mesh.chunks() {
+            for triangle in chunk.vertices.chunks(3) {
+                let u = triangle.iter().map(|vertex| vertex.texture_coords.x);
+                let span = u.clone().fold(f32::MIN, f32::max) - u.fold(f32::MAX, f32::min);
+                // Triangles at a pole reach a quarter of the way round, while wrapping from one
+                // edge of the texture to the other would smear all of it across the triangle
+                assert!(span < 0.5, "{:?} spans {span} of the texture", chunk.id);
+            }
+        }
+    }
+}
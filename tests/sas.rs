@@ -0,0 +1,118 @@
+mod common;
+
+use serde_json::json;
+use space::{
+    game_data::GameData,
+    motor::Transform,
+    physics::Environment,
+    sas::{Reference, Sas, SasMode},
+    vector3::Vector3,
+    vessel::Vessel,
+    Number,
+};
+
+const DT: f64 = 0.05;
+
+fn setup() -> GameData {
+    let mut data = GameData::new();
+    common::part(
+        &mut data,
+        json!({
+            "name": "probe",
+            "mass": 1000,
+            "inertia": [800, 1500, 1200],
+            "reaction_wheel": { "torque": [1500, 1500, 1500] },
+        }),
+    );
+    data
+}
+
+fn tumbling_probe(data: &GameData) -> Vessel {
+    let mut vessel = Vessel::new(data.parts.id("probe").unwrap(), Transform::IDENTITY, data);
+    vessel.angular_velocity = Vector3::new(
+        Number::from_num(0.6),
+        Number::from_num(-0.4),
+        Number::from_num(0.5),
+    );
+    vessel
+}
+
+/// Runs the SAS until the vessel has settled, returning how long that took in seconds
+fn settle(
+    sas: &mut Sas,
+    vessel: &mut Vessel,
+    data: &GameData,
+    reference: &Reference,
+    limit: f64,
+) -> Option<f64> {
+    let dt = Number::from_num(DT);
+    let tolerance = Number::from_num(0.01);
+    let mut settled_since = None;
+    for step in 0..(limit / DT) as usize {
+        sas.update(vessel, reference, dt);
+        vessel.update(data, &Environment::VACUUM, dt);
+
+        let settled = sas.attitude_error(vessel, reference).length() < tolerance
+            && vessel.angular_velocity.length() < tolerance;
+        match (settled, settled_since) {
+            (true, None) => settled_since = Some(step),
+            (false, _) => settled_since = None,
+            _ => {}
+        }
+    }
+    settled_since.map(|step| step as f64 * DT)
+}
+
+#[test]
+fn holds_a_target_attitude() {
+    let data = setup();
+    let mut vessel = tumbling_probe(&data);
+    let target = Transform::rotation(Vector3::Z, Number::from_num(1.0))
+        .apply(Transform::rotation(Vector3::X, Number::from_num(-2.5)));
+    let mut sas = Sas::new(SasMode::Hold(target));
+
+    let time = settle(&mut sas, &mut vessel, &data, &Reference::ORIGIN, 120.0);
+    assert!(
+        time.is_some_and(|time| time < 60.0),
+        "settled after {time:?}"
+    );
+
+    // The vessel ends up turned the same way as the target
+    for axis in [Vector3::X, Vector3::Y, Vector3::Z] {
+        let difference =
+            vessel.transform.transform_direction(axis) - target.transform_direction(axis);
+        assert!(difference.length() < Number::from_num(0.02));
+    }
+}
+
+#[test]
+fn points_prograde_and_at_targets() {
+    let data = setup();
+    let reference = Reference {
+        position: Vector3::new(Number::ZERO, Number::from_num(-1000), Number::ZERO),
+        velocity: Vector3::ZERO,
+    };
+
+    let mut vessel = tumbling_probe(&data);
+    vessel.velocity = Vector3::new(Number::from_num(3), Number::ZERO, Number::from_num(-4));
+    let mut sas = Sas::new(SasMode::Prograde);
+    let time = settle(&mut sas, &mut vessel, &data, &reference, 120.0);
+    assert!(
+        time.is_some_and(|time| time < 60.0),
+        "settled after {time:?}"
+    );
+    let forward = vessel.transform.transform_direction(Vector3::Y);
+    let prograde = vessel.velocity / vessel.velocity.length();
+    assert!((forward - prograde).length() < Number::from_num(0.02));
+
+    let mut vessel = tumbling_probe(&data);
+    let target = Vector3::new(Number::from_num(-50), Number::ZERO, Number::ZERO);
+    let mut sas = Sas::new(SasMode::Target(target));
+    let time = settle(&mut sas, &mut vessel, &data, &reference, 120.0);
+    assert!(
+        time.is_some_and(|time| time < 60.0),
+        "settled after {time:?}"
+    );
+    let forward = vessel.transform.transform_direction(Vector3::Y);
+    assert!((forward + Vector3::X).length() < Number::from_num(0.02));
+}
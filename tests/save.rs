@@ -0,0 +1,124 @@
+mod common;
+
+use common::{close, game_data_dir, number};
+use space::{
+    game_data::GameData, motor::Transform, save::SaveFile, staging, vector3::Vector3,
+    vessel::Vessel, Number,
+};
+use std::path::Path;
+
+/// A cockpit over a half empty tank and an engine, partway through a flight
+fn flown_vessel(data: &GameData) -> Vessel {
+    let mut vessel = Vessel::new(
+        data.parts.id("mk1-cockpit").unwrap(),
+        Transform::rotation(Vector3::Z, number(0.3))
+            .apply(Transform::translation(Vector3::X * number(700_000.0))),
+        data,
+    );
+    let tank = vessel.attach(
+        0,
+        data.parts.id("mk1-fl-100").unwrap(),
+        Transform::translation(Vector3::Y * number(-1.5)),
+        data,
+    );
+    vessel.attach(
+        tank,
+        data.parts.id("mk1-lv-60").unwrap(),
+        Transform::translation(Vector3::Y * number(-3.0)),
+        data,
+    );
+    staging::assign_stages(&mut vessel, data);
+    staging::activate_next_stage(&mut vessel, data);
+    for (index, part) in vessel.parts.iter_mut().enumerate() {
+        for stored in &mut part.resources {
+            stored.amount = stored.capacity / Number::from_num(index + 2) + number(0.125);
+        }
+        part.temperature = number(300.0 + index as f64 * 10.5);
+    }
+    vessel.velocity = Vector3::new(number(12.5), number(2200.25), number(-3.0));
+    vessel.angular_velocity = Vector3::Z * number(0.01);
+    vessel.throttle = number(0.75);
+    vessel
+}
+
+#[test]
+fn vessels_round_trip_through_a_save_file() {
+    let data = GameData::load(Path::new("GameData"));
+    let vessel = flown_vessel(&data);
+    let path = game_data_dir("save-round-trip", &[]).join("save.json");
+    SaveFile::new(std::slice::from_ref(&vessel), &data).save(&path);
+
+    // Loaded into a fresh load of the game data, where ids could have come out differently
+    let data = GameData::load(Path::new("GameData"));
+    let loaded = SaveFile::load(&path).vessels(&data);
+    assert_eq!(loaded.len(), 1);
+    let loaded = &loaded[0];
+
+    assert!(close(
+        loaded.transform.position(),
+        vessel.transform.position(),
+        1e-6
+    ));
+    assert!(close(
+        loaded.transform.transform_direction(Vector3::Y),
+        vessel.transform.transform_direction(Vector3::Y),
+        1e-9
+    ));
+    assert_eq!(loaded.velocity, vessel.velocity);
+    assert_eq!(loaded.angular_velocity, vessel.angular_velocity);
+    assert_eq!(loaded.throttle, vessel.throttle);
+    assert_eq!(loaded.current_stage, vessel.current_stage);
+
+    assert_eq!(loaded.parts.len(), vessel.parts.len());
+    for (loaded, part) in loaded.parts.iter().zip(&vessel.parts) {
+        assert_eq!(
+            data.parts[loaded.part].config.name,
+            data.parts[part.part].config.name
+        );
+        assert_eq!(loaded.parent, part.parent);
+        assert_eq!(loaded.stage, part.stage);
+        assert_eq!(loaded.activated, part.activated);
+        assert_eq!(loaded.temperature, part.temperature);
+        assert!(close(
+            loaded.transform.position(),
+            part.transform.position(),
+            1e-9
+        ));
+        let amounts = |part: &space::vessel::VesselPart| {
+            part.resources
+                .iter()
+                .map(|stored| {
+                    (
+                        data.resources.name(stored.resource).to_string(),
+                        stored.amount,
+                        stored.capacity,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(amounts(loaded), amounts(part));
+    }
+    assert!(vessel.parts[1].resources[0].amount < vessel.parts[1].resources[0].capacity);
+}
+
+#[test]
+fn older_saves_start_parts_at_room_temperature() {
+    let data = GameData::load(Path::new("GameData"));
+    let mut vessel = flown_vessel(&data);
+    vessel.parts[0].deployed = true;
+    // Written before parts kept their temperature or whether they were deployed
+    let mut save = serde_json::to_value(SaveFile::new(&[vessel], &data)).unwrap();
+    for part in save["vessels"][0]["parts"].as_array_mut().unwrap() {
+        let part = part.as_object_mut().unwrap();
+        part.remove("deployed");
+        part.remove("temperature");
+    }
+    let path = game_data_dir("save-defaults", &[("save.json", save.to_string().as_str())])
+        .join("save.json");
+
+    let vessels = SaveFile::load(&path).vessels(&data);
+    for part in &vessels[0].parts {
+        assert_eq!(part.temperature, space::thermal::ROOM_TEMPERATURE);
+        assert!(!part.deployed);
+    }
+}
@@ -0,0 +1,171 @@
+mod common;
+
+use common::{number, radians};
+use serde_json::json;
+use space::{
+    biome::BiomeMap,
+    body::{self, Body},
+    game_data::GameData,
+    geodetic::Geodetic,
+    motor::Transform,
+    situation::{self, Situation},
+    vector3::Vector3,
+    vessel::Vessel,
+    Number,
+};
+use std::path::{Path, PathBuf};
+
+/// A fresh directory with a planet that has an ocean over its western half, where the ground is
+/// 1000 meters below sea level, and land 1000 meters up over its eastern half
+fn ocean_planet(name: &str) -> PathBuf {
+    let directory = common::game_data_dir(
+        name,
+        &[(
+            "planet.json",
+            r#"{
+            "type": "body",
+            "name": "planet",
+            "title": "Planet",
+            "radius": 100000,
+            "mu": 1e11,
+            "atmosphere": {
+                "pressure": 1,
+                "density": 1.2,
+                "scale_height": 5000,
+                "height": 50000
+            },
+            "terrain": {
+                "type": "heightmap",
+                "image": "height.png",
+                "min_height": -1000,
+                "max_height": 1000
+            },
+            "ocean": true,
+            "flying_high_altitude": 20000,
+            "space_high_altitude": 200000
+        }"#,
+        )],
+    );
+    let pixels = [0, 0, 65535, 65535, 0, 0, 65535, 65535];
+    image::ImageBuffer::<image::Luma<u16>, _>::from_raw(4, 2, pixels.to_vec())
+        .unwrap()
+        .save(directory.join("height.png"))
+        .unwrap();
+    directory
+}
+
+/// A 2 meter cube with its bottom `height` meters above the ground, or above sea level where
+/// `over_sea`
+fn cube_at(data: &GameData, body: &Body, longitude: f64, height: f64, over_sea: bool) -> Vessel {
+    let cube = data.parts.id("cube").unwrap();
+    let (latitude, longitude) = (radians(10.0), radians(longitude));
+    let ground = if over_sea {
+        Number::ZERO
+    } else {
+        body.terrain_height(latitude, longitude)
+    };
+    let transform = Transform::translation(Vector3::Y).apply(body.north_east_up(Geodetic::new(
+        latitude,
+        longitude,
+        ground + number(height),
+    )));
+    Vessel::new(cube, transform, data)
+}
+
+#[test]
+fn biome_maps_pick_the_closest_color() {
+    // West half forest, east half desert, with a couple of smudged pixels
+    let forest = [20, 120, 30];
+    let desert = [230, 200, 120];
+    let map = BiomeMap::new(
+        4,
+        2,
+        vec![
+            forest,
+            forest,
+            desert,
+            desert,
+            forest,
+            [40, 110, 50],
+            desert,
+            [220, 190, 140],
+        ],
+        vec![
+            ("forest".to_string(), forest),
+            ("desert".to_string(), desert),
+        ],
+    );
+    assert_eq!(map.biome_at(radians(45.0), radians(-135.0)), "forest");
+    assert_eq!(map.biome_at(radians(45.0), radians(60.0)), "desert");
+    assert_eq!(map.biome_at(radians(-45.0), radians(-30.0)), "forest");
+    assert_eq!(map.biome_at(radians(-45.0), radians(170.0)), "desert");
+    // Longitude wraps around, and the poles stay on the map
+    assert_eq!(map.biome_at(radians(-90.0), radians(-190.0)), "desert");
+    assert_eq!(map.biome_at(radians(90.0), radians(190.0)), "forest");
+}
+
+#[test]
+fn loads_the_bundled_mun_biomes() {
+    let data = GameData::load(Path::new("GameData"));
+    let bodies = body::propagate(&data.bodies, Number::ZERO);
+    let mun = &bodies[data.bodies.id("mun").unwrap().index()];
+    assert_eq!(mun.biome_at(radians(85.0), radians(10.0)), Some("poles"));
+    assert_eq!(
+        mun.biome_at(radians(-50.0), radians(100.0)),
+        Some("highlands")
+    );
+    assert_eq!(mun.biome_at(radians(3.0), radians(-70.0)), Some("midlands"));
+
+    let kerbin = &bodies[data.bodies.id("kerbin").unwrap().index()];
+    assert_eq!(kerbin.biome_at(Number::ZERO, Number::ZERO), None);
+}
+
+#[test]
+fn classifies_situations_by_altitude() {
+    let mut data = GameData::load(&ocean_planet("situations"));
+    common::part_with_model(
+        &mut data,
+        json!({ "name": "cube", "mass": 1000.0 }),
+        common::cube_model(),
+    );
+    let body = &body::propagate(&data.bodies, Number::ZERO)[0];
+    let situation = |longitude: f64, height: f64, over_sea: bool| {
+        let vessel = cube_at(&data, body, longitude, height, over_sea);
+        situation::situation(&vessel, &data, body)
+    };
+
+    assert_eq!(situation(90.0, 0.0, false), Situation::Landed);
+    // On the sea floor, and floating on the surface
+    assert_eq!(situation(-90.0, 0.0, false), Situation::Splashed);
+    assert_eq!(situation(-90.0, 0.0, true), Situation::Splashed);
+    // Just off the ground is already flying
+    assert_eq!(situation(90.0, 5.0, false), Situation::FlyingLow);
+    assert_eq!(situation(-90.0, 5.0, true), Situation::FlyingLow);
+    assert_eq!(situation(90.0, 25_000.0, true), Situation::FlyingHigh);
+    assert_eq!(situation(90.0, 60_000.0, true), Situation::SpaceLow);
+    assert_eq!(situation(-90.0, 300_000.0, true), Situation::SpaceHigh);
+}
+
+#[test]
+fn dry_bodies_have_no_splashing_down() {
+    let mut data = GameData::load(&ocean_planet("dry-situations"));
+    common::part_with_model(
+        &mut data,
+        json!({ "name": "cube", "mass": 1000.0 }),
+        common::cube_model(),
+    );
+    let mut body = body::propagate(&data.bodies, Number::ZERO).remove(0);
+    body.ocean = false;
+    let vessel = cube_at(&data, &body, -90.0, 0.0, false);
+    assert_eq!(
+        situation::situation(&vessel, &data, &body),
+        Situation::Landed
+    );
+    // Without an atmosphere there's only space above the ground
+    body.atmosphere = None;
+    let vessel = cube_at(&data, &body, -90.0, 100.0, false);
+    assert_eq!(
+        situation::situation(&vessel, &data, &body),
+        Situation::SpaceLow
+    );
+}
@@ -0,0 +1,197 @@
+mod common;
+
+use common::number;
+use serde_json::json;
+use space::{
+    game_data::GameData, motor::Transform, staging, vector3::Vector3, vessel::Vessel, Number,
+};
+
+fn setup() -> GameData {
+    let mut data = GameData::new();
+    common::resource(
+        &mut data,
+        json!({ "name": "LiquidFuel", "title": "", "density": 5 }),
+    );
+    common::part(
+        &mut data,
+        json!({ "name": "pod", "mass": 1000, "inertia": [100, 100, 100] }),
+    );
+    common::part(
+        &mut data,
+        json!({
+            "name": "engine",
+            "mass": 200,
+            "inertia": [100, 100, 100],
+            "engine": {
+                "max_thrust": 20000,
+                "isp": [[0, 300]],
+                "propellants": [{ "name": "LiquidFuel", "ratio": 1 }],
+            },
+        }),
+    );
+    common::part(
+        &mut data,
+        json!({
+            "name": "tank",
+            "mass": 100,
+            "inertia": [100, 100, 100],
+            "resources": [{ "name": "LiquidFuel", "capacity": 100, "amount": 100 }],
+        }),
+    );
+    for (name, separator) in [("decoupler", false), ("separator", true)] {
+        common::part(
+            &mut data,
+            json!({
+                "name": name,
+                "mass": 50,
+                "inertia": [10, 10, 10],
+                "crossfeed": false,
+                "decoupler": { "ejection_impulse": 600, "separator": separator },
+            }),
+        );
+    }
+    data
+}
+
+/// A pod with an engine, over `coupling`, a tank and another engine, stacked down along -Y and
+/// moving up at 100 m/s
+fn two_stages(data: &GameData, coupling: &str) -> Vessel {
+    let mut vessel = Vessel::new(
+        data.parts.id("pod").unwrap(),
+        Transform::translation(Vector3::X * number(50.0)),
+        data,
+    );
+    let mut parent = 0;
+    for (name, height) in [
+        ("engine", -1.0),
+        (coupling, -2.0),
+        ("tank", -3.0),
+        ("engine", -4.0),
+    ] {
+        parent = vessel.attach(
+            parent,
+            data.parts.id(name).unwrap(),
+            Transform::translation(Vector3::Y * number(height)),
+            data,
+        );
+    }
+    staging::assign_stages(&mut vessel, data);
+    vessel.velocity = Vector3::Y * number(100.0);
+    vessel.throttle = Number::ONE;
+    vessel
+}
+
+fn world_positions(vessel: &Vessel) -> Vec<Vector3> {
+    vessel
+        .parts
+        .iter()
+        .map(|part| part.transform.apply(vessel.transform).position())
+        .collect()
+}
+
+#[test]
+fn stages_fire_bottom_up() {
+    let data = setup();
+    let mut vessel = two_stages(&data, "decoupler");
+    let stages = vessel
+        .parts
+        .iter()
+        .map(|part| part.stage)
+        .collect::<Vec<_>>();
+    // The lower engine first, then the decoupler together with the engine above it
+    assert_eq!(stages, [None, Some(1), Some(1), None, Some(0)]);
+    assert_eq!(staging::remaining_stages(&vessel), 2);
+
+    assert!(staging::activate_next_stage(&mut vessel, &data).is_empty());
+    let activated = vessel
+        .parts
+        .iter()
+        .map(|part| part.activated)
+        .collect::<Vec<_>>();
+    assert_eq!(activated, [false, false, false, false, true]);
+    assert_eq!(staging::remaining_stages(&vessel), 1);
+}
+
+#[test]
+fn decouplers_split_off_a_vessel_of_their_own() {
+    let data = setup();
+    let mut vessel = two_stages(&data, "decoupler");
+    let mass = vessel.mass(&data);
+    let positions = world_positions(&vessel);
+    staging::activate_next_stage(&mut vessel, &data);
+
+    let pieces = staging::activate_next_stage(&mut vessel, &data);
+    assert_eq!(pieces.len(), 1);
+    let piece = &pieces[0];
+    assert_eq!(staging::remaining_stages(&vessel), 0);
+
+    // The decoupler stays with the pod, and every part stays where it was
+    let names = |vessel: &Vessel| {
+        vessel
+            .parts
+            .iter()
+            .map(|part| data.parts[part.part].config.name.as_str())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(&vessel), ["pod", "engine", "decoupler"]);
+    assert_eq!(names(piece), ["tank", "engine"]);
+    assert_eq!(piece.parts[0].parent, None);
+    assert_eq!(piece.parts[1].parent, Some(0));
+    let split = world_positions(&vessel)
+        .into_iter()
+        .chain(world_positions(piece))
+        .collect::<Vec<_>>();
+    for (before, after) in positions.iter().zip(&split) {
+        assert!(common::close(*before, *after, 1e-6), "{before:?} {after:?}");
+    }
+    assert_eq!(vessel.mass(&data) + piece.mass(&data), mass);
+
+    // Pushed apart along the stack without changing the total momentum
+    let (upper, lower) = (vessel.mass(&data), piece.mass(&data));
+    let momentum = vessel.velocity * upper + piece.velocity * lower;
+    assert!(common::close(
+        momentum,
+        Vector3::Y * (number(100.0) * mass),
+        1e-3
+    ));
+    let relative = vessel.velocity - piece.velocity;
+    assert!(common::close(
+        relative,
+        Vector3::Y * (number(600.0) / upper + number(600.0) / lower),
+        1e-6
+    ));
+    assert_eq!(piece.angular_velocity, Vector3::ZERO);
+
+    // Only the vessel being flown keeps its throttle
+    assert_eq!(vessel.throttle, Number::ONE);
+    assert_eq!(piece.throttle, Number::ZERO);
+}
+
+#[test]
+fn separators_let_go_of_both_sides() {
+    let data = setup();
+    let mut vessel = two_stages(&data, "separator");
+    let mass = vessel.mass(&data);
+    staging::activate_next_stage(&mut vessel, &data);
+
+    let pieces = staging::activate_next_stage(&mut vessel, &data);
+    assert_eq!(pieces.len(), 2);
+    assert_eq!(vessel.parts.len(), 2);
+    let total = pieces
+        .iter()
+        .fold(vessel.mass(&data), |total, piece| total + piece.mass(&data));
+    assert_eq!(total, mass);
+    let momentum = pieces
+        .iter()
+        .fold(vessel.velocity * vessel.mass(&data), |total, piece| {
+            total + piece.velocity * piece.mass(&data)
+        });
+    assert!(common::close(
+        momentum,
+        Vector3::Y * (number(100.0) * mass),
+        1e-3
+    ));
+    // The pod ends up faster than it was going, and the stage below slower
+    assert!(vessel.velocity.y > number(100.0));
+    assert!(pieces.iter().all(|piece| piece.throttle == Number::ZERO));
+}
@@ -0,0 +1,151 @@
+mod common;
+
+use common::{number, radians};
+use space::{
+    body,
+    game_data::GameData,
+    geodetic::Ellipsoid,
+    terrain::{Heightmap, Noise, Terrain},
+    vector3::Vector3,
+    Number,
+};
+use std::path::{Path, PathBuf};
+
+/// A fresh directory with a body config and a 4 by 2 heightmap next to it
+fn heightmap_body(name: &str, pixels: [u16; 8]) -> PathBuf {
+    let directory = common::game_data_dir(
+        name,
+        &[(
+            "planet.json",
+            r#"{
+            "type": "body",
+            "name": "planet",
+            "title": "Planet",
+            "radius": 100000,
+            "mu": 1e11,
+            "terrain": {
+                "type": "heightmap",
+                "image": "height.png",
+                "min_height": -1000,
+                "max_height": 3000
+            }
+        }"#,
+        )],
+    );
+    image::ImageBuffer::<image::Luma<u16>, _>::from_raw(4, 2, pixels.to_vec())
+        .unwrap()
+        .save(directory.join("height.png"))
+        .unwrap();
+    directory
+}
+
+fn noise(seed: u32) -> Noise {
+    Noise {
+        seed,
+        amplitude: number(1000.0),
+        wavelength: number(50_000.0),
+        octaves: 5,
+        persistence: number(0.5),
+        radius: number(200_000.0),
+    }
+}
+
+#[test]
+fn heightmaps_interpolate_between_pixel_centers() {
+    let directory = heightmap_body("heightmap", [0, 65535, 0, 0, 0, 0, 0, 16384]);
+    let data = GameData::load(&directory);
+    let bodies = body::propagate(&data.bodies, Number::ZERO);
+    let body = &bodies[0];
+    let height = |latitude: f64, longitude: f64| {
+        body.terrain_height(radians(latitude), radians(longitude))
+            .to_num::<f64>()
+    };
+    let close = |a: f64, b: f64| (a - b).abs() < 0.1;
+
+    // Pixel centers are 90° apart, starting at 135° west and 45° north
+    assert!(close(height(45.0, -135.0), -1000.0));
+    assert!(close(height(45.0, -45.0), 3000.0));
+    assert!(close(height(45.0, -90.0), 1000.0));
+    assert!(close(height(0.0, -45.0), 1000.0));
+    // Past the last pixel center the map wraps around the antimeridian
+    let corner = -1000.0 + 4000.0 * 16384.0 / 65535.0;
+    assert!(close(height(-45.0, 135.0), corner));
+    assert!(close(height(-45.0, 180.0), (corner - 1000.0) / 2.0));
+    assert!(close(height(-45.0, -180.0), (corner - 1000.0) / 2.0));
+    // And it stays flat towards the poles
+    assert!(close(height(90.0, -45.0), 3000.0));
+    assert!(close(height(-89.0, 135.0), corner));
+}
+
+#[test]
+fn noise_is_smooth_seeded_and_seamless() {
+    let terrain = Terrain::Noise(noise(1));
+    let heights = (0..200)
+        .map(|index| {
+            let index = index as f64;
+            terrain.height_at(radians(index * 0.9 - 90.0), radians(index * 7.3))
+        })
+        .collect::<Vec<_>>();
+    assert!(heights.iter().all(|height| height.abs() <= number(1000.0)));
+    let (min, max) = (heights.iter().min().unwrap(), heights.iter().max().unwrap());
+    assert!(*max - *min > number(500.0), "{min}..{max}");
+
+    // The same seed always gives the same terrain, others different terrain
+    let at = (radians(12.0), radians(34.0));
+    assert_eq!(
+        terrain.height_at(at.0, at.1),
+        Terrain::Noise(noise(1)).height_at(at.0, at.1)
+    );
+    assert_ne!(
+        terrain.height_at(at.0, at.1),
+        Terrain::Noise(noise(2)).height_at(at.0, at.1)
+    );
+
+    // Neighbouring points are at nearly the same height, including across the antimeridian
+    // and over the poles
+    for (a, b) in [
+        ((10.0, 20.0), (10.0, 20.001)),
+        ((10.0, 180.0), (10.0, -179.999)),
+        ((90.0, 0.0), (90.0, 120.0)),
+    ] {
+        let a = terrain.height_at(radians(a.0), radians(a.1));
+        let b = terrain.height_at(radians(b.0), radians(b.1));
+        assert!((a - b).abs() < number(1.0), "{a} and {b}");
+    }
+}
+
+#[test]
+fn normals_lean_away_from_slopes() {
+    let sphere = Ellipsoid::sphere(number(100_000.0));
+
+    // Flat ground points straight up, even at the poles
+    let flat = Terrain::Heightmap(Heightmap::new(1, 1, vec![number(500.0)]));
+    for (latitude, longitude) in [(0.0, 0.0), (40.0, -70.0), (90.0, 0.0)] {
+        let (latitude, longitude) = (radians(latitude), radians(longitude));
+        let normal = flat.normal_at(&sphere, latitude, longitude);
+        let up = sphere.up(latitude, longitude);
+        assert!((normal - up).length() < number(1e-4), "{normal:?}");
+    }
+
+    // Rising 100 meters every degree eastwards, passing sea level at the prime meridian
+    let columns = 360;
+    let heights = (0..columns)
+        .map(|column| number(100.0 * (column as f64 - 179.5)))
+        .collect();
+    let slope = Terrain::Heightmap(Heightmap::new(columns, 1, heights));
+    let normal = slope.normal_at(&sphere, Number::ZERO, Number::ZERO);
+    let east = Vector3::Y;
+    let expected = (100.0 / (100_000.0 * 1f64.to_radians())).atan();
+    let lean = -normal.dot(east).to_num::<f64>();
+    assert!((lean - expected.sin()).abs() < 1e-3, "leaning {lean}");
+    assert!(normal.dot(Vector3::Z).abs() < number(1e-4));
+}
+
+#[test]
+fn the_mun_has_terrain() {
+    let data = GameData::load(Path::new("GameData"));
+    let mun = &data.bodies[data.bodies.id("mun").unwrap()];
+    let terrain = mun.terrain.as_ref().unwrap();
+    let height = terrain.height_at(radians(10.0), radians(20.0));
+    assert!(height != Number::ZERO && height.abs() < number(4000.0));
+}
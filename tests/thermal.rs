@@ -0,0 +1,313 @@
+mod common;
+
+use common::number;
+use serde_json::json;
+use space::{
+    body, engine::EngineOutput, game_data::GameData, motor::Transform, physics::Environment,
+    thermal, vector3::Vector3, vessel::Vessel, Number,
+};
+use std::path::Path;
+
+/// Two 2 meter cubes of 1 kilogram, a plate giving off heat and an insulated one that doesn't
+fn setup() -> GameData {
+    let mut data = GameData::new();
+    for (name, emissivity) in [("plate", 0.4), ("insulated", 0.0)] {
+        common::part_with_model(
+            &mut data,
+            json!({
+                "name": name,
+                "mass": 1,
+                "thermal": { "emissivity": emissivity, "conductance": 10 },
+            }),
+            common::cube_model(),
+        );
+    }
+    data
+}
+
+/// About what a capsule coming back from orbit meets 30 kilometers above Kerbin
+fn reentry() -> Environment {
+    Environment {
+        density: number(0.0057),
+        air_temperature: number(288.0),
+        ..Environment::VACUUM
+    }
+}
+
+/// A Mk-1 cockpit falling at orbital speed, with a heat shield underneath holding `ablator`
+/// units when given
+fn capsule(data: &GameData, ablator: Option<f64>) -> Vessel {
+    let mut vessel = Vessel::new(
+        data.parts.id("mk1-cockpit").unwrap(),
+        Transform::IDENTITY,
+        data,
+    );
+    if let Some(ablator) = ablator {
+        let shield = vessel.attach(
+            0,
+            data.parts.id("mk1-heat-shield").unwrap(),
+            Transform::translation(Vector3::Y * number(-1.1)),
+            data,
+        );
+        vessel.parts[shield].resources[0].amount = number(ablator);
+    }
+    vessel.velocity = Vector3::Y * number(-2300.0);
+    vessel
+}
+
+/// Runs `vessel` through `environment` for `seconds`, returning the names of the parts that
+/// burned up
+fn fly(
+    vessel: &mut Vessel,
+    data: &GameData,
+    environment: &Environment,
+    seconds: u32,
+) -> Vec<String> {
+    let dt = number(0.1);
+    let mut burned_up = Vec::new();
+    for _ in 0..seconds * 10 {
+        let before = vessel
+            .parts
+            .iter()
+            .map(|part| part.part)
+            .collect::<Vec<_>>();
+        thermal::heat(vessel, data, &[], environment, dt);
+        let pieces = thermal::destroy_overheated(vessel, data);
+        let after = vessel
+            .parts
+            .iter()
+            .chain(pieces.iter().flat_map(|piece| &piece.parts))
+            .map(|part| part.part)
+            .collect::<Vec<_>>();
+        burned_up.extend(
+            before
+                .into_iter()
+                .filter(|part| !after.contains(part))
+                .map(|part| data.parts[part].config.name.clone()),
+        );
+        if let Some(piece) = pieces.into_iter().next() {
+            *vessel = piece;
+        }
+        if vessel.parts.is_empty() {
+            break;
+        }
+    }
+    burned_up
+}
+
+#[test]
+fn reentry_burns_up_a_bare_capsule() {
+    let data = GameData::load(Path::new("GameData"));
+    let mut vessel = capsule(&data, None);
+    assert_eq!(
+        fly(&mut vessel, &data, &reentry(), 60),
+        ["mk1-cockpit".to_string()]
+    );
+
+    // Coming in slower stays well short of it
+    let mut vessel = capsule(&data, None);
+    vessel.velocity = Vector3::Y * number(-800.0);
+    assert!(fly(&mut vessel, &data, &reentry(), 60).is_empty());
+    let temperature = vessel.parts[0].temperature;
+    assert!(temperature < number(600.0), "{temperature}");
+}
+
+#[test]
+fn resting_on_the_launch_pad_stays_at_air_temperature() {
+    let data = GameData::load(Path::new("GameData"));
+    let site = &data.launch_sites[data.launch_sites.id("launch-pad").unwrap()];
+    let body = &body::propagate(&data.bodies, Number::ZERO)[site.body.index()];
+    let mut vessel = capsule(&data, Some(200.0));
+    site.spawn(&mut vessel, body, &data);
+    let environment = body.environment(vessel.transform.position());
+
+    // Carried along at thousands of meters per second, but still in the air turning with Kerbin
+    assert!(fly(&mut vessel, &data, &environment, 1).is_empty());
+    for part in &vessel.parts {
+        let temperature = part.temperature;
+        assert!(
+            (temperature - thermal::ROOM_TEMPERATURE).abs() < number(1.0),
+            "{temperature}"
+        );
+    }
+}
+
+#[test]
+fn heat_shields_protect_what_is_behind_them() {
+    let data = GameData::load(Path::new("GameData"));
+    let ablator = data.resources.id("Ablator").unwrap();
+    let mut vessel = capsule(&data, Some(200.0));
+    assert!(fly(&mut vessel, &data, &reentry(), 60).is_empty());
+
+    let cockpit = vessel.parts[0].temperature;
+    assert!(cockpit < number(400.0), "{cockpit}");
+    // Held at the temperature the ablator chars at, by burning it away
+    let shield = &vessel.parts[1];
+    assert!((shield.temperature - number(700.0)).abs() < number(1.0));
+    let left = shield.resource(ablator).unwrap().amount;
+    assert!(left > Number::ZERO && left < number(100.0), "{left}");
+}
+
+#[test]
+fn spent_heat_shields_burn_up() {
+    let data = GameData::load(Path::new("GameData"));
+    let mut vessel = capsule(&data, Some(0.0));
+    assert_eq!(
+        fly(&mut vessel, &data, &reentry(), 120),
+        ["mk1-heat-shield".to_string(), "mk1-cockpit".to_string()]
+    );
+}
+
+#[test]
+fn sunlight_and_radiation_settle_in_between() {
+    let data = setup();
+    let sunlit = Environment {
+        sun_direction: Vector3::Z,
+        solar_flux: number(1360.0),
+        ..Environment::VACUUM
+    };
+    for start in [0.0, 1000.0] {
+        let mut vessel = Vessel::new(data.parts.id("plate").unwrap(), Transform::IDENTITY, &data);
+        vessel.parts[0].temperature = number(start);
+        for _ in 0..2000 {
+            thermal::heat(&mut vessel, &data, &[], &sunlit, Number::ONE);
+        }
+        // Taking in sunlight over a quarter of its skin, and giving off heat over all of it
+        let temperature = vessel.parts[0].temperature;
+        assert!(
+            (temperature - number(278.6)).abs() < number(1.0),
+            "{temperature}"
+        );
+    }
+
+    // Without the sun it only cools down
+    let mut vessel = Vessel::new(data.parts.id("plate").unwrap(), Transform::IDENTITY, &data);
+    for _ in 0..100 {
+        let before = vessel.parts[0].temperature;
+        thermal::heat(&mut vessel, &data, &[], &Environment::VACUUM, Number::ONE);
+        assert!(vessel.parts[0].temperature < before);
+    }
+}
+
+#[test]
+fn heat_conducts_between_attached_parts() {
+    let data = setup();
+    let insulated = data.parts.id("insulated").unwrap();
+    let mut vessel = Vessel::new(insulated, Transform::IDENTITY, &data);
+    vessel.attach(
+        0,
+        insulated,
+        Transform::translation(Vector3::Y * number(2.0)),
+        &data,
+    );
+    vessel.parts[0].temperature = number(1000.0);
+    vessel.parts[1].temperature = number(200.0);
+
+    thermal::heat(&mut vessel, &data, &[], &Environment::VACUUM, Number::ONE);
+    // 10 watts for every kelvin of difference, into 800 joules per kelvin
+    let [hot, cold] = [0, 1].map(|index| vessel.parts[index].temperature);
+    assert!((hot - number(990.0)).abs() < number(0.01), "{hot}");
+    assert!((cold - number(210.0)).abs() < number(0.01), "{cold}");
+
+    // A long step evens them out without overshooting
+    thermal::heat(
+        &mut vessel,
+        &data,
+        &[],
+        &Environment::VACUUM,
+        number(1000.0),
+    );
+    let [hot, cold] = [0, 1].map(|index| vessel.parts[index].temperature);
+    assert!((hot - number(600.0)).abs() < number(0.01), "{hot}");
+    assert!((cold - number(600.0)).abs() < number(0.01), "{cold}");
+}
+
+#[test]
+fn engines_heat_up_while_running() {
+    let data = GameData::load(Path::new("GameData"));
+    let engine = data.parts.id("mk1-lv-60").unwrap();
+    let definition = data.parts[engine].engine.as_ref().unwrap();
+    let mut vessel = Vessel::new(engine, Transform::IDENTITY, &data);
+    let running = |thrust: Number| EngineOutput {
+        part: 0,
+        thrust,
+        direction: Vector3::Y,
+        flameout: false,
+    };
+
+    let mut temperatures = Vec::new();
+    for thrust in [
+        definition.max_thrust,
+        definition.max_thrust / 2,
+        Number::ZERO,
+    ] {
+        let before = vessel.parts[0].temperature;
+        thermal::heat(
+            &mut vessel,
+            &data,
+            &[running(thrust)],
+            &Environment::VACUUM,
+            Number::ONE,
+        );
+        temperatures.push(vessel.parts[0].temperature - before);
+    }
+    // Half the thrust makes half the heat, and radiating away a little is all that's left
+    // without any
+    assert!(temperatures[0] > Number::ZERO);
+    let radiated = temperatures[2];
+    assert!(radiated < Number::ZERO);
+    assert!(((temperatures[1] - radiated) * 2 - (temperatures[0] - radiated)).abs() < number(0.01));
+}
+
+#[test]
+fn engines_without_thrust_make_no_heat() {
+    let mut data = GameData::load(Path::new("GameData"));
+    let dud = common::part(
+        &mut data,
+        json!({
+            "name": "dud-engine",
+            "mass": 100,
+            "engine": {
+                "max_thrust": 0,
+                "isp": [[0, 320]],
+                "propellants": [{ "name": "LiquidFuel", "ratio": 1 }],
+                "heat": 200000,
+            },
+        }),
+    );
+    let mut vessel = Vessel::new(dud, Transform::IDENTITY, &data);
+    let idle = EngineOutput {
+        part: 0,
+        thrust: Number::ZERO,
+        direction: Vector3::Y,
+        flameout: false,
+    };
+
+    thermal::heat(
+        &mut vessel,
+        &data,
+        &[idle],
+        &Environment::VACUUM,
+        Number::ONE,
+    );
+    let temperature = vessel.parts[0].temperature;
+    assert!(temperature <= thermal::ROOM_TEMPERATURE, "{temperature}");
+}
+
+#[test]
+#[should_panic(expected = "shield needs its ablator to carry off some heat")]
+fn ablators_have_to_carry_off_heat() {
+    let mut data = GameData::new();
+    common::resource(
+        &mut data,
+        json!({ "name": "Ablator", "title": "", "density": 1 }),
+    );
+    common::part(
+        &mut data,
+        json!({
+            "name": "shield",
+            "mass": 1,
+            "ablator": { "resource": "Ablator", "temperature": 700, "heat": 0 },
+        }),
+    );
+}